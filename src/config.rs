@@ -1,7 +1,7 @@
 use crate::authentication::CredentialsStore;
 use crate::providers::units::Coordinates;
 use crate::providers::HttpRequestCache;
-use crate::providers::{Providers, WeatherProvider, WeatherRequest};
+use crate::providers::{ProviderParameters, Providers, WeatherProvider, WeatherRequest};
 use anyhow::{anyhow, Context};
 use const_format::concatcp;
 use figment::{
@@ -24,6 +24,9 @@ use std::time::Duration;
 
 pub const NAME: &str = env!("CARGO_PKG_NAME");
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+// Some providers require a User-Agent that identifies the application
+pub const USER_AGENT: &str =
+    concatcp!(NAME, "/", VERSION, " (+", env!("CARGO_PKG_REPOSITORY"), ")");
 pub const DEFAULT_CONFIG: &str = concatcp!("/etc/", NAME, "/weathermen.toml");
 const DEFAULT_PORT: u16 = 36333;
 
@@ -32,6 +35,9 @@ pub struct Location {
     pub name: Option<String>,
     #[serde(flatten)]
     pub coordinates: Coordinates,
+    // Keyed by the name the provider is configured with
+    #[serde(default, rename = "provider")]
+    pub providers: BTreeMap<String, ProviderParameters>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -103,7 +109,7 @@ pub fn get_provider_tasks(config: Config) -> anyhow::Result<ProviderTasks> {
 
     let mut tasks: ProviderTasks = vec![];
 
-    for configured_provider in configured_providers {
+    for (provider_name, configured_provider) in configured_providers {
         let max_capacity = config
             .locations
             .len()
@@ -114,6 +120,7 @@ pub fn get_provider_tasks(config: Config) -> anyhow::Result<ProviderTasks> {
             .build();
 
         debug!("Found configured provider {configured_provider:?}");
+        configured_provider.start()?;
 
         if configured_provider.refresh_interval() < Duration::from_secs(60 * 5) {
             warn!(
@@ -123,14 +130,15 @@ pub fn get_provider_tasks(config: Config) -> anyhow::Result<ProviderTasks> {
         }
 
         let locations = config.locations.clone();
-        for (name, location) in locations {
+        for (name, mut location) in locations {
             tasks.push(Task {
                 provider: Arc::clone(&configured_provider),
                 request: WeatherRequest {
                     name: location.name.unwrap_or(name),
                     query: location.coordinates,
+                    parameters: location.providers.remove(provider_name).unwrap_or_default(),
                 },
-                client: Client::new(),
+                client: Client::builder().user_agent(USER_AGENT).build()?,
                 cache: cache.clone(),
             });
         }
@@ -0,0 +1,276 @@
+use crate::providers::Weather;
+
+// Magnus formula coefficients for water above -45 °C (Sonntag 1990)
+const MAGNUS_A: f64 = 17.62;
+const MAGNUS_B_CELSIUS: f64 = 243.12;
+const MAGNUS_SATURATION_VAPOR_PRESSURE_HECTOPASCAL: f64 = 6.112;
+
+const KELVIN_ZERO_CELSIUS: f64 = 273.15;
+// Pa to hPa (100) and kg to g (1000) divided by the specific gas constant of water vapor (461.5 J/(kg*K))
+const ABSOLUTE_HUMIDITY_FACTOR: f64 = 100.0 * 1000.0 / 461.5;
+
+// Heat index is only defined for warm conditions, see https://www.wpc.ncep.noaa.gov/html/heatindex_equation.shtml
+const HEAT_INDEX_MIN_FAHRENHEIT: f64 = 80.0;
+
+// Wind chill is only defined for cold and windy conditions, see https://en.wikipedia.org/wiki/Wind_chill
+const WIND_CHILL_MAX_CELSIUS: f64 = 10.0;
+const WIND_CHILL_MIN_KILOMETERS_PER_HOUR: f64 = 4.8;
+
+const KILOMETERS_PER_HOUR_PER_METER_PER_SECOND: f64 = 3.6;
+
+// Derived values are rounded as the input data does not allow for more precision
+fn round(value: f64) -> f64 {
+    (value * 100.0_f64).round() / 100.0_f64
+}
+
+pub fn dew_point(weather: &Weather) -> Option<f64> {
+    weather.dew_point.map(f64::from).or_else(|| {
+        weather
+            .relative_humidity
+            .and_then(|rh| calculate_dew_point(weather.temperature.into(), rh.into()))
+            .map(round)
+    })
+}
+
+pub fn apparent_temperature(weather: &Weather) -> Option<f64> {
+    weather.apparent_temperature.map(f64::from).or_else(|| {
+        weather
+            .relative_humidity
+            .zip(weather.wind_speed)
+            .map(|(rh, wind_speed)| {
+                calculate_apparent_temperature(
+                    weather.temperature.into(),
+                    rh.into(),
+                    wind_speed.into(),
+                )
+            })
+            .map(round)
+    })
+}
+
+pub fn heat_index(weather: &Weather) -> Option<f64> {
+    weather
+        .relative_humidity
+        .and_then(|rh| calculate_heat_index(weather.temperature.into(), rh.into()))
+        .map(round)
+}
+
+pub fn wind_chill(weather: &Weather) -> Option<f64> {
+    weather
+        .wind_speed
+        .and_then(|wind_speed| calculate_wind_chill(weather.temperature.into(), wind_speed.into()))
+        .map(round)
+}
+
+pub fn absolute_humidity(weather: &Weather) -> Option<f64> {
+    weather
+        .relative_humidity
+        .map(|rh| calculate_absolute_humidity(weather.temperature.into(), rh.into()))
+        .map(round)
+}
+
+fn saturation_vapor_pressure(temperature: f64) -> f64 {
+    MAGNUS_SATURATION_VAPOR_PRESSURE_HECTOPASCAL
+        * (MAGNUS_A * temperature / (MAGNUS_B_CELSIUS + temperature)).exp()
+}
+
+fn vapor_pressure(temperature: f64, relative_humidity: f64) -> f64 {
+    relative_humidity * saturation_vapor_pressure(temperature)
+}
+
+// Inverse of the dew point calculation for sources that only report temperature and dew point
+pub fn relative_humidity_from_dew_point(temperature: f64, dew_point: f64) -> f64 {
+    saturation_vapor_pressure(dew_point) / saturation_vapor_pressure(temperature)
+}
+
+fn calculate_dew_point(temperature: f64, relative_humidity: f64) -> Option<f64> {
+    if relative_humidity <= 0.0_f64 {
+        return None;
+    }
+
+    let gamma = relative_humidity.ln() + MAGNUS_A * temperature / (MAGNUS_B_CELSIUS + temperature);
+
+    Some(MAGNUS_B_CELSIUS * gamma / (MAGNUS_A - gamma))
+}
+
+fn calculate_absolute_humidity(temperature: f64, relative_humidity: f64) -> f64 {
+    vapor_pressure(temperature, relative_humidity) * ABSOLUTE_HUMIDITY_FACTOR
+        / (temperature + KELVIN_ZERO_CELSIUS)
+}
+
+// Non-radiative apparent temperature as used by the Australian Bureau of Meteorology
+// See http://www.bom.gov.au/info/thermal_stress/#atapproximation
+fn calculate_apparent_temperature(
+    temperature: f64,
+    relative_humidity: f64,
+    wind_speed: f64,
+) -> f64 {
+    0.33_f64.mul_add(
+        vapor_pressure(temperature, relative_humidity),
+        0.70_f64.mul_add(-wind_speed, temperature),
+    ) - 4.00_f64
+}
+
+// Coefficient, exponent of temperature, exponent of relative humidity
+const ROTHFUSZ_REGRESSION: [(f64, i32, i32); 9] = [
+    (-42.379, 0, 0),
+    (2.049_015_23, 1, 0),
+    (10.143_331_27, 0, 1),
+    (-0.224_755_41, 1, 1),
+    (-0.006_837_83, 2, 0),
+    (-0.054_817_17, 0, 2),
+    (0.001_228_74, 2, 1),
+    (0.000_852_82, 1, 2),
+    (-0.000_001_99, 2, 2),
+];
+
+// NWS heat index: Steadman's simple formula with the Rothfusz regression for higher values
+fn calculate_heat_index(temperature: f64, relative_humidity: f64) -> Option<f64> {
+    let t = temperature.mul_add(1.8_f64, 32.0_f64);
+    let rh = relative_humidity * 100.0_f64;
+
+    if t < HEAT_INDEX_MIN_FAHRENHEIT {
+        return None;
+    }
+
+    let simple = 0.5_f64 * rh.mul_add(0.094_f64, (t - 68.0_f64).mul_add(1.2_f64, t + 61.0_f64));
+
+    let heat_index = if (simple + t) / 2.0_f64 < HEAT_INDEX_MIN_FAHRENHEIT {
+        simple
+    } else {
+        let regression: f64 = ROTHFUSZ_REGRESSION
+            .iter()
+            .map(|&(coefficient, t_exponent, rh_exponent)| {
+                coefficient * t.powi(t_exponent) * rh.powi(rh_exponent)
+            })
+            .sum();
+
+        if rh < 13.0_f64 && t <= 112.0_f64 {
+            ((13.0_f64 - rh) / 4.0_f64).mul_add(
+                -((17.0_f64 - (t - 95.0_f64).abs()) / 17.0_f64).sqrt(),
+                regression,
+            )
+        } else if rh > 85.0_f64 && t <= 87.0_f64 {
+            ((rh - 85.0_f64) / 10.0_f64).mul_add((87.0_f64 - t) / 5.0_f64, regression)
+        } else {
+            regression
+        }
+    };
+
+    Some((heat_index - 32.0_f64) / 1.8_f64)
+}
+
+// North American and UK wind chill index
+fn calculate_wind_chill(temperature: f64, wind_speed: f64) -> Option<f64> {
+    let wind_speed_kilometers_per_hour = wind_speed * KILOMETERS_PER_HOUR_PER_METER_PER_SECOND;
+
+    if temperature > WIND_CHILL_MAX_CELSIUS
+        || wind_speed_kilometers_per_hour < WIND_CHILL_MIN_KILOMETERS_PER_HOUR
+    {
+        return None;
+    }
+
+    let wind_factor = wind_speed_kilometers_per_hour.powf(0.16_f64);
+
+    Some(wind_factor.mul_add(
+        0.3965_f64.mul_add(temperature, -11.37_f64),
+        0.6215_f64.mul_add(temperature, 13.12_f64),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::derived::{
+        calculate_absolute_humidity, calculate_apparent_temperature, calculate_dew_point,
+        calculate_heat_index, calculate_wind_chill, relative_humidity_from_dew_point,
+    };
+
+    fn assert_approx(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 0.05_f64,
+            "Expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn dew_point() {
+        assert_approx(
+            calculate_dew_point(20.0_f64, 0.5_f64).expect("Dew point is defined"),
+            9.26_f64,
+        );
+        assert_approx(
+            calculate_dew_point(5.1_f64, 0.826_f64).expect("Dew point is defined"),
+            2.38_f64,
+        );
+        assert_approx(
+            calculate_dew_point(25.0_f64, 1.0_f64).expect("Dew point is defined"),
+            25.0_f64,
+        );
+    }
+
+    #[test]
+    fn dew_point_undefined_without_humidity() {
+        assert_eq!(calculate_dew_point(20.0_f64, 0.0_f64), None);
+    }
+
+    #[test]
+    fn relative_humidity_from_dew_point_is_inverse() {
+        assert_approx(
+            relative_humidity_from_dew_point(20.0_f64, 9.26_f64),
+            0.5_f64,
+        );
+        assert_approx(
+            relative_humidity_from_dew_point(25.0_f64, 25.0_f64),
+            1.0_f64,
+        );
+    }
+
+    #[test]
+    fn absolute_humidity() {
+        assert_approx(calculate_absolute_humidity(20.0_f64, 0.5_f64), 8.62_f64);
+        assert_approx(calculate_absolute_humidity(0.0_f64, 1.0_f64), 4.85_f64);
+    }
+
+    #[test]
+    fn apparent_temperature() {
+        assert_approx(
+            calculate_apparent_temperature(30.0_f64, 0.6_f64, 2.0_f64),
+            32.98_f64,
+        );
+        assert_approx(
+            calculate_apparent_temperature(5.0_f64, 0.8_f64, 5.0_f64),
+            -0.20_f64,
+        );
+    }
+
+    #[test]
+    fn heat_index() {
+        assert_approx(
+            calculate_heat_index(32.0_f64, 0.7_f64).expect("Heat index is defined"),
+            40.41_f64,
+        );
+        assert_approx(
+            calculate_heat_index(27.0_f64, 0.4_f64).expect("Heat index is defined"),
+            26.86_f64,
+        );
+    }
+
+    #[test]
+    fn heat_index_undefined_if_cold() {
+        assert_eq!(calculate_heat_index(20.0_f64, 0.7_f64), None);
+    }
+
+    #[test]
+    fn wind_chill() {
+        assert_approx(
+            calculate_wind_chill(-10.0_f64, 20.0_f64 / 3.6_f64).expect("Wind chill is defined"),
+            -17.86_f64,
+        );
+    }
+
+    #[test]
+    fn wind_chill_undefined_if_warm_or_calm() {
+        assert_eq!(calculate_wind_chill(15.0_f64, 10.0_f64), None);
+        assert_eq!(calculate_wind_chill(-5.0_f64, 1.0_f64), None);
+    }
+}
@@ -1,10 +1,13 @@
 use crate::config::NAME;
-use log::{error, info, trace};
+use chrono::Utc;
+use log::{error, info, trace, warn};
 use once_cell::sync::Lazy;
+use rocket::form::Form;
 use rocket::http::{Accept, ContentType, Header, MediaType, QMediaType, Status};
-use rocket::{get, routes, Build, Either, Responder, Rocket, State};
+use rocket::{get, post, routes, Build, Either, Responder, Rocket, State};
 use rocket_basicauth::BasicAuth;
 use std::cmp::Ordering;
+use std::collections::HashMap;
 
 use crate::config::ProviderTasks;
 use crate::config::{get_provider_tasks, Config};
@@ -12,6 +15,8 @@ use crate::config::{get_provider_tasks, Config};
 use crate::authentication::{maybe_authenticate, CredentialsStore, Denied};
 use crate::error::exit_if_handle_fatal;
 use crate::prometheus::{format_metrics, Format};
+use crate::providers::ecowitt::{self, Ecowitt};
+use crate::providers::wunderground_upload::{self, WundergroundUpload};
 use crate::providers::Weather;
 use rocket::tokio::task;
 use rocket::tokio::task::JoinSet;
@@ -23,12 +28,35 @@ pub async fn configure_rocket(config: Config) -> Rocket<Build> {
         .await
         .unwrap_or_else(exit_if_handle_fatal)
         .unwrap_or_else(exit_if_handle_fatal);
+    let ecowitt = config
+        .providers
+        .as_ref()
+        .and_then(|providers| providers.ecowitt().cloned());
+    let wunderground_upload = config
+        .providers
+        .as_ref()
+        .and_then(|providers| providers.wunderground_upload().cloned());
 
     #[allow(clippy::no_effect_underscore_binding)]
-    rocket::custom(config.http)
+    let server = rocket::custom(config.http)
         .manage(tasks)
         .manage(config.auth)
-        .mount("/", routes![index, metrics])
+        .mount("/", routes![index, metrics]);
+
+    // Consoles cannot authenticate, so the endpoint only exists when the provider is configured
+    let with_ecowitt = match ecowitt {
+        Some(provider) => server
+            .manage(provider)
+            .mount("/", routes![ingest_ecowitt_form, ingest_ecowitt_query]),
+        None => server,
+    };
+
+    match wunderground_upload {
+        Some(provider) => with_ecowitt
+            .manage(provider)
+            .mount("/", routes![ingest_wunderground_upload]),
+        None => with_ecowitt,
+    }
 }
 
 #[get("/")]
@@ -61,6 +89,57 @@ async fn metrics(
     }
 }
 
+// Ecowitt consoles post form data, Ambient Weather consoles send a query string
+#[post("/ingest/ecowitt", data = "<report>")]
+#[allow(clippy::needless_pass_by_value)]
+fn ingest_ecowitt_form(provider: &State<Ecowitt>, report: Form<HashMap<String, String>>) -> Status {
+    ingest_ecowitt(provider, &report)
+}
+
+#[get("/ingest/ecowitt?<report..>")]
+#[allow(clippy::needless_pass_by_value)]
+fn ingest_ecowitt_query(provider: &State<Ecowitt>, report: HashMap<String, String>) -> Status {
+    ingest_ecowitt(provider, &report)
+}
+
+fn ingest_ecowitt(provider: &Ecowitt, report: &HashMap<String, String>) -> Status {
+    if provider.authenticate(report).is_err() {
+        warn!("Discarding Ecowitt report of an unknown station");
+        return Status::Forbidden;
+    }
+
+    ecowitt::receive(report, Utc::now()).map_or_else(
+        |e| {
+            warn!("Discarding Ecowitt report: {e}");
+            Status::BadRequest
+        },
+        |()| Status::Ok,
+    )
+}
+
+// Stations speaking the Weather Underground protocol expect the path and responses of its API
+#[get("/weatherstation/updateweatherstation.php?<report..>")]
+#[allow(clippy::needless_pass_by_value)]
+fn ingest_wunderground_upload(
+    provider: &State<WundergroundUpload>,
+    report: HashMap<String, String>,
+) -> (Status, &'static str) {
+    if provider.authenticate(&report).is_err() {
+        return (
+            Status::Unauthorized,
+            "INVALID PASSWORDID|Password or key and/or id are incorrect",
+        );
+    }
+
+    wunderground_upload::receive(&report, Utc::now()).map_or_else(
+        |e| {
+            warn!("Discarding station upload: {e}");
+            (Status::BadRequest, "INVALID")
+        },
+        |()| (Status::Ok, "success"),
+    )
+}
+
 async fn serve_metrics(
     format: Format,
     unscheduled_tasks: &State<ProviderTasks>,
@@ -76,7 +155,7 @@ async fn serve_metrics(
                 task.request.query,
             );
             task.provider
-                .for_coordinates(&task.client, &task.cache, &task.request)
+                .all_for_coordinates(&task.client, &task.cache, &task.request)
         }));
     }
 
@@ -95,14 +174,14 @@ async fn serve_metrics(
 
 async fn wait_for_metrics(
     format: Format,
-    mut join_set: JoinSet<Result<anyhow::Result<Weather>, JoinError>>,
+    mut join_set: JoinSet<Result<anyhow::Result<Vec<Weather>>, JoinError>>,
 ) -> anyhow::Result<String> {
     let mut weather = vec![];
 
     while let Some(result) = join_set.join_next().await {
         result??.map_or_else(
             |e| error!("Provider error while fetching weather data: {e}"),
-            |w| weather.push(w),
+            |w| weather.extend(w),
         );
     }
 
@@ -8,6 +8,7 @@ use std::path::PathBuf;
 
 mod authentication;
 mod config;
+mod derived;
 mod error;
 mod http_server;
 mod logging;
@@ -1,13 +1,25 @@
 use crate::config::{NAME, VERSION};
+use crate::derived;
 use crate::providers::Weather;
+use chrono::{DateTime, Utc};
+use humantime_serde::re::humantime::format_duration;
 use log::debug;
 use prometheus_client::encoding::text::encode;
-use prometheus_client::encoding::EncodeLabelSet;
-use prometheus_client::metrics::family::Family;
+use prometheus_client::encoding::{EncodeLabelSet, LabelSetEncoder};
+use prometheus_client::metrics::counter::Counter;
+use prometheus_client::metrics::family::{Family, MetricConstructor};
 use prometheus_client::metrics::gauge::Gauge;
-use prometheus_client::registry::{Registry, Unit};
+use prometheus_client::metrics::TypedMetric;
+use prometheus_client::registry::{Metric, Registry, Unit};
+use std::fmt;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::iter;
 use std::sync::atomic::AtomicU64;
 
+// Air temperature is measured at 2 meters above ground by convention
+const STANDARD_TEMPERATURE_HEIGHT_METERS: f64 = 2.0;
+
 #[derive(PartialEq, Debug, Eq, Copy, Clone)]
 pub enum Format {
     Prometheus,
@@ -22,70 +34,483 @@ struct Labels {
     city: String,
     latitude: String,
     longitude: String,
+    #[prometheus(flatten)]
+    module: ModuleLabel,
 }
 
-pub fn format_metrics(_format: Format, weathers: Vec<Weather>) -> anyhow::Result<String> {
-    debug!("Formatting {weathers:?}");
+// Only present for providers reporting several modules per location
+#[derive(Clone, Hash, Eq, PartialEq, Debug)]
+struct ModuleLabel(Option<String>);
 
-    let mut registry = Registry::with_prefix("weather");
+impl EncodeLabelSet for ModuleLabel {
+    fn encode(&self, encoder: LabelSetEncoder) -> Result<(), fmt::Error> {
+        self.0.as_ref().map_or(Ok(()), |module| {
+            [("module", module.as_str())].encode(encoder)
+        })
+    }
+}
+
+// Height above ground in meters
+#[derive(Clone, Hash, Eq, PartialEq, EncodeLabelSet, Debug)]
+struct HeightLabels {
+    height: String,
+    #[prometheus(flatten)]
+    labels: Labels,
+}
 
-    let temperature = Family::<Labels, Gauge<f64, AtomicU64>>::default();
-    registry.register_with_unit(
-        "temperature",
-        format!("{NAME} temperature"),
-        Unit::Celsius,
-        temperature.clone(),
-    );
+#[derive(Clone, Hash, Eq, PartialEq, EncodeLabelSet, Debug)]
+struct PressureLabels {
+    level: String,
+    #[prometheus(flatten)]
+    labels: Labels,
+}
 
-    let humidity = Family::<Labels, Gauge<f64, AtomicU64>>::default();
-    let mut humidity_registered = false;
+#[derive(Clone, Hash, Eq, PartialEq, EncodeLabelSet, Debug)]
+struct RadiationLabels {
+    component: String,
+    #[prometheus(flatten)]
+    labels: Labels,
+}
 
-    let station_distance = Family::<Labels, Gauge<f64, AtomicU64>>::default();
-    let mut station_distance_registered = false;
+#[derive(Clone, Hash, Eq, PartialEq, EncodeLabelSet, Debug)]
+struct ConditionLabels {
+    condition: String,
+    code: String,
+    #[prometheus(flatten)]
+    labels: Labels,
+}
+
+#[derive(Clone, Hash, Eq, PartialEq, EncodeLabelSet, Debug)]
+struct PeriodLabels {
+    period: String,
+    #[prometheus(flatten)]
+    labels: Labels,
+}
+
+trait SetValue {
+    fn set_value(&self, value: f64);
+}
+
+impl SetValue for Gauge<f64, AtomicU64> {
+    fn set_value(&self, value: f64) {
+        self.set(value);
+    }
+}
+
+// The registry is created from scratch for every scrape, so a counter is set by incrementing from 0
+impl SetValue for Counter<f64, AtomicU64> {
+    fn set_value(&self, value: f64) {
+        self.inc_by(value);
+    }
+}
+
+// Metric family that is only registered once the first value is set to not emit empty metrics
+struct OptionalMetric<L, M> {
+    name: &'static str,
+    help: &'static str,
+    unit: Option<Unit>,
+    family: Family<L, M>,
+    registered: bool,
+}
+
+type OptionalGauge<L> = OptionalMetric<L, Gauge<f64, AtomicU64>>;
+type OptionalCounter<L> = OptionalMetric<L, Counter<f64, AtomicU64>>;
+
+impl<L, M> OptionalMetric<L, M>
+where
+    L: Clone + Hash + Eq + EncodeLabelSet + Debug + Send + Sync + 'static,
+    M: SetValue + Default + TypedMetric + Debug + Send + Sync + 'static,
+    Family<L, M>: Metric,
+    fn() -> M: MetricConstructor<M>,
+{
+    fn new(name: &'static str, help: &'static str, unit: Unit) -> Self {
+        Self {
+            name,
+            help,
+            unit: Some(unit),
+            family: Family::default(),
+            registered: false,
+        }
+    }
+
+    fn without_unit(name: &'static str, help: &'static str) -> Self {
+        Self {
+            name,
+            help,
+            unit: None,
+            family: Family::default(),
+            registered: false,
+        }
+    }
+
+    fn set(&mut self, registry: &mut Registry, labels: &L, value: f64) {
+        if !self.registered {
+            let help = format!("{NAME} {}", self.help);
+
+            match &self.unit {
+                Some(unit) => {
+                    registry.register_with_unit(self.name, help, unit.clone(), self.family.clone());
+                }
+                None => registry.register(self.name, help, self.family.clone()),
+            }
+            self.registered = true;
+        }
+
+        self.family.get_or_create(labels).set_value(value);
+    }
+}
+
+struct Metrics {
+    temperature: Family<HeightLabels, Gauge<f64, AtomicU64>>,
+    relative_humidity: OptionalGauge<Labels>,
+    absolute_humidity: OptionalGauge<Labels>,
+    dew_point: OptionalGauge<Labels>,
+    apparent_temperature: OptionalGauge<Labels>,
+    heat_index: OptionalGauge<Labels>,
+    wind_chill: OptionalGauge<Labels>,
+    station_distance: OptionalGauge<Labels>,
+    pressure: OptionalGauge<PressureLabels>,
+    wind_speed: OptionalGauge<Labels>,
+    wind_gust_speed: OptionalGauge<Labels>,
+    wind_direction: OptionalGauge<Labels>,
+    precipitation: OptionalGauge<PeriodLabels>,
+    precipitation_intensity: OptionalGauge<Labels>,
+    daily_precipitation: OptionalCounter<Labels>,
+    snow_depth: OptionalGauge<Labels>,
+    snowfall: OptionalGauge<PeriodLabels>,
+    cloud_cover: OptionalGauge<Labels>,
+    visibility: OptionalGauge<Labels>,
+    uv_index: OptionalGauge<Labels>,
+    solar_radiation: OptionalGauge<RadiationLabels>,
+    sunshine_duration: OptionalGauge<PeriodLabels>,
+    condition: OptionalGauge<ConditionLabels>,
+    observation_timestamp: OptionalGauge<Labels>,
+    observation_age: OptionalGauge<Labels>,
+    co2: OptionalGauge<Labels>,
+    noise: OptionalGauge<Labels>,
+    now: DateTime<Utc>,
+}
+
+impl Metrics {
+    // Plain list of metric definitions that grows with every metric
+    #[allow(clippy::too_many_lines)]
+    fn new(registry: &mut Registry, now: DateTime<Utc>) -> Self {
+        let temperature = Family::<HeightLabels, Gauge<f64, AtomicU64>>::default();
+        registry.register_with_unit(
+            "temperature",
+            format!("{NAME} temperature"),
+            Unit::Celsius,
+            temperature.clone(),
+        );
+
+        Self {
+            temperature,
+            relative_humidity: OptionalGauge::new(
+                "relative_humidity",
+                "relative humidity",
+                Unit::Other("ratio".into()),
+            ),
+            absolute_humidity: OptionalGauge::new(
+                "absolute_humidity",
+                "absolute humidity",
+                Unit::Other("grams_per_cubic_meter".into()),
+            ),
+            dew_point: OptionalGauge::new("dew_point", "dew point", Unit::Celsius),
+            apparent_temperature: OptionalGauge::new(
+                "apparent_temperature",
+                "apparent temperature",
+                Unit::Celsius,
+            ),
+            heat_index: OptionalGauge::new("heat_index", "heat index", Unit::Celsius),
+            wind_chill: OptionalGauge::new("wind_chill", "wind chill", Unit::Celsius),
+            station_distance: OptionalGauge::new(
+                "station_distance",
+                "weather station distance in meters",
+                Unit::Meters,
+            ),
+            pressure: OptionalGauge::new(
+                "pressure",
+                "atmospheric pressure",
+                Unit::Other("pascals".into()),
+            ),
+            wind_speed: OptionalGauge::new(
+                "wind_speed",
+                "wind speed",
+                Unit::Other("meters_per_second".into()),
+            ),
+            wind_gust_speed: OptionalGauge::new(
+                "wind_gust_speed",
+                "wind gust speed",
+                Unit::Other("meters_per_second".into()),
+            ),
+            wind_direction: OptionalGauge::new(
+                "wind_direction",
+                "direction the wind is coming from",
+                Unit::Other("degrees".into()),
+            ),
+            precipitation: OptionalGauge::new(
+                "precipitation",
+                "precipitation amount over the given period",
+                Unit::Other("millimeters".into()),
+            ),
+            precipitation_intensity: OptionalGauge::new(
+                "precipitation_intensity",
+                "precipitation intensity",
+                Unit::Other("millimeters_per_hour".into()),
+            ),
+            daily_precipitation: OptionalCounter::new(
+                "daily_precipitation",
+                "precipitation accumulated since midnight UTC",
+                Unit::Other("millimeters".into()),
+            ),
+            snow_depth: OptionalGauge::new("snow_depth", "snow depth", Unit::Meters),
+            snowfall: OptionalGauge::new(
+                "snowfall",
+                "fresh snowfall over the given period",
+                Unit::Other("millimeters".into()),
+            ),
+            cloud_cover: OptionalGauge::new(
+                "cloud_cover",
+                "cloud cover",
+                Unit::Other("ratio".into()),
+            ),
+            visibility: OptionalGauge::new("visibility", "visibility", Unit::Meters),
+            uv_index: OptionalGauge::without_unit("uv_index", "UV index"),
+            solar_radiation: OptionalGauge::new(
+                "solar_radiation",
+                "solar radiation on a horizontal surface",
+                Unit::Other("watts_per_square_meter".into()),
+            ),
+            sunshine_duration: OptionalGauge::new(
+                "sunshine_duration",
+                "sunshine duration within the given period",
+                Unit::Seconds,
+            ),
+            condition: OptionalGauge::without_unit("condition", "current weather condition"),
+            observation_timestamp: OptionalGauge::new(
+                "observation_timestamp",
+                "time the weather was observed at",
+                Unit::Seconds,
+            ),
+            observation_age: OptionalGauge::new(
+                "observation_age",
+                "age of the observed weather",
+                Unit::Seconds,
+            ),
+            co2: OptionalGauge::new(
+                "co2",
+                "carbon dioxide concentration",
+                Unit::Other("ppm".into()),
+            ),
+            noise: OptionalGauge::new("noise", "noise level", Unit::Other("decibels".into())),
+            now,
+        }
+    }
+
+    fn record(&mut self, registry: &mut Registry, weather: &Weather) {
+        let absolute_humidity = derived::absolute_humidity(weather);
+        let dew_point = derived::dew_point(weather);
+        let apparent_temperature = derived::apparent_temperature(weather);
+        let heat_index = derived::heat_index(weather);
+        let wind_chill = derived::wind_chill(weather);
+        let observation_timestamp = weather.observation_time.and_then(|time| {
+            time.signed_duration_since(DateTime::UNIX_EPOCH)
+                .to_std()
+                .ok()
+        });
+        // Clock skew can place observations slightly in the future
+        let observation_age = weather.observation_time.map(|time| {
+            self.now
+                .signed_duration_since(time)
+                .to_std()
+                .unwrap_or_default()
+        });
 
-    for weather in weathers {
         let labels = Labels {
             version: VERSION.into(),
-            source: weather.source,
+            source: weather.source.clone(),
             location: weather.location.clone(),
-            city: weather.city.unwrap_or_else(String::new),
+            city: weather.city.clone().unwrap_or_default(),
             latitude: weather.coordinates.latitude.to_string(),
             longitude: weather.coordinates.longitude.to_string(),
+            module: ModuleLabel(weather.module.clone()),
         };
 
-        temperature
-            .get_or_create(&labels)
-            .set(weather.temperature.into());
-
-        weather.relative_humidity.map(|relative_humidity_ratio| {
-            if !humidity_registered {
-                registry.register_with_unit(
-                    "relative_humidity",
-                    format!("{NAME} relative humidity"),
-                    Unit::Other("ratio".into()),
-                    humidity.clone(),
+        for (height, temperature) in
+            iter::once((STANDARD_TEMPERATURE_HEIGHT_METERS, weather.temperature)).chain(
+                weather.temperatures_at_height.iter().map(|measurement| {
+                    (measurement.height.clone().into(), measurement.temperature)
+                }),
+            )
+        {
+            self.temperature
+                .get_or_create(&HeightLabels {
+                    height: height.to_string(),
+                    labels: labels.clone(),
+                })
+                .set(temperature.into());
+        }
+
+        for (gauge, value) in [
+            (
+                &mut self.relative_humidity,
+                weather.relative_humidity.map(f64::from),
+            ),
+            (&mut self.absolute_humidity, absolute_humidity),
+            (&mut self.dew_point, dew_point),
+            (&mut self.apparent_temperature, apparent_temperature),
+            (&mut self.heat_index, heat_index),
+            (&mut self.wind_chill, wind_chill),
+            (
+                &mut self.station_distance,
+                weather.distance.clone().map(f64::from),
+            ),
+            (&mut self.wind_speed, weather.wind_speed.map(f64::from)),
+            (
+                &mut self.wind_gust_speed,
+                weather.wind_gust_speed.map(f64::from),
+            ),
+            (
+                &mut self.wind_direction,
+                weather.wind_direction.map(f64::from),
+            ),
+            (
+                &mut self.precipitation_intensity,
+                weather.precipitation_intensity.map(f64::from),
+            ),
+            (&mut self.cloud_cover, weather.cloud_cover.map(f64::from)),
+            (
+                &mut self.visibility,
+                weather.visibility.clone().map(f64::from),
+            ),
+            (&mut self.uv_index, weather.uv_index),
+            (
+                &mut self.snow_depth,
+                weather.snow_depth.clone().map(f64::from),
+            ),
+            (
+                &mut self.observation_timestamp,
+                observation_timestamp.map(|duration| duration.as_secs_f64()),
+            ),
+            (
+                &mut self.observation_age,
+                observation_age.map(|duration| duration.as_secs_f64()),
+            ),
+            (&mut self.co2, weather.co2.map(f64::from)),
+            (&mut self.noise, weather.noise.map(f64::from)),
+        ] {
+            if let Some(measurement) = value {
+                gauge.set(registry, &labels, measurement);
+            }
+        }
+
+        self.record_qualified(registry, &labels, weather);
+
+        if let Some(amount) = weather.daily_precipitation {
+            self.daily_precipitation
+                .set(registry, &labels, amount.into());
+        }
+    }
+
+    // Metrics with an additional label qualifying the measurement
+    fn record_qualified(&mut self, registry: &mut Registry, labels: &Labels, weather: &Weather) {
+        for (level, value) in [
+            ("station", weather.station_pressure),
+            ("sea_level", weather.sea_level_pressure),
+        ] {
+            if let Some(hectopascal) = value {
+                self.pressure.set(
+                    registry,
+                    &PressureLabels {
+                        level: level.into(),
+                        labels: labels.clone(),
+                    },
+                    hectopascal.to_pascals(),
                 );
-                humidity_registered = true;
             }
+        }
 
-            humidity
-                .get_or_create(&labels)
-                .set(relative_humidity_ratio.into())
-        });
+        if let Some(precipitation) = &weather.precipitation {
+            self.precipitation.set(
+                registry,
+                &PeriodLabels {
+                    period: format_duration(precipitation.period).to_string(),
+                    labels: labels.clone(),
+                },
+                precipitation.amount.into(),
+            );
+        }
+
+        if let Some(snowfall) = &weather.snowfall {
+            self.snowfall.set(
+                registry,
+                &PeriodLabels {
+                    period: format_duration(snowfall.period).to_string(),
+                    labels: labels.clone(),
+                },
+                snowfall.amount.into(),
+            );
+        }
 
-        weather.distance.map(|meters| {
-            if !station_distance_registered {
-                registry.register_with_unit(
-                    "station_distance",
-                    format!("{NAME} weather station distance in meters"),
-                    Unit::Meters,
-                    station_distance.clone(),
+        for (component, value) in [
+            ("global", weather.global_radiation),
+            ("direct", weather.direct_radiation),
+            ("diffuse", weather.diffuse_radiation),
+        ] {
+            if let Some(irradiance) = value {
+                self.solar_radiation.set(
+                    registry,
+                    &RadiationLabels {
+                        component: component.into(),
+                        labels: labels.clone(),
+                    },
+                    irradiance.into(),
                 );
-                station_distance_registered = true;
             }
+        }
 
-            station_distance.get_or_create(&labels).set(meters.into())
-        });
+        if let Some(sunshine) = &weather.sunshine {
+            self.sunshine_duration.set(
+                registry,
+                &PeriodLabels {
+                    period: format_duration(sunshine.period).to_string(),
+                    labels: labels.clone(),
+                },
+                sunshine.duration.as_secs_f64(),
+            );
+        }
+
+        // Info style metric, the condition is conveyed by the labels
+        if let Some(condition) = &weather.condition {
+            self.condition.set(
+                registry,
+                &ConditionLabels {
+                    condition: condition.condition.to_string(),
+                    code: condition.code.clone(),
+                    labels: labels.clone(),
+                },
+                1.0_f64,
+            );
+        }
+    }
+}
+
+pub fn format_metrics(format: Format, weathers: Vec<Weather>) -> anyhow::Result<String> {
+    format_metrics_at(format, weathers, Utc::now())
+}
+
+fn format_metrics_at(
+    _format: Format,
+    weathers: Vec<Weather>,
+    now: DateTime<Utc>,
+) -> anyhow::Result<String> {
+    debug!("Formatting {weathers:?}");
+
+    let mut registry = Registry::with_prefix("weather");
+    let mut metrics = Metrics::new(&mut registry, now);
+
+    for weather in weathers {
+        metrics.record(&mut registry, &weather);
     }
 
     let mut buffer = String::new();
@@ -98,12 +523,20 @@ pub fn format_metrics(_format: Format, weathers: Vec<Weather>) -> anyhow::Result
 #[cfg(test)]
 mod tests {
     use crate::config::VERSION;
-    use crate::prometheus::{format_metrics, Format};
+    use crate::prometheus::{format_metrics, format_metrics_at, Format};
     use crate::providers::units::Ratio::Fraction;
-    use crate::providers::units::{Celsius, Coordinate, Coordinates, Meters, Ratio};
-    use crate::providers::Weather;
+    use crate::providers::units::{
+        Celsius, Condition, Coordinate, Coordinates, Decibels, Degrees, Hectopascal, Meters,
+        MetersPerSecond, Millimeters, MillimetersPerHour, PartsPerMillion, Ratio,
+        WattsPerSquareMeter,
+    };
+    use crate::providers::{
+        Precipitation, Sunshine, TemperatureAtHeight, Weather, WeatherCondition,
+    };
+    use chrono::{DateTime, TimeDelta, Utc};
     use pretty_assertions::assert_str_eq;
     use std::cmp::Ordering;
+    use std::time::Duration;
 
     fn sort_output_deterministically(output: &str) -> String {
         let mut lines: Vec<&str> = output.lines().collect();
@@ -147,16 +580,18 @@ mod tests {
 
     fn create_weather(relative_humidity: Option<Ratio>, distance: Option<Meters>) -> Weather {
         Weather {
-            source: "org.example".into(),
-            coordinates: Coordinates {
-                latitude: Coordinate::from(20.1_f64),
-                longitude: Coordinate::from(10.01234_f64),
-            },
-            location: "My Name".into(),
             city: Some("Some City".into()),
-            temperature: Celsius::from(25.5),
             relative_humidity,
             distance,
+            ..Weather::new(
+                "org.example".into(),
+                "My Name".into(),
+                Coordinates {
+                    latitude: Coordinate::from(20.1_f64),
+                    longitude: Coordinate::from(10.01234_f64),
+                },
+                Celsius::from(25.5),
+            )
         }
     }
 
@@ -169,7 +604,7 @@ mod tests {
                 r#"# HELP weather_temperature_celsius prometheus-weathermen temperature.
 # TYPE weather_temperature_celsius gauge
 # UNIT weather_temperature_celsius celsius
-weather_temperature_celsius{{version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 25.5
+weather_temperature_celsius{{height="2",version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 25.5
 # EOF"#
             ),
         );
@@ -184,11 +619,19 @@ weather_temperature_celsius{{version="{VERSION}",source="org.example",location="
                 r#"# HELP weather_temperature_celsius prometheus-weathermen temperature.
 # TYPE weather_temperature_celsius gauge
 # UNIT weather_temperature_celsius celsius
-weather_temperature_celsius{{version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 25.5
+weather_temperature_celsius{{height="2",version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 25.5
 # HELP weather_relative_humidity_ratio prometheus-weathermen relative humidity.
 # TYPE weather_relative_humidity_ratio gauge
 # UNIT weather_relative_humidity_ratio ratio
 weather_relative_humidity_ratio{{version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 0.55
+# HELP weather_absolute_humidity_grams_per_cubic_meter prometheus-weathermen absolute humidity.
+# TYPE weather_absolute_humidity_grams_per_cubic_meter gauge
+# UNIT weather_absolute_humidity_grams_per_cubic_meter grams_per_cubic_meter
+weather_absolute_humidity_grams_per_cubic_meter{{version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 12.99
+# HELP weather_dew_point_celsius prometheus-weathermen dew point.
+# TYPE weather_dew_point_celsius gauge
+# UNIT weather_dew_point_celsius celsius
+weather_dew_point_celsius{{version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 15.79
 # EOF"#
             ),
         );
@@ -203,7 +646,7 @@ weather_relative_humidity_ratio{{version="{VERSION}",source="org.example",locati
                 r#"# HELP weather_temperature_celsius prometheus-weathermen temperature.
 # TYPE weather_temperature_celsius gauge
 # UNIT weather_temperature_celsius celsius
-weather_temperature_celsius{{version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 25.5
+weather_temperature_celsius{{height="2",version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 25.5
 # HELP weather_station_distance_meters prometheus-weathermen weather station distance in meters.
 # TYPE weather_station_distance_meters gauge
 # UNIT weather_station_distance_meters meters
@@ -213,47 +656,386 @@ weather_station_distance_meters{{version="{VERSION}",source="org.example",locati
         );
     }
 
+    #[test]
+    fn format_temperature_at_height() {
+        test_format_metrics(
+            Format::Prometheus,
+            vec![Weather {
+                temperatures_at_height: vec![TemperatureAtHeight {
+                    height: Meters::from(0.05_f64),
+                    temperature: Celsius::from(-1.5_f32),
+                }],
+                ..create_weather(None, None)
+            }],
+            &format!(
+                r#"# HELP weather_temperature_celsius prometheus-weathermen temperature.
+# TYPE weather_temperature_celsius gauge
+# UNIT weather_temperature_celsius celsius
+weather_temperature_celsius{{height="0.05",version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} -1.5
+weather_temperature_celsius{{height="2",version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 25.5
+# EOF"#
+            ),
+        );
+    }
+
+    #[test]
+    fn format_temperature_and_sky() {
+        test_format_metrics(
+            Format::Prometheus,
+            vec![Weather {
+                cloud_cover: Some(Ratio::Percentage(75.0_f64)),
+                visibility: Some(Meters::from(10_000.0_f64)),
+                uv_index: Some(3.5_f64),
+                ..create_weather(None, None)
+            }],
+            &format!(
+                r#"# HELP weather_temperature_celsius prometheus-weathermen temperature.
+# TYPE weather_temperature_celsius gauge
+# UNIT weather_temperature_celsius celsius
+weather_temperature_celsius{{height="2",version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 25.5
+# HELP weather_cloud_cover_ratio prometheus-weathermen cloud cover.
+# TYPE weather_cloud_cover_ratio gauge
+# UNIT weather_cloud_cover_ratio ratio
+weather_cloud_cover_ratio{{version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 0.75
+# HELP weather_visibility_meters prometheus-weathermen visibility.
+# TYPE weather_visibility_meters gauge
+# UNIT weather_visibility_meters meters
+weather_visibility_meters{{version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 10000.0
+# HELP weather_uv_index prometheus-weathermen UV index.
+# TYPE weather_uv_index gauge
+weather_uv_index{{version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 3.5
+# EOF"#
+            ),
+        );
+    }
+
+    #[test]
+    fn format_temperature_and_solar_radiation() {
+        test_format_metrics(
+            Format::Prometheus,
+            vec![Weather {
+                global_radiation: Some(WattsPerSquareMeter::from(750.0_f64)),
+                direct_radiation: Some(WattsPerSquareMeter::from(600.0_f64)),
+                diffuse_radiation: Some(WattsPerSquareMeter::from(150.0_f64)),
+                sunshine: Some(Sunshine {
+                    duration: Duration::from_secs(450),
+                    period: Duration::from_secs(600),
+                }),
+                ..create_weather(None, None)
+            }],
+            &format!(
+                r#"# HELP weather_temperature_celsius prometheus-weathermen temperature.
+# TYPE weather_temperature_celsius gauge
+# UNIT weather_temperature_celsius celsius
+weather_temperature_celsius{{height="2",version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 25.5
+# HELP weather_solar_radiation_watts_per_square_meter prometheus-weathermen solar radiation on a horizontal surface.
+# TYPE weather_solar_radiation_watts_per_square_meter gauge
+# UNIT weather_solar_radiation_watts_per_square_meter watts_per_square_meter
+weather_solar_radiation_watts_per_square_meter{{component="diffuse",version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 150.0
+weather_solar_radiation_watts_per_square_meter{{component="direct",version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 600.0
+weather_solar_radiation_watts_per_square_meter{{component="global",version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 750.0
+# HELP weather_sunshine_duration_seconds prometheus-weathermen sunshine duration within the given period.
+# TYPE weather_sunshine_duration_seconds gauge
+# UNIT weather_sunshine_duration_seconds seconds
+weather_sunshine_duration_seconds{{period="10m",version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 450.0
+# EOF"#
+            ),
+        );
+    }
+
+    #[test]
+    fn format_temperature_and_condition() {
+        test_format_metrics(
+            Format::Prometheus,
+            vec![Weather {
+                condition: Some(WeatherCondition {
+                    condition: Condition::Rain,
+                    code: "61".into(),
+                }),
+                ..create_weather(None, None)
+            }],
+            &format!(
+                r#"# HELP weather_temperature_celsius prometheus-weathermen temperature.
+# TYPE weather_temperature_celsius gauge
+# UNIT weather_temperature_celsius celsius
+weather_temperature_celsius{{height="2",version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 25.5
+# HELP weather_condition prometheus-weathermen current weather condition.
+# TYPE weather_condition gauge
+weather_condition{{condition="rain",code="61",version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 1.0
+# EOF"#
+            ),
+        );
+    }
+
+    #[test]
+    fn format_temperature_and_observation_time() {
+        let observation_time = DateTime::parse_from_rfc3339("2024-06-01T12:00:00Z")
+            .expect("Static value")
+            .with_timezone(&Utc);
+
+        assert_str_eq!(
+            sort_output_deterministically(
+                &format_metrics_at(
+                    Format::Prometheus,
+                    vec![Weather {
+                        observation_time: Some(observation_time),
+                        ..create_weather(None, None)
+                    }],
+                    observation_time + TimeDelta::seconds(90),
+                )
+                .expect("Formatting should work")
+            ),
+            format!(
+                r#"# HELP weather_temperature_celsius prometheus-weathermen temperature.
+# TYPE weather_temperature_celsius gauge
+# UNIT weather_temperature_celsius celsius
+weather_temperature_celsius{{height="2",version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 25.5
+# HELP weather_observation_timestamp_seconds prometheus-weathermen time the weather was observed at.
+# TYPE weather_observation_timestamp_seconds gauge
+# UNIT weather_observation_timestamp_seconds seconds
+weather_observation_timestamp_seconds{{version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 1717243200.0
+# HELP weather_observation_age_seconds prometheus-weathermen age of the observed weather.
+# TYPE weather_observation_age_seconds gauge
+# UNIT weather_observation_age_seconds seconds
+weather_observation_age_seconds{{version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 90.0
+# EOF"#
+            )
+        );
+    }
+
+    #[test]
+    fn format_temperature_and_snow() {
+        test_format_metrics(
+            Format::Prometheus,
+            vec![Weather {
+                snow_depth: Some(Meters::from(0.35_f64)),
+                snowfall: Some(Precipitation {
+                    amount: Millimeters::from(40.0_f64),
+                    period: Duration::from_secs(60 * 60 * 24),
+                }),
+                ..create_weather(None, None)
+            }],
+            &format!(
+                r#"# HELP weather_temperature_celsius prometheus-weathermen temperature.
+# TYPE weather_temperature_celsius gauge
+# UNIT weather_temperature_celsius celsius
+weather_temperature_celsius{{height="2",version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 25.5
+# HELP weather_snow_depth_meters prometheus-weathermen snow depth.
+# TYPE weather_snow_depth_meters gauge
+# UNIT weather_snow_depth_meters meters
+weather_snow_depth_meters{{version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 0.35
+# HELP weather_snowfall_millimeters prometheus-weathermen fresh snowfall over the given period.
+# TYPE weather_snowfall_millimeters gauge
+# UNIT weather_snowfall_millimeters millimeters
+weather_snowfall_millimeters{{period="1day",version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 40.0
+# EOF"#
+            ),
+        );
+    }
+
+    #[test]
+    fn format_temperature_and_pressure() {
+        test_format_metrics(
+            Format::Prometheus,
+            vec![Weather {
+                station_pressure: Some(Hectopascal::from(955.3_f64)),
+                sea_level_pressure: Some(Hectopascal::from(1_013.25_f64)),
+                ..create_weather(None, None)
+            }],
+            &format!(
+                r#"# HELP weather_temperature_celsius prometheus-weathermen temperature.
+# TYPE weather_temperature_celsius gauge
+# UNIT weather_temperature_celsius celsius
+weather_temperature_celsius{{height="2",version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 25.5
+# HELP weather_pressure_pascals prometheus-weathermen atmospheric pressure.
+# TYPE weather_pressure_pascals gauge
+# UNIT weather_pressure_pascals pascals
+weather_pressure_pascals{{level="sea_level",version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 101325.0
+weather_pressure_pascals{{level="station",version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 95530.0
+# EOF"#
+            ),
+        );
+    }
+
+    #[test]
+    fn format_derived_cold_weather() {
+        test_format_metrics(
+            Format::Prometheus,
+            vec![Weather {
+                temperature: Celsius::from(-10.0_f32),
+                dew_point: Some(Celsius::from(-12.5_f32)),
+                wind_speed: Some(MetersPerSecond::from(5.0_f64)),
+                ..create_weather(Some(Fraction(0.8_f64)), None)
+            }],
+            &format!(
+                r#"# HELP weather_temperature_celsius prometheus-weathermen temperature.
+# TYPE weather_temperature_celsius gauge
+# UNIT weather_temperature_celsius celsius
+weather_temperature_celsius{{height="2",version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} -10.0
+# HELP weather_relative_humidity_ratio prometheus-weathermen relative humidity.
+# TYPE weather_relative_humidity_ratio gauge
+# UNIT weather_relative_humidity_ratio ratio
+weather_relative_humidity_ratio{{version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 0.8
+# HELP weather_absolute_humidity_grams_per_cubic_meter prometheus-weathermen absolute humidity.
+# TYPE weather_absolute_humidity_grams_per_cubic_meter gauge
+# UNIT weather_absolute_humidity_grams_per_cubic_meter grams_per_cubic_meter
+weather_absolute_humidity_grams_per_cubic_meter{{version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 1.89
+# HELP weather_dew_point_celsius prometheus-weathermen dew point.
+# TYPE weather_dew_point_celsius gauge
+# UNIT weather_dew_point_celsius celsius
+weather_dew_point_celsius{{version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} -12.5
+# HELP weather_apparent_temperature_celsius prometheus-weathermen apparent temperature.
+# TYPE weather_apparent_temperature_celsius gauge
+# UNIT weather_apparent_temperature_celsius celsius
+weather_apparent_temperature_celsius{{version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} -16.74
+# HELP weather_wind_chill_celsius prometheus-weathermen wind chill.
+# TYPE weather_wind_chill_celsius gauge
+# UNIT weather_wind_chill_celsius celsius
+weather_wind_chill_celsius{{version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} -17.45
+# HELP weather_wind_speed_meters_per_second prometheus-weathermen wind speed.
+# TYPE weather_wind_speed_meters_per_second gauge
+# UNIT weather_wind_speed_meters_per_second meters_per_second
+weather_wind_speed_meters_per_second{{version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 5.0
+# EOF"#
+            ),
+        );
+    }
+
+    #[test]
+    fn format_temperature_and_wind() {
+        test_format_metrics(
+            Format::Prometheus,
+            vec![Weather {
+                wind_speed: Some(MetersPerSecond::from(3.5_f64)),
+                wind_gust_speed: Some(MetersPerSecond::from(7.25_f64)),
+                wind_direction: Some(Degrees::from(270_f64)),
+                ..create_weather(None, None)
+            }],
+            &format!(
+                r#"# HELP weather_temperature_celsius prometheus-weathermen temperature.
+# TYPE weather_temperature_celsius gauge
+# UNIT weather_temperature_celsius celsius
+weather_temperature_celsius{{height="2",version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 25.5
+# HELP weather_wind_speed_meters_per_second prometheus-weathermen wind speed.
+# TYPE weather_wind_speed_meters_per_second gauge
+# UNIT weather_wind_speed_meters_per_second meters_per_second
+weather_wind_speed_meters_per_second{{version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 3.5
+# HELP weather_wind_gust_speed_meters_per_second prometheus-weathermen wind gust speed.
+# TYPE weather_wind_gust_speed_meters_per_second gauge
+# UNIT weather_wind_gust_speed_meters_per_second meters_per_second
+weather_wind_gust_speed_meters_per_second{{version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 7.25
+# HELP weather_wind_direction_degrees prometheus-weathermen direction the wind is coming from.
+# TYPE weather_wind_direction_degrees gauge
+# UNIT weather_wind_direction_degrees degrees
+weather_wind_direction_degrees{{version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 270.0
+# EOF"#
+            ),
+        );
+    }
+
+    #[test]
+    fn format_temperature_and_precipitation() {
+        test_format_metrics(
+            Format::Prometheus,
+            vec![Weather {
+                precipitation: Some(Precipitation {
+                    amount: Millimeters::from(0.4_f64),
+                    period: Duration::from_secs(600),
+                }),
+                precipitation_intensity: Some(MillimetersPerHour::from(2.5_f64)),
+                daily_precipitation: Some(Millimeters::from(12.5_f64)),
+                ..create_weather(None, None)
+            }],
+            &format!(
+                r#"# HELP weather_temperature_celsius prometheus-weathermen temperature.
+# TYPE weather_temperature_celsius gauge
+# UNIT weather_temperature_celsius celsius
+weather_temperature_celsius{{height="2",version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 25.5
+# HELP weather_precipitation_intensity_millimeters_per_hour prometheus-weathermen precipitation intensity.
+# TYPE weather_precipitation_intensity_millimeters_per_hour gauge
+# UNIT weather_precipitation_intensity_millimeters_per_hour millimeters_per_hour
+weather_precipitation_intensity_millimeters_per_hour{{version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 2.5
+# HELP weather_precipitation_millimeters prometheus-weathermen precipitation amount over the given period.
+# TYPE weather_precipitation_millimeters gauge
+# UNIT weather_precipitation_millimeters millimeters
+weather_precipitation_millimeters{{period="10m",version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 0.4
+# HELP weather_daily_precipitation_millimeters prometheus-weathermen precipitation accumulated since midnight UTC.
+# TYPE weather_daily_precipitation_millimeters counter
+# UNIT weather_daily_precipitation_millimeters millimeters
+weather_daily_precipitation_millimeters_total{{version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 12.5
+# EOF"#
+            ),
+        );
+    }
+
+    #[test]
+    fn format_module_with_air_quality() {
+        test_format_metrics(
+            Format::Prometheus,
+            vec![Weather {
+                module: Some("Indoor".into()),
+                co2: Some(PartsPerMillion::from(612_f64)),
+                noise: Some(Decibels::from(38_f64)),
+                ..create_weather(None, None)
+            }],
+            &format!(
+                r#"# HELP weather_temperature_celsius prometheus-weathermen temperature.
+# TYPE weather_temperature_celsius gauge
+# UNIT weather_temperature_celsius celsius
+weather_temperature_celsius{{height="2",version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400",module="Indoor"}} 25.5
+# HELP weather_co2_ppm prometheus-weathermen carbon dioxide concentration.
+# TYPE weather_co2_ppm gauge
+# UNIT weather_co2_ppm ppm
+weather_co2_ppm{{version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400",module="Indoor"}} 612.0
+# HELP weather_noise_decibels prometheus-weathermen noise level.
+# TYPE weather_noise_decibels gauge
+# UNIT weather_noise_decibels decibels
+weather_noise_decibels{{version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400",module="Indoor"}} 38.0
+# EOF"#
+            ),
+        );
+    }
+
     #[test]
     fn format_multiple() {
         test_format_metrics(
             Format::Prometheus,
             vec![
+                create_weather(Some(Fraction(0.55)), None),
                 Weather {
-                    source: "org.example".into(),
-                    coordinates: Coordinates {
-                        latitude: Coordinate::from(20.1_f64),
-                        longitude: Coordinate::from(10.01234_f64),
-                    },
-                    location: "My Name".into(),
-                    city: Some("Some City".into()),
-                    temperature: Celsius::from(25.5),
-                    relative_humidity: Some(Fraction(0.55)),
-                    distance: None,
-                },
-                Weather {
-                    source: "com.example".into(),
-                    coordinates: Coordinates {
-                        latitude: Coordinate::from(30.1_f64),
-                        longitude: Coordinate::from(20.01234_f64),
-                    },
-                    location: "Another Name".into(),
-                    city: None,
-                    temperature: Celsius::from(15.5),
                     relative_humidity: Some(Fraction(0.75)),
-                    distance: None,
+                    ..Weather::new(
+                        "com.example".into(),
+                        "Another Name".into(),
+                        Coordinates {
+                            latitude: Coordinate::from(30.1_f64),
+                            longitude: Coordinate::from(20.01234_f64),
+                        },
+                        Celsius::from(15.5),
+                    )
                 },
             ],
             &format!(
                 r#"# HELP weather_temperature_celsius prometheus-weathermen temperature.
 # TYPE weather_temperature_celsius gauge
 # UNIT weather_temperature_celsius celsius
-weather_temperature_celsius{{version="{VERSION}",source="com.example",location="Another Name",city="",latitude="30.1000000",longitude="20.0123400"}} 15.5
-weather_temperature_celsius{{version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 25.5
+weather_temperature_celsius{{height="2",version="{VERSION}",source="com.example",location="Another Name",city="",latitude="30.1000000",longitude="20.0123400"}} 15.5
+weather_temperature_celsius{{height="2",version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 25.5
 # HELP weather_relative_humidity_ratio prometheus-weathermen relative humidity.
 # TYPE weather_relative_humidity_ratio gauge
 # UNIT weather_relative_humidity_ratio ratio
 weather_relative_humidity_ratio{{version="{VERSION}",source="com.example",location="Another Name",city="",latitude="30.1000000",longitude="20.0123400"}} 0.75
 weather_relative_humidity_ratio{{version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 0.55
+# HELP weather_absolute_humidity_grams_per_cubic_meter prometheus-weathermen absolute humidity.
+# TYPE weather_absolute_humidity_grams_per_cubic_meter gauge
+# UNIT weather_absolute_humidity_grams_per_cubic_meter grams_per_cubic_meter
+weather_absolute_humidity_grams_per_cubic_meter{{version="{VERSION}",source="com.example",location="Another Name",city="",latitude="30.1000000",longitude="20.0123400"}} 9.89
+weather_absolute_humidity_grams_per_cubic_meter{{version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 12.99
+# HELP weather_dew_point_celsius prometheus-weathermen dew point.
+# TYPE weather_dew_point_celsius gauge
+# UNIT weather_dew_point_celsius celsius
+weather_dew_point_celsius{{version="{VERSION}",source="com.example",location="Another Name",city="",latitude="30.1000000",longitude="20.0123400"}} 11.08
+weather_dew_point_celsius{{version="{VERSION}",source="org.example",location="My Name",city="Some City",latitude="20.1000000",longitude="10.0123400"}} 15.79
 # EOF"#
             ),
         );
@@ -0,0 +1,329 @@
+use crate::derived::relative_humidity_from_dew_point;
+use crate::providers::http_request::{request_cached, Configuration, HttpCacheRequest};
+use crate::providers::metar::{phenomena, Metar};
+use crate::providers::units::{Condition, Coordinates, Ratio};
+use crate::providers::{
+    calculate_distance, find_closest, HttpRequestCache, Weather, WeatherCondition, WeatherProvider,
+    WeatherRequest,
+};
+use anyhow::anyhow;
+use chrono::Utc;
+use log::trace;
+use reqwest::blocking::Client;
+use reqwest::{Method, Url};
+use serde::{Deserialize, Serialize};
+use std::str;
+use std::time::Duration;
+
+const SOURCE_URI: &str = "gov.aviationweather";
+const STATION_INFO_URL: &str = "https://aviationweather.gov/api/data/stationinfo";
+const METAR_URL: &str = "https://aviationweather.gov/api/data/metar";
+
+// Airports are searched within a box of this many degrees around the location
+const SEARCH_RADIUS_DEGREES: f64 = 1.0;
+const OKTAS_OVERCAST: f64 = 8.0;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AviationWeather {
+    #[serde(flatten)]
+    cache: Configuration,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct Airport {
+    icao_id: Option<String>,
+    site: String,
+    lat: f64,
+    lon: f64,
+    // Report types issued by the station, e.g. METAR and TAF
+    #[serde(default)]
+    site_type: Vec<String>,
+}
+
+impl Airport {
+    fn coordinates(&self) -> Coordinates {
+        Coordinates {
+            latitude: self.lat.into(),
+            longitude: self.lon.into(),
+        }
+    }
+}
+
+fn find_closest_reporting_airport<'airports>(
+    coords: &Coordinates,
+    airports: &'airports [Airport],
+) -> anyhow::Result<&'airports Airport> {
+    let reporting_airports: Vec<&Airport> = airports
+        .iter()
+        .filter(|airport| airport.icao_id.is_some())
+        .filter(|airport| {
+            airport
+                .site_type
+                .iter()
+                .any(|site_type| site_type == "METAR")
+        })
+        .collect();
+
+    find_closest(coords, &reporting_airports, |airport| airport.coordinates()).copied()
+}
+
+fn condition_from_weather(group: &str) -> Condition {
+    let codes = phenomena(group).unwrap_or_default();
+    let has = |code: &str| codes.contains(&code);
+
+    if has("TS") {
+        Condition::Thunderstorm
+    } else if has("FZ") && (has("RA") || has("DZ")) {
+        Condition::FreezingRain
+    } else if has("PL") || has("GR") || has("GS") || has("IC") {
+        Condition::Sleet
+    } else if has("SN") || has("SG") {
+        Condition::Snow
+    } else if has("RA") || has("SH") {
+        Condition::Rain
+    } else if has("DZ") {
+        Condition::Drizzle
+    } else if has("FG") || has("BR") {
+        Condition::Fog
+    } else if has("HZ") || has("FU") || has("DU") || has("SA") {
+        Condition::Haze
+    } else {
+        Condition::Unknown
+    }
+}
+
+// Present weather takes precedence, otherwise the sky condition is used
+fn to_condition(metar: &Metar) -> Option<WeatherCondition> {
+    if let Some(group) = metar.weather.first() {
+        return Some(WeatherCondition {
+            condition: condition_from_weather(group),
+            code: group.clone(),
+        });
+    }
+
+    let densest = metar.clouds.iter().max_by_key(|layer| layer.cover.oktas());
+    match (densest, metar.clear_sky) {
+        (Some(layer), _) => Some(WeatherCondition {
+            condition: if layer.cover.oktas() <= 4 {
+                Condition::PartlyCloudy
+            } else {
+                Condition::Cloudy
+            },
+            code: layer.cover.code().into(),
+        }),
+        (None, true) => Some(WeatherCondition {
+            condition: Condition::Clear,
+            code: "SKC".into(),
+        }),
+        (None, false) => None,
+    }
+}
+
+fn to_weather(
+    request: &WeatherRequest<Coordinates>,
+    airport: &Airport,
+    metar: &Metar,
+) -> anyhow::Result<Weather> {
+    let coordinates = airport.coordinates();
+    let temperature = metar
+        .temperature
+        .ok_or_else(|| anyhow!("No temperature reported by {}", metar.station))?;
+
+    Ok(Weather {
+        city: Some(airport.site.clone()),
+        distance: Some(calculate_distance(&request.query, &coordinates)),
+        relative_humidity: metar.dew_point.map(|dew_point| {
+            Ratio::Fraction(relative_humidity_from_dew_point(
+                temperature.into(),
+                dew_point.into(),
+            ))
+        }),
+        dew_point: metar.dew_point,
+        // The altimeter setting (QNH) is reduced to sea level using the standard atmosphere
+        sea_level_pressure: metar.altimeter,
+        wind_speed: metar.wind.as_ref().map(|wind| wind.speed),
+        wind_gust_speed: metar.wind.as_ref().and_then(|wind| wind.gust),
+        wind_direction: metar.wind.as_ref().and_then(|wind| wind.direction),
+        cloud_cover: metar
+            .cloud_oktas()
+            .map(|oktas| Ratio::Fraction(f64::from(oktas) / OKTAS_OVERCAST)),
+        visibility: metar.visibility.clone(),
+        condition: to_condition(metar),
+        observation_time: Some(metar.observation_time),
+        ..Weather::new(
+            SOURCE_URI.into(),
+            request.name.clone(),
+            coordinates,
+            temperature,
+        )
+    })
+}
+
+impl WeatherProvider for AviationWeather {
+    fn id(&self) -> &str {
+        SOURCE_URI
+    }
+
+    fn for_coordinates(
+        &self,
+        client: &Client,
+        cache: &HttpRequestCache,
+        request: &WeatherRequest<Coordinates>,
+    ) -> anyhow::Result<Weather> {
+        let latitude = f64::from(request.query.latitude.clone());
+        let longitude = f64::from(request.query.longitude.clone());
+
+        let station_info_url = Url::parse_with_params(
+            STATION_INFO_URL,
+            &[
+                (
+                    "bbox",
+                    format!(
+                        "{},{},{},{}",
+                        latitude - SEARCH_RADIUS_DEGREES,
+                        longitude - SEARCH_RADIUS_DEGREES,
+                        latitude + SEARCH_RADIUS_DEGREES,
+                        longitude + SEARCH_RADIUS_DEGREES
+                    ),
+                ),
+                ("format", "json".into()),
+            ],
+        )?;
+        let airports: Vec<Airport> = request_cached(&HttpCacheRequest::new_json_request(
+            SOURCE_URI,
+            client,
+            cache,
+            &Method::GET,
+            &station_info_url,
+        ))?;
+
+        let airport = find_closest_reporting_airport(&request.query, &airports)?;
+        trace!("Found closest airport {:?}", airport);
+
+        let icao_id = airport.icao_id.clone().unwrap_or_default();
+        let metar_url =
+            Url::parse_with_params(METAR_URL, &[("ids", icao_id.as_str()), ("format", "raw")])?;
+        let metar = request_cached(&HttpCacheRequest::new(
+            SOURCE_URI,
+            client,
+            cache,
+            &Method::GET,
+            &metar_url,
+            |body| {
+                let report = str::from_utf8(body)?
+                    .lines()
+                    .find(|line| !line.trim().is_empty())
+                    .ok_or_else(|| anyhow!("No current METAR report"))?;
+
+                Metar::parse(report, Utc::now())
+            },
+        ))?;
+
+        to_weather(request, airport, &metar)
+    }
+
+    fn refresh_interval(&self) -> Duration {
+        self.cache.refresh_interval
+    }
+
+    // Station info and METAR report
+    fn cache_cardinality(&self) -> usize {
+        2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod find_closest_reporting_airport {
+        use crate::providers::aviation_weather::{find_closest_reporting_airport, Airport};
+        use crate::providers::testing::{json_fixture, request};
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn skip_airports_without_metar() {
+            let airports: Vec<Airport> = json_fixture("aviation_weather/stationinfo.json");
+
+            assert_eq!(
+                find_closest_reporting_airport(
+                    &request("New York", 40.7128_f64, -74.0060_f64).query,
+                    &airports,
+                )
+                .expect("Airport found"),
+                &Airport {
+                    icao_id: Some("KLGA".into()),
+                    site: "New York/LaGuardia Arpt".into(),
+                    lat: 40.7772_f64,
+                    lon: -73.8726_f64,
+                    site_type: vec!["METAR".into(), "TAF".into()],
+                }
+            );
+        }
+    }
+
+    mod to_weather {
+        use crate::providers::aviation_weather::{to_weather, Airport};
+        use crate::providers::metar::Metar;
+        use crate::providers::testing::{fixture, request};
+        use crate::providers::units::{
+            Condition, Coordinates, InchesOfMercury, Knots, Miles, Ratio, ToMetersPerSecond,
+        };
+        use crate::providers::{Weather, WeatherCondition};
+        use chrono::{DateTime, Utc};
+        use pretty_assertions::assert_eq;
+
+        fn time(rfc3339: &str) -> DateTime<Utc> {
+            DateTime::parse_from_rfc3339(rfc3339)
+                .expect("Static value")
+                .with_timezone(&Utc)
+        }
+
+        #[test]
+        fn map_metar_report() {
+            assert_eq!(
+                to_weather(
+                    &request("New York", 40.7128_f64, -74.0060_f64),
+                    &Airport {
+                        icao_id: Some("KLGA".into()),
+                        site: "New York/LaGuardia Arpt".into(),
+                        lat: 40.7772_f64,
+                        lon: -73.8726_f64,
+                        site_type: vec!["METAR".into(), "TAF".into()],
+                    },
+                    &Metar::parse(
+                        &String::from_utf8_lossy(&fixture("aviation_weather/metar.txt")),
+                        time("2024-06-01T13:05:00Z"),
+                    )
+                    .expect("Fixture is valid"),
+                )
+                .expect("Mapping works"),
+                Weather {
+                    city: Some("New York/LaGuardia Arpt".into()),
+                    distance: Some(13_325.720_695_116_068_f64.into()),
+                    relative_humidity: Some(Ratio::Fraction(0.535_695_878_148_220_8_f64)),
+                    dew_point: Some(13.9_f32.into()),
+                    sea_level_pressure: Some(InchesOfMercury::from(29.92_f64).to_hectopascal()),
+                    wind_speed: Some(Knots::from(10_f64).to_meters_per_second()),
+                    wind_gust_speed: Some(Knots::from(18_f64).to_meters_per_second()),
+                    wind_direction: Some(220_f64.into()),
+                    cloud_cover: Some(Ratio::Fraction(0.875_f64)),
+                    visibility: Some(Miles::from(10_f64).to_meters()),
+                    condition: Some(WeatherCondition {
+                        condition: Condition::Rain,
+                        code: "-RA".into(),
+                    }),
+                    observation_time: Some(time("2024-06-01T12:51:00Z")),
+                    ..Weather::new(
+                        "gov.aviationweather".into(),
+                        "New York".into(),
+                        Coordinates {
+                            latitude: 40.7772_f64.into(),
+                            longitude: (-73.8726_f64).into(),
+                        },
+                        23.9_f32.into(),
+                    )
+                }
+            );
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use crate::providers::http_request::{request_cached, Configuration, HttpCacheRequest};
+use crate::providers::units::{
+    Celsius, Condition, Coordinates, Degrees, Hectopascal, KilometersPerHour, Meters, Millimeters,
+    Ratio, ToMetersPerSecond,
+};
+use crate::providers::{
+    calculate_distance, HttpRequestCache, Precipitation, Sunshine, Weather, WeatherCondition,
+    WeatherProvider, WeatherRequest,
+};
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use reqwest::blocking::Client;
+use reqwest::{Method, Url};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const SOURCE_URI: &str = "dev.brightsky";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BrightSky {
+    // Point to a self-hosted instance instead of the public API
+    #[serde(default = "default_base_url")]
+    base_url: String,
+    #[serde(flatten)]
+    cache: Configuration,
+}
+
+fn default_base_url() -> String {
+    "https://api.brightsky.dev".into()
+}
+
+#[derive(Deserialize, Debug)]
+struct BrightSkyResponse {
+    weather: BrightSkyWeather,
+    sources: Vec<BrightSkySource>,
+}
+
+// Values are aggregated over the last 10, 30 or 60 minutes as indicated by the suffix
+#[derive(Deserialize, Debug)]
+struct BrightSkyWeather {
+    source_id: u64,
+    timestamp: DateTime<Utc>,
+    temperature: Option<Celsius>,
+    dew_point: Option<Celsius>,
+    relative_humidity: Option<Ratio>,
+    pressure_msl: Option<Hectopascal>,
+    wind_speed_10: Option<KilometersPerHour>,
+    wind_gust_speed_10: Option<KilometersPerHour>,
+    wind_direction_10: Option<Degrees>,
+    precipitation_60: Option<Millimeters>,
+    cloud_cover: Option<Ratio>,
+    visibility: Option<Meters>,
+    // Minutes of sunshine within the last hour
+    sunshine_60: Option<f64>,
+    icon: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct BrightSkySource {
+    id: u64,
+    lat: f64,
+    lon: f64,
+    station_name: String,
+}
+
+// See https://brightsky.dev/docs/#/operations/getCurrentWeather
+fn condition_from_icon(icon: &str) -> Condition {
+    match icon {
+        "clear-day" | "clear-night" => Condition::Clear,
+        "partly-cloudy-day" | "partly-cloudy-night" => Condition::PartlyCloudy,
+        "cloudy" => Condition::Cloudy,
+        "fog" => Condition::Fog,
+        "rain" => Condition::Rain,
+        "sleet" => Condition::Sleet,
+        "snow" => Condition::Snow,
+        "thunderstorm" => Condition::Thunderstorm,
+        _ => Condition::Unknown,
+    }
+}
+
+fn to_weather(
+    request: &WeatherRequest<Coordinates>,
+    response: BrightSkyResponse,
+) -> anyhow::Result<Weather> {
+    let weather = response.weather;
+    // Single values may be filled in from fallback sources, the primary source is the station
+    let source = response
+        .sources
+        .into_iter()
+        .find(|source| source.id == weather.source_id)
+        .ok_or_else(|| anyhow!("Source {} not found in response", weather.source_id))?;
+    let coordinates = Coordinates {
+        latitude: source.lat.into(),
+        longitude: source.lon.into(),
+    };
+
+    Ok(Weather {
+        city: Some(source.station_name),
+        distance: Some(calculate_distance(&request.query, &coordinates)),
+        relative_humidity: weather.relative_humidity,
+        dew_point: weather.dew_point,
+        sea_level_pressure: weather.pressure_msl,
+        wind_speed: weather
+            .wind_speed_10
+            .map(|speed| speed.to_meters_per_second()),
+        wind_gust_speed: weather
+            .wind_gust_speed_10
+            .map(|speed| speed.to_meters_per_second()),
+        wind_direction: weather.wind_direction_10,
+        precipitation: weather.precipitation_60.map(|amount| Precipitation {
+            amount,
+            period: Duration::from_secs(60 * 60),
+        }),
+        cloud_cover: weather.cloud_cover,
+        visibility: weather.visibility,
+        sunshine: weather
+            .sunshine_60
+            .and_then(|minutes| Duration::try_from_secs_f64(minutes * 60.0_f64).ok())
+            .map(|duration| Sunshine {
+                duration,
+                period: Duration::from_secs(60 * 60),
+            }),
+        condition: weather.icon.map(|icon| WeatherCondition {
+            condition: condition_from_icon(&icon),
+            code: icon,
+        }),
+        observation_time: Some(weather.timestamp),
+        ..Weather::new(
+            SOURCE_URI.into(),
+            request.name.clone(),
+            coordinates,
+            weather
+                .temperature
+                .ok_or_else(|| anyhow!("No temperature reported for {}", request.name))?,
+        )
+    })
+}
+
+impl WeatherProvider for BrightSky {
+    fn id(&self) -> &str {
+        SOURCE_URI
+    }
+
+    fn for_coordinates(
+        &self,
+        client: &Client,
+        cache: &HttpRequestCache,
+        request: &WeatherRequest<Coordinates>,
+    ) -> anyhow::Result<Weather> {
+        let url = Url::parse_with_params(
+            &format!("{}/current_weather", self.base_url.trim_end_matches('/')),
+            &[
+                ("lat", request.query.latitude.to_string()),
+                ("lon", request.query.longitude.to_string()),
+            ],
+        )?;
+
+        let response: BrightSkyResponse = request_cached(&HttpCacheRequest::new_json_request(
+            SOURCE_URI,
+            client,
+            cache,
+            &Method::GET,
+            &url,
+        ))?;
+
+        to_weather(request, response)
+    }
+
+    fn refresh_interval(&self) -> Duration {
+        self.cache.refresh_interval
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod to_weather {
+        use crate::providers::bright_sky::{to_weather, BrightSkyResponse};
+        use crate::providers::testing::{json_fixture, request};
+        use crate::providers::units::{Condition, Coordinates, Ratio};
+        use crate::providers::{Precipitation, Sunshine, Weather, WeatherCondition};
+        use chrono::{DateTime, Utc};
+        use pretty_assertions::assert_eq;
+        use std::time::Duration;
+
+        #[test]
+        fn map_current_weather() {
+            assert_eq!(
+                to_weather(
+                    &request("Berlin", 52.52_f64, 13.405_f64),
+                    json_fixture("bright_sky/current_weather.json"),
+                )
+                .expect("Mapping works"),
+                Weather {
+                    city: Some("Berlin-Tempelhof".into()),
+                    distance: Some(5_841.042_267_840_092_f64.into()),
+                    relative_humidity: Some(Ratio::Percentage(57_f64)),
+                    dew_point: Some(11.5_f32.into()),
+                    sea_level_pressure: Some(1012.5_f64.into()),
+                    wind_speed: Some(5_f64.into()),
+                    wind_gust_speed: Some(10_f64.into()),
+                    wind_direction: Some(250_f64.into()),
+                    precipitation: Some(Precipitation {
+                        amount: 0.2_f64.into(),
+                        period: Duration::from_secs(3600),
+                    }),
+                    cloud_cover: Some(Ratio::Percentage(75_f64)),
+                    visibility: Some(42_000_f64.into()),
+                    sunshine: Some(Sunshine {
+                        duration: Duration::from_secs(1800),
+                        period: Duration::from_secs(3600),
+                    }),
+                    condition: Some(WeatherCondition {
+                        condition: Condition::PartlyCloudy,
+                        code: "partly-cloudy-day".into(),
+                    }),
+                    observation_time: Some(
+                        DateTime::parse_from_rfc3339("2024-06-01T12:30:00Z")
+                            .expect("Static value")
+                            .with_timezone(&Utc)
+                    ),
+                    ..Weather::new(
+                        "dev.brightsky".into(),
+                        "Berlin".into(),
+                        Coordinates {
+                            latitude: 52.4675_f64.into(),
+                            longitude: 13.4021_f64.into(),
+                        },
+                        20.2_f32.into(),
+                    )
+                }
+            );
+        }
+
+        #[test]
+        fn unknown_source_is_an_error() {
+            let mut response: BrightSkyResponse = json_fixture("bright_sky/current_weather.json");
+            response.weather.source_id = 1;
+
+            assert_eq!(
+                to_weather(&request("Berlin", 52.52_f64, 13.405_f64), response)
+                    .expect_err("Nothing to map")
+                    .to_string(),
+                "Source 1 not found in response"
+            );
+        }
+    }
+
+    mod condition_from_icon {
+        use crate::providers::bright_sky::condition_from_icon;
+        use crate::providers::units::Condition;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn map_icons() {
+            assert_eq!(
+                ["clear-night", "cloudy", "sleet", "wind"].map(condition_from_icon),
+                [
+                    Condition::Clear,
+                    Condition::Cloudy,
+                    Condition::Sleet,
+                    Condition::Unknown,
+                ]
+            );
+        }
+    }
+}
@@ -1,24 +1,96 @@
 use crate::providers::http_request::{request_cached, Configuration, HttpCacheRequest};
-use crate::providers::units::{Celsius, Coordinate, Coordinates, Ratio};
+use crate::providers::station_network::{
+    decode_latin1, minute_precision_date_format, parse_measurement_data_csv,
+};
+use crate::providers::units::{
+    Celsius, Centimeters, Coordinate, Coordinates, Degrees, Hectopascal, JoulesPerSquareCentimeter,
+    MetersPerSecond, Millimeters, Ratio, WattsPerSquareMeter,
+};
 use crate::providers::{
-    calculate_distance, HttpRequestCache, Weather, WeatherProvider, WeatherRequest,
+    calculate_distance, find_closest, HttpRequestCache, Precipitation, Sunshine,
+    TemperatureAtHeight, Weather, WeatherProvider, WeatherRequest,
 };
 use anyhow::{anyhow, Context};
-use chrono::Utc;
-use const_format::concatcp;
+use chrono::{NaiveDate, TimeDelta, Utc};
 use csv::Trim;
-use geo::{Closest, ClosestPoint, MultiPoint, Point};
-use log::{debug, trace};
+use log::{debug, trace, warn};
 use reqwest::blocking::Client;
 use reqwest::{Method, Url};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
 use std::io::{Cursor, Read};
 use std::time::Duration;
 use zip::ZipArchive;
 
 const SOURCE_URI: &str = "de.dwd";
-const BASE_URL: &str = "https://opendata.dwd.de/climate_environment/CDC/observations_germany/climate/10_minutes/air_temperature/now";
-const STATION_LIST_URL: &str = concatcp!(BASE_URL, "/zehn_now_tu_Beschreibung_Stationen.txt");
+const BASE_URL: &str =
+    "https://opendata.dwd.de/climate_environment/CDC/observations_germany/climate";
+
+struct Dataset {
+    path: &'static str,
+    station_list: &'static str,
+    archive_prefix: &'static str,
+    archive_suffix: &'static str,
+}
+
+impl Dataset {
+    fn station_list_url(&self) -> anyhow::Result<Url> {
+        Ok(Url::parse(&format!(
+            "{BASE_URL}/{}/{}",
+            self.path, self.station_list
+        ))?)
+    }
+
+    fn measurement_url(&self, station_id: &str) -> anyhow::Result<Url> {
+        Ok(Url::parse(&format!(
+            "{BASE_URL}/{}/{}_{station_id}_{}.zip",
+            self.path, self.archive_prefix, self.archive_suffix
+        ))?)
+    }
+}
+
+const AIR_TEMPERATURE: Dataset = Dataset {
+    path: "10_minutes/air_temperature/now",
+    station_list: "zehn_now_tu_Beschreibung_Stationen.txt",
+    archive_prefix: "10minutenwerte_TU",
+    archive_suffix: "now",
+};
+
+const WIND: Dataset = Dataset {
+    path: "10_minutes/wind/now",
+    station_list: "zehn_now_ff_Beschreibung_Stationen.txt",
+    archive_prefix: "10minutenwerte_wind",
+    archive_suffix: "now",
+};
+
+const PRECIPITATION: Dataset = Dataset {
+    path: "10_minutes/precipitation/now",
+    station_list: "zehn_now_rr_Beschreibung_Stationen.txt",
+    archive_prefix: "10minutenwerte_nieder",
+    archive_suffix: "now",
+};
+
+const SOLAR: Dataset = Dataset {
+    path: "10_minutes/solar/now",
+    station_list: "zehn_now_SOLAR_Beschreibung_Stationen.txt",
+    archive_prefix: "10minutenwerte_SOLAR",
+    archive_suffix: "now",
+};
+
+// Snow is only observed once a day, recent data is updated daily
+const DAILY_PRECIPITATION: Dataset = Dataset {
+    path: "daily/more_precip/recent",
+    station_list: "RR_Tageswerte_Beschreibung_Stationen.txt",
+    archive_prefix: "tageswerte_RR",
+    archive_suffix: "akt",
+};
+
+const MEASUREMENT_INTERVAL: Duration = Duration::from_secs(60 * 10);
+const DAILY_MEASUREMENT_INTERVAL: Duration = Duration::from_secs(60 * 60 * 24);
+// TM5_10 is measured 5 cm above ground
+const GROUND_TEMPERATURE_HEIGHT_METERS: f64 = 0.05;
+const SECONDS_PER_HOUR: f64 = 3600.0;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DeutscherWetterdienst {
@@ -99,39 +171,16 @@ fn find_closest_weather_station<'stations>(
     coords: &Coordinates,
     weather_stations: &'stations [WeatherStation],
 ) -> anyhow::Result<&'stations WeatherStation> {
-    let point: Point<f64> = Point::new(
-        coords.longitude.clone().into(),
-        coords.latitude.clone().into(),
-    );
-    let points = MultiPoint::new(
-        weather_stations
-            .iter()
-            .map(|s| Point::new(s.longitude.clone().into(), s.latitude.clone().into()))
-            .collect(),
-    );
-
-    match points.closest_point(&point) {
-        Closest::SinglePoint(closest_point) | Closest::Intersection(closest_point) => {
-            let matching_station = weather_stations
-                .iter()
-                .find(|station| {
-                    station.longitude == closest_point.x().into()
-                        && station.latitude == closest_point.y().into()
-                })
-                .ok_or_else(|| anyhow!("Could not find matching station"))?;
-
-            Ok(matching_station)
-        }
-        Closest::Indeterminate => Err(anyhow!("Could not find closest point")),
-    }
+    find_closest(coords, weather_stations, |station| Coordinates {
+        latitude: station.latitude.clone(),
+        longitude: station.longitude.clone(),
+    })
 }
 
 fn is_measurement_file(file_name: &str) -> bool {
     let file_path = std::path::Path::new(file_name);
 
-    file_name
-        .to_ascii_lowercase()
-        .starts_with("produkt_zehn_now")
+    file_name.to_ascii_lowercase().starts_with("produkt_")
         && file_path
             .extension()
             .is_some_and(|ext| ext.eq_ignore_ascii_case("txt"))
@@ -169,68 +218,204 @@ struct Measurement {
     _station_id: String,
     #[serde(rename = "MESS_DATUM", with = "minute_precision_date_format")]
     time: chrono::DateTime<Utc>,
-    #[serde(rename = "PP_10")]
-    _atmospheric_pressure: String,
+    #[serde(rename = "PP_10", with = "optional_measurement")]
+    atmospheric_pressure: Option<Hectopascal>,
     #[serde(rename = "TT_10")]
     temperature_200_centimers: Celsius,
-    #[serde(rename = "TM5_10")]
-    _temperature_5_centimeters: Celsius,
+    #[serde(rename = "TM5_10", with = "optional_measurement")]
+    temperature_5_centimeters: Option<Celsius>,
     #[serde(rename = "RF_10")]
     relative_humidity_200_centimeters: Ratio,
-    #[serde(rename = "TD_10")]
-    _dew_point_temperature_200_centimeters: Celsius,
+    #[serde(rename = "TD_10", with = "optional_measurement")]
+    dew_point_temperature_200_centimeters: Option<Celsius>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+struct WindMeasurement {
+    #[serde(rename = "MESS_DATUM", with = "minute_precision_date_format")]
+    _time: chrono::DateTime<Utc>,
+    #[serde(rename = "FF_10", with = "optional_measurement")]
+    wind_speed: Option<MetersPerSecond>,
+    #[serde(rename = "DD_10", with = "optional_measurement")]
+    wind_direction: Option<Degrees>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+struct PrecipitationMeasurement {
+    #[serde(rename = "MESS_DATUM", with = "minute_precision_date_format")]
+    time: chrono::DateTime<Utc>,
+    #[serde(rename = "RWS_10", with = "optional_measurement")]
+    precipitation: Option<Millimeters>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+struct SolarMeasurement {
+    #[serde(rename = "MESS_DATUM", with = "minute_precision_date_format")]
+    _time: chrono::DateTime<Utc>,
+    // Radiation is reported as energy received over the measurement interval
+    #[serde(rename = "DS_10", with = "optional_measurement")]
+    diffuse_radiation_energy: Option<JoulesPerSquareCentimeter>,
+    #[serde(rename = "GS_10", with = "optional_measurement")]
+    global_radiation_energy: Option<JoulesPerSquareCentimeter>,
+    #[serde(rename = "SD_10", with = "optional_measurement")]
+    sunshine_duration_hours: Option<f64>,
+}
+
+impl SolarMeasurement {
+    fn global_radiation(&self) -> Option<WattsPerSquareMeter> {
+        self.global_radiation_energy
+            .map(|energy| energy.to_watts_per_square_meter(MEASUREMENT_INTERVAL))
+    }
+
+    fn diffuse_radiation(&self) -> Option<WattsPerSquareMeter> {
+        self.diffuse_radiation_energy
+            .map(|energy| energy.to_watts_per_square_meter(MEASUREMENT_INTERVAL))
+    }
+
+    // Direct radiation on a horizontal surface is the part of global radiation that is not diffuse
+    fn direct_radiation(&self) -> Option<WattsPerSquareMeter> {
+        let global = f64::from(self.global_radiation()?);
+        let diffuse = f64::from(self.diffuse_radiation()?);
+
+        Some((global - diffuse).max(0.0_f64).into())
+    }
+
+    fn sunshine(&self) -> Option<Sunshine> {
+        let hours = self.sunshine_duration_hours?;
+
+        Some(Sunshine {
+            duration: Duration::try_from_secs_f64(hours * SECONDS_PER_HOUR).ok()?,
+            period: MEASUREMENT_INTERVAL,
+        })
+    }
 }
 
-mod minute_precision_date_format {
-    use chrono::{DateTime, NaiveDateTime, Utc};
-    use serde::de::Error;
-    use serde::{self, Deserialize, Deserializer};
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+struct SnowMeasurement {
+    #[serde(rename = "SH_TAG", with = "optional_measurement")]
+    snow_depth: Option<Centimeters>,
+    #[serde(rename = "NSH_TAG", with = "optional_measurement")]
+    fresh_snow: Option<Centimeters>,
+}
+
+// MESS_DATUM marks the end of the interval, so the measurement at 00:00 belongs to the previous day
+fn measurement_day(time: &chrono::DateTime<Utc>) -> Option<NaiveDate> {
+    TimeDelta::from_std(MEASUREMENT_INTERVAL)
+        .ok()
+        .and_then(|interval| time.checked_sub_signed(interval))
+        .map(|start| start.date_naive())
+}
+
+fn sum_daily_precipitation(measurements: &[PrecipitationMeasurement]) -> Option<Millimeters> {
+    let day = measurement_day(&measurements.last()?.time)?;
 
-    const FORMAT: &str = "%Y%m%d%H%M";
+    Some(
+        measurements
+            .iter()
+            .filter(|measurement| measurement_day(&measurement.time) == Some(day))
+            .filter_map(|measurement| measurement.precipitation)
+            .map(f64::from)
+            .sum::<f64>()
+            .into(),
+    )
+}
+
+mod optional_measurement {
+    use crate::providers::station_network::deserialize_optional_measurement;
+    use serde::de::DeserializeOwned;
+    use serde::Deserializer;
 
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+    // DWD marks missing measurements with -999
+    const MISSING_VALUE: &str = "-999";
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
     where
         D: Deserializer<'de>,
+        T: DeserializeOwned,
     {
-        let s = String::deserialize(deserializer)?;
-        NaiveDateTime::parse_from_str(&s, FORMAT)
-            .map(|v| v.and_utc())
-            .map_err(Error::custom)
+        deserialize_optional_measurement(deserializer, MISSING_VALUE)
     }
 }
 
-fn parse_measurement_data_csv(data: &String) -> anyhow::Result<Vec<Measurement>> {
-    let reader = csv::ReaderBuilder::new()
-        .delimiter(b';')
-        .double_quote(false)
-        .trim(Trim::All)
-        .from_reader(data.as_bytes());
-
-    Ok(reader
-        .into_deserialize::<Measurement>()
-        .collect::<Result<_, _>>()?)
+fn request_cached_weather_stations(
+    cache: &HttpRequestCache,
+    client: &Client,
+    dataset: &Dataset,
+) -> anyhow::Result<Vec<WeatherStation>> {
+    request_cached(&HttpCacheRequest::new(
+        SOURCE_URI,
+        client,
+        cache,
+        &Method::GET,
+        &dataset.station_list_url()?,
+        |body| parse_weather_station_list_csv(&decode_latin1(body)),
+    ))
 }
 
 fn reqwest_cached_measurement_csv(
     cache: &HttpRequestCache,
     client: &Client,
-    station_id: &String,
+    dataset: &Dataset,
+    station_id: &str,
 ) -> anyhow::Result<String> {
-    let method = Method::GET;
-    let url = Url::parse(&format!(
-        "{BASE_URL}/10minutenwerte_TU_{station_id}_now.zip"
-    ))?;
-
     request_cached(&HttpCacheRequest::new(
         SOURCE_URI,
         client,
         cache,
-        &method,
-        &url,
+        &Method::GET,
+        &dataset.measurement_url(station_id)?,
         |body| read_measurement_data_zip(body),
     ))
 }
 
+fn request_measurements<T: DeserializeOwned>(
+    cache: &HttpRequestCache,
+    client: &Client,
+    dataset: &Dataset,
+    station_id: &str,
+) -> anyhow::Result<Vec<T>> {
+    let measurement_csv = reqwest_cached_measurement_csv(cache, client, dataset, station_id)?;
+
+    parse_measurement_data_csv(&measurement_csv)
+}
+
+// Not every station measures every parameter so supplementary datasets are only requested for
+// stations listed in them. Failures are logged but do not fail the whole request.
+fn request_supplementary_measurements<T: DeserializeOwned + Debug>(
+    cache: &HttpRequestCache,
+    client: &Client,
+    dataset: &Dataset,
+    station: &WeatherStation,
+) -> Vec<T> {
+    let measurements = request_cached_weather_stations(cache, client, dataset)
+        .and_then(|stations| {
+            if stations.iter().any(|s| s.station_id == station.station_id) {
+                request_measurements(cache, client, dataset, &station.station_id)
+            } else {
+                debug!(
+                    "Station {} is not part of dataset {}",
+                    station.station_id, dataset.path
+                );
+                Ok(vec![])
+            }
+        })
+        .unwrap_or_else(|e| {
+            warn!(
+                "Could not fetch {} measurements for station {}: {e}",
+                dataset.path, station.station_id
+            );
+            vec![]
+        });
+
+    debug!(
+        "Using latest {} measurement {:?}",
+        dataset.path,
+        measurements.last()
+    );
+
+    measurements
+}
+
 impl WeatherProvider for DeutscherWetterdienst {
     fn id(&self) -> &str {
         SOURCE_URI
@@ -242,55 +427,89 @@ impl WeatherProvider for DeutscherWetterdienst {
         cache: &HttpRequestCache,
         request: &WeatherRequest<Coordinates>,
     ) -> anyhow::Result<Weather> {
-        let stations = request_cached(&HttpCacheRequest::new(
-            SOURCE_URI,
-            client,
-            cache,
-            &Method::GET,
-            &Url::parse(STATION_LIST_URL)?,
-            |body| {
-                let str: String = body
-                    .iter()
-                    .filter_map(|&c| char::from_u32(c.into()))
-                    .collect();
-
-                parse_weather_station_list_csv(&str)
-            },
-        ))?;
+        let stations = request_cached_weather_stations(cache, client, &AIR_TEMPERATURE)?;
 
         let closest_station = find_closest_weather_station(&request.query, &stations)?;
         trace!("Found closest weather station {:?}", closest_station);
-        let measurement_csv =
-            reqwest_cached_measurement_csv(cache, client, &closest_station.station_id)?;
-        let measurements = parse_measurement_data_csv(&measurement_csv)?;
 
-        match &*measurements {
-            [.., latest_measurement] => {
-                debug!(
-                    "Using latest measurement from {}: {:?}",
-                    latest_measurement.time,
-                    latest_measurement.clone()
-                );
+        let latest_measurement: Measurement =
+            request_measurements(cache, client, &AIR_TEMPERATURE, &closest_station.station_id)?
+                .pop()
+                .ok_or_else(|| anyhow!("Empty measurement list"))?;
+        debug!(
+            "Using latest measurement from {}: {:?}",
+            latest_measurement.time,
+            latest_measurement.clone()
+        );
+
+        let wind: Option<WindMeasurement> =
+            request_supplementary_measurements(cache, client, &WIND, closest_station).pop();
+        let precipitation: Vec<PrecipitationMeasurement> =
+            request_supplementary_measurements(cache, client, &PRECIPITATION, closest_station);
+        let solar: Option<SolarMeasurement> =
+            request_supplementary_measurements(cache, client, &SOLAR, closest_station).pop();
+        let snow: Option<SnowMeasurement> = request_supplementary_measurements(
+            cache,
+            client,
+            &DAILY_PRECIPITATION,
+            closest_station,
+        )
+        .pop();
+
+        let coordinates = Coordinates {
+            latitude: closest_station.latitude.clone(),
+            longitude: closest_station.longitude.clone(),
+        };
+
+        let distance = calculate_distance(&request.query, &coordinates);
 
-                let coordinates = Coordinates {
-                    latitude: closest_station.latitude.clone(),
-                    longitude: closest_station.longitude.clone(),
-                };
-
-                let distance = calculate_distance(&request.query, &coordinates);
-
-                Ok(Weather {
-                    source: SOURCE_URI.into(),
-                    location: request.name.clone(),
-                    city: Some(closest_station.name.clone()),
-                    coordinates,
-                    distance: Some(distance),
-                    temperature: latest_measurement.temperature_200_centimers,
-                    relative_humidity: Some(latest_measurement.relative_humidity_200_centimeters),
+        Ok(Weather {
+            city: Some(closest_station.name.clone()),
+            distance: Some(distance),
+            temperatures_at_height: latest_measurement
+                .temperature_5_centimeters
+                .map(|temperature| TemperatureAtHeight {
+                    height: GROUND_TEMPERATURE_HEIGHT_METERS.into(),
+                    temperature,
                 })
-            }
-            [] => Err(anyhow!("Empty measurement list")),
-        }
+                .into_iter()
+                .collect(),
+            relative_humidity: Some(latest_measurement.relative_humidity_200_centimeters),
+            dew_point: latest_measurement.dew_point_temperature_200_centimeters,
+            station_pressure: latest_measurement.atmospheric_pressure,
+            wind_speed: wind.as_ref().and_then(|w| w.wind_speed),
+            wind_direction: wind.as_ref().and_then(|w| w.wind_direction),
+            precipitation: precipitation
+                .last()
+                .and_then(|p| p.precipitation)
+                .map(|amount| Precipitation {
+                    amount,
+                    period: MEASUREMENT_INTERVAL,
+                }),
+            daily_precipitation: sum_daily_precipitation(&precipitation),
+            snow_depth: snow
+                .as_ref()
+                .and_then(|s| s.snow_depth)
+                .map(Centimeters::to_meters),
+            snowfall: snow
+                .as_ref()
+                .and_then(|s| s.fresh_snow)
+                .map(|depth| Precipitation {
+                    amount: depth.to_millimeters(),
+                    period: DAILY_MEASUREMENT_INTERVAL,
+                }),
+            global_radiation: solar.as_ref().and_then(SolarMeasurement::global_radiation),
+            direct_radiation: solar.as_ref().and_then(SolarMeasurement::direct_radiation),
+            diffuse_radiation: solar.as_ref().and_then(SolarMeasurement::diffuse_radiation),
+            sunshine: solar.as_ref().and_then(SolarMeasurement::sunshine),
+            observation_time: Some(latest_measurement.time),
+            ..Weather::new(
+                SOURCE_URI.into(),
+                request.name.clone(),
+                coordinates,
+                latest_measurement.temperature_200_centimers,
+            )
+        })
     }
 
     fn refresh_interval(&self) -> Duration {
@@ -298,7 +517,7 @@ impl WeatherProvider for DeutscherWetterdienst {
     }
 
     fn cache_cardinality(&self) -> usize {
-        2
+        10
     }
 }
 
@@ -407,25 +626,50 @@ broken\n\
     }
 
     mod parse_measurement_data_csv {
-        use crate::providers::deutscher_wetterdienst::{parse_measurement_data_csv, Measurement};
+        use crate::providers::deutscher_wetterdienst::{
+            parse_measurement_data_csv, sum_daily_precipitation, Measurement,
+            PrecipitationMeasurement, SnowMeasurement, SolarMeasurement, WindMeasurement,
+        };
         use crate::providers::units::Ratio;
         use chrono::{DateTime, Utc};
         use pretty_assertions::assert_eq;
+        use std::time::Duration;
+
+        #[test]
+        fn parse_example_with_pressure() {
+            assert_eq!(
+                &*parse_measurement_data_csv::<Measurement>(
+                    "STATIONS_ID;MESS_DATUM;  QN;PP_10;TT_10;TM5_10;RF_10;TD_10;eor\n\
+            379;202301120000;    2;  955.3;   5.1;   2.5;  82.6;   2.4;eor",
+                )
+                .expect("Parsing works"),
+                [Measurement {
+                    _station_id: "379".into(),
+                    atmospheric_pressure: Some(955.3_f64.into()),
+                    dew_point_temperature_200_centimeters: Some(2.4_f32.into()),
+                    temperature_5_centimeters: Some(2.5_f32.into()),
+                    time: DateTime::parse_from_rfc3339("2023-01-12T00:00:00Z")
+                        .expect("Static value")
+                        .with_timezone(&Utc {}),
+                    temperature_200_centimers: 5.1.into(),
+                    relative_humidity_200_centimeters: Ratio::Percentage(82.6),
+                }]
+            );
+        }
 
         #[test]
         fn parse_example() {
             assert_eq!(
-                &*parse_measurement_data_csv(
-                    &"STATIONS_ID;MESS_DATUM;  QN;PP_10;TT_10;TM5_10;RF_10;TD_10;eor\n\
-            379;202301120000;    2;   -999;   5.1;   2.5;  82.6;   2.4;eor"
-                        .to_owned(),
+                &*parse_measurement_data_csv::<Measurement>(
+                    "STATIONS_ID;MESS_DATUM;  QN;PP_10;TT_10;TM5_10;RF_10;TD_10;eor\n\
+            379;202301120000;    2;   -999;   5.1;   2.5;  82.6;   2.4;eor",
                 )
                 .expect("Parsing works"),
                 [Measurement {
                     _station_id: "379".into(),
-                    _atmospheric_pressure: "-999".into(),
-                    _dew_point_temperature_200_centimeters: 2.4.into(),
-                    _temperature_5_centimeters: 2.5.into(),
+                    atmospheric_pressure: None,
+                    dew_point_temperature_200_centimeters: Some(2.4_f32.into()),
+                    temperature_5_centimeters: Some(2.5_f32.into()),
                     time: DateTime::parse_from_rfc3339("2023-01-12T00:00:00Z")
                         .expect("Static value")
                         .with_timezone(&Utc {}),
@@ -434,5 +678,123 @@ broken\n\
                 }]
             );
         }
+
+        #[test]
+        fn parse_wind_example() {
+            assert_eq!(
+                &*parse_measurement_data_csv::<WindMeasurement>(
+                    "STATIONS_ID;MESS_DATUM;  QN;FF_10;DD_10;eor\n\
+            379;202301120000;    3;   2.7;  240;eor\n\
+            379;202301120010;    3;   3.1; -999;eor",
+                )
+                .expect("Parsing works"),
+                [
+                    WindMeasurement {
+                        _time: DateTime::parse_from_rfc3339("2023-01-12T00:00:00Z")
+                            .expect("Static value")
+                            .with_timezone(&Utc {}),
+                        wind_speed: Some(2.7_f64.into()),
+                        wind_direction: Some(240_f64.into()),
+                    },
+                    WindMeasurement {
+                        _time: DateTime::parse_from_rfc3339("2023-01-12T00:10:00Z")
+                            .expect("Static value")
+                            .with_timezone(&Utc {}),
+                        wind_speed: Some(3.1_f64.into()),
+                        wind_direction: None,
+                    }
+                ]
+            );
+        }
+
+        #[test]
+        fn parse_precipitation_example_and_sum_daily_precipitation() {
+            let measurements = parse_measurement_data_csv::<PrecipitationMeasurement>(
+                "STATIONS_ID;MESS_DATUM;  QN;RWS_DAU_10;RWS_10;RWS_IND_10;eor\n\
+            379;202301112350;    3;   10;   1.20;   1;eor\n\
+            379;202301120000;    3;   10;   0.50;   1;eor\n\
+            379;202301120010;    3; -999;   -999;-999;eor\n\
+            379;202301120020;    3;    5;   0.25;   1;eor",
+            )
+            .expect("Parsing works");
+
+            assert_eq!(
+                measurements.last(),
+                Some(&PrecipitationMeasurement {
+                    time: DateTime::parse_from_rfc3339("2023-01-12T00:20:00Z")
+                        .expect("Static value")
+                        .with_timezone(&Utc {}),
+                    precipitation: Some(0.25_f64.into()),
+                })
+            );
+            assert_eq!(
+                sum_daily_precipitation(&measurements),
+                Some(0.25_f64.into())
+            );
+        }
+
+        #[test]
+        fn sum_daily_precipitation_until_midnight() {
+            let measurements = parse_measurement_data_csv::<PrecipitationMeasurement>(
+                "STATIONS_ID;MESS_DATUM;  QN;RWS_DAU_10;RWS_10;RWS_IND_10;eor\n\
+            379;202301112340;    3;   10;   1.20;   1;eor\n\
+            379;202301112350;    3;   10;   0.30;   1;eor\n\
+            379;202301120000;    3;   10;   0.50;   1;eor",
+            )
+            .expect("Parsing works");
+
+            assert_eq!(sum_daily_precipitation(&measurements), Some(2_f64.into()));
+        }
+
+        #[test]
+        fn parse_solar_example() {
+            let measurements = parse_measurement_data_csv::<SolarMeasurement>(
+                "STATIONS_ID;MESS_DATUM;  QN;DS_10;GS_10;SD_10;LS_10;eor\n\
+            379;202306211200;    3;   9.000;  45.000;   0.125;-999;eor\n\
+            379;202306211210;    3;   -999;  -999;   -999;-999;eor",
+            )
+            .expect("Parsing works");
+
+            let measurement = measurements.first().expect("Measurement exists");
+            assert_eq!(measurement.global_radiation(), Some(750_f64.into()));
+            assert_eq!(measurement.diffuse_radiation(), Some(150_f64.into()));
+            assert_eq!(measurement.direct_radiation(), Some(600_f64.into()));
+            assert_eq!(
+                measurement.sunshine().map(|sunshine| sunshine.duration),
+                Some(Duration::from_secs(450))
+            );
+
+            let missing = measurements.last().expect("Measurement exists");
+            assert_eq!(missing.global_radiation(), None);
+            assert_eq!(missing.direct_radiation(), None);
+            assert_eq!(missing.sunshine().map(|sunshine| sunshine.duration), None);
+        }
+
+        #[test]
+        fn parse_daily_snow_example() {
+            assert_eq!(
+                &*parse_measurement_data_csv::<SnowMeasurement>(
+                    "STATIONS_ID;MESS_DATUM;QN_6;  RS;RSF;SH_TAG;NSH_TAG;eor\n\
+            379;20240114;    3;   4.2;   7;  12;   3;eor\n\
+            379;20240115;    3;   0.0;   0;-999;-999;eor",
+                )
+                .expect("Parsing works"),
+                [
+                    SnowMeasurement {
+                        snow_depth: Some(12_f64.into()),
+                        fresh_snow: Some(3_f64.into()),
+                    },
+                    SnowMeasurement {
+                        snow_depth: None,
+                        fresh_snow: None,
+                    }
+                ]
+            );
+        }
+
+        #[test]
+        fn sum_daily_precipitation_without_measurements() {
+            assert_eq!(sum_daily_precipitation(&[]), None);
+        }
     }
 }
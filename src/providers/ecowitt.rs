@@ -0,0 +1,238 @@
+use crate::authentication::Denied;
+use crate::providers::station_report::{
+    latest_weathers, new_report_store, FieldNames, Report, ReportStore,
+};
+use crate::providers::units::Coordinates;
+use crate::providers::{HttpRequestCache, Weather, WeatherProvider, WeatherRequest};
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use log::trace;
+use once_cell::sync::Lazy;
+use reqwest::blocking::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+pub const SOURCE_URI: &str = "local.ecowitt";
+
+// Ecowitt consoles send PASSKEY, Ambient Weather consoles their MAC address
+const FIELD_NAMES: FieldNames = FieldNames {
+    station: &["PASSKEY", "MAC"],
+    indoor_temperature: "tempinf",
+    indoor_relative_humidity: "humidityin",
+    station_pressure: "baromabsin",
+    sea_level_pressure: "baromrelin",
+    precipitation_last_hour: "hourlyrainin",
+    uv_index: "uv",
+};
+
+// Per location parameter selecting a station instead of the one reporting last
+const PASSKEY_PARAMETER: &str = "passkey";
+
+static REPORTS: Lazy<ReportStore> = Lazy::new(new_report_store);
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Ecowitt {
+    // PASSKEY (Ecowitt) or MAC address (Ambient Weather) of the consoles allowed to report
+    stations: Vec<String>,
+    // Reports older than this are discarded, e.g. after the console went offline
+    #[serde(default = "default_max_age")]
+    #[serde(with = "humantime_serde")]
+    max_age: Duration,
+}
+
+const fn default_max_age() -> Duration {
+    Duration::from_secs(60 * 10)
+}
+
+// Stores the latest report of a station as pushed to the ingest endpoint
+pub fn receive(fields: &HashMap<String, String>, received: DateTime<Utc>) -> anyhow::Result<()> {
+    let report = Report::parse(fields, &FIELD_NAMES, received)?;
+    trace!("Received Ecowitt report {:?}", report);
+
+    REPORTS.insert(report.station.clone(), report);
+
+    Ok(())
+}
+
+impl Ecowitt {
+    // Consoles cannot authenticate, so only reports identifying a configured station are accepted
+    pub fn authenticate(&self, fields: &HashMap<String, String>) -> Result<(), Denied> {
+        FIELD_NAMES
+            .station
+            .iter()
+            .find_map(|key| fields.get(*key))
+            .filter(|station| {
+                self.stations
+                    .iter()
+                    .any(|accepted| accepted.eq_ignore_ascii_case(station))
+            })
+            .map(|_| ())
+            .ok_or(Denied::Forbidden)
+    }
+
+    fn latest_weathers(
+        &self,
+        request: &WeatherRequest<Coordinates>,
+    ) -> anyhow::Result<Vec<Weather>> {
+        latest_weathers(
+            SOURCE_URI,
+            &REPORTS,
+            request.parameters.get(PASSKEY_PARAMETER),
+            self.max_age,
+            request,
+        )
+    }
+}
+
+impl WeatherProvider for Ecowitt {
+    fn id(&self) -> &str {
+        SOURCE_URI
+    }
+
+    fn for_coordinates(
+        &self,
+        _client: &Client,
+        _cache: &HttpRequestCache,
+        request: &WeatherRequest<Coordinates>,
+    ) -> anyhow::Result<Weather> {
+        self.latest_weathers(request)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("No Ecowitt report"))
+    }
+
+    fn all_for_coordinates(
+        &self,
+        _client: &Client,
+        _cache: &HttpRequestCache,
+        request: &WeatherRequest<Coordinates>,
+    ) -> anyhow::Result<Vec<Weather>> {
+        self.latest_weathers(request)
+    }
+
+    fn refresh_interval(&self) -> Duration {
+        self.max_age
+    }
+
+    // Reports are pushed by the console, nothing is requested
+    fn cache_cardinality(&self) -> usize {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // As posted by a GW1100 gateway with a WS69 sensor array
+    const REPORT: &str = "PASSKEY=0123456789ABCDEF0123456789ABCDEF&stationtype=GW1100A_V2.3.1&runtime=3&dateutc=2024-06-01+12:55:02&tempinf=71.6&humidityin=48&baromrelin=29.921&baromabsin=29.786&tempf=64.4&humidity=66&winddir=250&windspeedmph=10.0&windgustmph=15.0&maxdailygust=20.8&solarradiation=436.50&uv=4&rainratein=0.000&eventrainin=0.000&hourlyrainin=0.050&dailyrainin=0.120&weeklyrainin=0.300&monthlyrainin=0.300&yearlyrainin=10.250&wh65batt=0&freq=868M&model=GW1100A";
+
+    mod authenticate {
+        use crate::authentication::Denied;
+        use crate::providers::ecowitt::tests::REPORT;
+        use crate::providers::ecowitt::Ecowitt;
+        use crate::providers::station_report::parse_query;
+        use pretty_assertions::assert_eq;
+        use std::time::Duration;
+
+        #[test]
+        fn accept_configured_stations() {
+            let provider = Ecowitt {
+                stations: vec![
+                    "0123456789ABCDEF0123456789ABCDEF".into(),
+                    "AA:BB:CC:DD:EE:FF".into(),
+                ],
+                max_age: Duration::from_secs(600),
+            };
+
+            assert_eq!(
+                [
+                    REPORT,
+                    "MAC=aa:bb:cc:dd:ee:ff&tempf=64.4",
+                    "PASSKEY=FEDCBA9876543210&tempf=64.4",
+                    "tempf=64.4",
+                ]
+                .map(|report| provider.authenticate(&parse_query(report))),
+                [
+                    Ok(()),
+                    Ok(()),
+                    Err(Denied::Forbidden),
+                    Err(Denied::Forbidden)
+                ]
+            );
+        }
+    }
+
+    mod to_weathers {
+        use crate::providers::ecowitt::tests::REPORT;
+        use crate::providers::ecowitt::{FIELD_NAMES, SOURCE_URI};
+        use crate::providers::station_report::{parse_query, to_weathers, Report};
+        use crate::providers::testing::request;
+        use crate::providers::units::{
+            Fahrenheit, InchesOfMercury, MilesPerHour, Ratio, ToCelsius, ToMetersPerSecond,
+        };
+        use crate::providers::{Precipitation, Weather};
+        use chrono::{DateTime, Utc};
+        use pretty_assertions::assert_eq;
+        use std::time::Duration;
+
+        #[test]
+        fn convert_imperial_report() {
+            let request = request("Berlin", 52.520_008_f64, 13.404_954_f64);
+            let observation_time = DateTime::parse_from_rfc3339("2024-06-01T12:55:02Z")
+                .expect("Static value")
+                .with_timezone(&Utc);
+
+            assert_eq!(
+                to_weathers(
+                    SOURCE_URI,
+                    &request,
+                    &Report::parse(
+                        &parse_query(REPORT),
+                        &FIELD_NAMES,
+                        DateTime::parse_from_rfc3339("2024-06-01T12:55:05Z")
+                            .expect("Static value")
+                            .with_timezone(&Utc),
+                    )
+                    .expect("Report is valid"),
+                ),
+                [
+                    Weather {
+                        relative_humidity: Some(Ratio::Percentage(66_f64)),
+                        station_pressure: Some(InchesOfMercury::from(29.786_f64).to_hectopascal()),
+                        sea_level_pressure: Some(
+                            InchesOfMercury::from(29.921_f64).to_hectopascal()
+                        ),
+                        wind_speed: Some(MilesPerHour::from(10_f64).to_meters_per_second()),
+                        wind_gust_speed: Some(MilesPerHour::from(15_f64).to_meters_per_second()),
+                        wind_direction: Some(250_f64.into()),
+                        precipitation: Some(Precipitation {
+                            amount: 1.27_f64.into(),
+                            period: Duration::from_secs(3600),
+                        }),
+                        precipitation_intensity: Some(0_f64.into()),
+                        uv_index: Some(4_f64),
+                        global_radiation: Some(436.5_f64.into()),
+                        observation_time: Some(observation_time),
+                        ..Weather::new(
+                            "local.ecowitt".into(),
+                            "Berlin".into(),
+                            request.query.clone(),
+                            Fahrenheit::from(64.4_f32).to_celsius(),
+                        )
+                    },
+                    Weather {
+                        relative_humidity: Some(Ratio::Percentage(48_f64)),
+                        observation_time: Some(observation_time),
+                        module: Some("Indoor".into()),
+                        ..Weather::new(
+                            "local.ecowitt".into(),
+                            "Berlin".into(),
+                            request.query.clone(),
+                            Fahrenheit::from(71.6_f32).to_celsius(),
+                        )
+                    },
+                ]
+            );
+        }
+    }
+}
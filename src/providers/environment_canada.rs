@@ -0,0 +1,371 @@
+use crate::providers::http_request::{request_cached, Configuration, HttpCacheRequest};
+use crate::providers::units::{
+    Celsius, Coordinates, Degrees, Hectopascal, Kilometers, KilometersPerHour, Millimeters, Ratio,
+    ToMetersPerSecond,
+};
+use crate::providers::{
+    calculate_distance, find_closest, HttpRequestCache, Precipitation, Weather, WeatherProvider,
+    WeatherRequest,
+};
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use log::{debug, trace};
+use reqwest::blocking::Client;
+use reqwest::{Method, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::time::Duration;
+
+const SOURCE_URI: &str = "ca.gc.weather";
+const ENDPOINT_URL: &str = "https://api.weather.gc.ca/collections/swob-realtime/items";
+
+// Stations are searched within a box of this many degrees around the location
+const SEARCH_RADIUS_DEGREES: f64 = 0.5;
+const SEARCH_LIMIT: u16 = 500;
+
+const HECTOPASCALS_PER_KILOPASCAL: f64 = 10.0;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EnvironmentCanada {
+    #[serde(flatten)]
+    cache: Configuration,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct SwobObservation {
+    station_id: String,
+    station_name: String,
+    coordinates: Coordinates,
+    time: DateTime<Utc>,
+    air_temperature: Option<f64>,
+    relative_humidity: Option<f64>,
+    dew_point: Option<f64>,
+    station_pressure: Option<Hectopascal>,
+    sea_level_pressure: Option<Hectopascal>,
+    wind_speed: Option<KilometersPerHour>,
+    wind_gust_speed: Option<KilometersPerHour>,
+    wind_direction: Option<Degrees>,
+    precipitation_last_hour: Option<Millimeters>,
+    visibility: Option<Kilometers>,
+}
+
+// Missing values are reported as strings like "MSNG" instead of numbers
+fn number(properties: &Map<String, Value>, key: &str) -> Option<f64> {
+    properties.get(key).and_then(Value::as_f64)
+}
+
+fn text(properties: &Map<String, Value>, key: &str) -> Option<String> {
+    properties
+        .get(key)
+        .and_then(Value::as_str)
+        .map(ToOwned::to_owned)
+}
+
+fn pressure(properties: &Map<String, Value>, key: &str) -> Option<Hectopascal> {
+    let value = number(properties, key)?;
+
+    match text(properties, &format!("{key}-uom")).as_deref() {
+        Some("kPa") => Some(Hectopascal::from(value * HECTOPASCALS_PER_KILOPASCAL)),
+        _ => Some(Hectopascal::from(value)),
+    }
+}
+
+fn to_observation(feature: &Value) -> Option<SwobObservation> {
+    let properties = feature.get("properties")?.as_object()?;
+    let position = feature.get("geometry")?.get("coordinates")?.as_array()?;
+
+    Some(SwobObservation {
+        station_id: text(properties, "msc_id-value")?,
+        station_name: text(properties, "stn_nam-value")?,
+        coordinates: Coordinates {
+            latitude: position.get(1)?.as_f64()?.into(),
+            longitude: position.first()?.as_f64()?.into(),
+        },
+        time: text(properties, "date_tm-value")?.parse().ok()?,
+        air_temperature: number(properties, "air_temp"),
+        relative_humidity: number(properties, "rel_hum"),
+        dew_point: number(properties, "dwpt_temp"),
+        station_pressure: pressure(properties, "stn_pres"),
+        sea_level_pressure: pressure(properties, "mslp"),
+        wind_speed: number(properties, "avg_wnd_spd_10m_pst10mts").map(Into::into),
+        wind_gust_speed: number(properties, "max_wnd_spd_10m_pst10mts").map(Into::into),
+        wind_direction: number(properties, "avg_wnd_dir_10m_pst10mts").map(Into::into),
+        precipitation_last_hour: number(properties, "pcpn_amt_pst1hr").map(Into::into),
+        visibility: number(properties, "vis").map(Into::into),
+    })
+}
+
+// SWOB observations carry hundreds of optional, loosely typed properties, so features are
+// decoded one by one and incomplete ones are skipped instead of failing the whole response
+fn parse_observations(body: &[u8]) -> anyhow::Result<Vec<SwobObservation>> {
+    let collection: Value = serde_json::from_slice(body)?;
+    let features = collection
+        .get("features")
+        .and_then(Value::as_array)
+        .ok_or_else(|| anyhow!("Response is not a feature collection"))?;
+
+    Ok(features
+        .iter()
+        .filter_map(|feature| {
+            let observation = to_observation(feature);
+            if observation.is_none() {
+                debug!("Skipping incomplete SWOB feature {:?}", feature.get("id"));
+            }
+            observation
+        })
+        .collect())
+}
+
+fn to_weather(
+    request: &WeatherRequest<Coordinates>,
+    observations: &[SwobObservation],
+) -> anyhow::Result<Weather> {
+    let measured: Vec<&SwobObservation> = observations
+        .iter()
+        .filter(|observation| observation.air_temperature.is_some())
+        .collect();
+
+    let station = find_closest(&request.query, &measured, |observation| {
+        observation.coordinates.clone()
+    })?;
+    trace!("Found closest weather station {:?}", station);
+
+    let latest = measured
+        .iter()
+        .filter(|observation| observation.station_id == station.station_id)
+        .max_by_key(|observation| observation.time)
+        .ok_or_else(|| anyhow!("No observation for station {}", station.station_name))?;
+
+    Ok(Weather {
+        city: Some(latest.station_name.clone()),
+        distance: Some(calculate_distance(&request.query, &latest.coordinates)),
+        relative_humidity: latest.relative_humidity.map(Ratio::Percentage),
+        dew_point: latest.dew_point.map(Celsius::from_f64),
+        station_pressure: latest.station_pressure,
+        sea_level_pressure: latest.sea_level_pressure,
+        wind_speed: latest.wind_speed.map(|speed| speed.to_meters_per_second()),
+        wind_gust_speed: latest
+            .wind_gust_speed
+            .map(|speed| speed.to_meters_per_second()),
+        wind_direction: latest.wind_direction,
+        precipitation: latest.precipitation_last_hour.map(|amount| Precipitation {
+            amount,
+            period: Duration::from_secs(60 * 60),
+        }),
+        visibility: latest.visibility.map(Kilometers::to_meters),
+        observation_time: Some(latest.time),
+        ..Weather::new(
+            SOURCE_URI.into(),
+            request.name.clone(),
+            latest.coordinates.clone(),
+            latest
+                .air_temperature
+                .map(Celsius::from_f64)
+                .ok_or_else(|| anyhow!("No temperature reported by {}", latest.station_name))?,
+        )
+    })
+}
+
+impl WeatherProvider for EnvironmentCanada {
+    fn id(&self) -> &str {
+        SOURCE_URI
+    }
+
+    fn for_coordinates(
+        &self,
+        client: &Client,
+        cache: &HttpRequestCache,
+        request: &WeatherRequest<Coordinates>,
+    ) -> anyhow::Result<Weather> {
+        let latitude = f64::from(request.query.latitude.clone());
+        let longitude = f64::from(request.query.longitude.clone());
+
+        let url = Url::parse_with_params(
+            ENDPOINT_URL,
+            &[
+                ("f", "json".into()),
+                (
+                    "bbox",
+                    format!(
+                        "{},{},{},{}",
+                        longitude - SEARCH_RADIUS_DEGREES,
+                        latitude - SEARCH_RADIUS_DEGREES,
+                        longitude + SEARCH_RADIUS_DEGREES,
+                        latitude + SEARCH_RADIUS_DEGREES
+                    ),
+                ),
+                ("sortby", "-date_tm-value".into()),
+                ("limit", SEARCH_LIMIT.to_string()),
+            ],
+        )?;
+
+        let observations = request_cached(&HttpCacheRequest::new(
+            SOURCE_URI,
+            client,
+            cache,
+            &Method::GET,
+            &url,
+            |body| parse_observations(body),
+        ))?;
+
+        to_weather(request, &observations)
+    }
+
+    fn refresh_interval(&self) -> Duration {
+        self.cache.refresh_interval
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod parse_observations {
+        use crate::providers::environment_canada::{parse_observations, SwobObservation};
+        use crate::providers::testing::fixture;
+        use crate::providers::units::Coordinates;
+        use chrono::{DateTime, Utc};
+        use pretty_assertions::assert_eq;
+
+        fn time(rfc3339: &str) -> DateTime<Utc> {
+            DateTime::parse_from_rfc3339(rfc3339)
+                .expect("Static value")
+                .with_timezone(&Utc)
+        }
+
+        fn observation(
+            station_id: &str,
+            station_name: &str,
+            coordinates: (f64, f64),
+            time: DateTime<Utc>,
+        ) -> SwobObservation {
+            SwobObservation {
+                station_id: station_id.into(),
+                station_name: station_name.into(),
+                coordinates: Coordinates {
+                    latitude: coordinates.0.into(),
+                    longitude: coordinates.1.into(),
+                },
+                time,
+                air_temperature: None,
+                relative_humidity: None,
+                dew_point: None,
+                station_pressure: None,
+                sea_level_pressure: None,
+                wind_speed: None,
+                wind_gust_speed: None,
+                wind_direction: None,
+                precipitation_last_hour: None,
+                visibility: None,
+            }
+        }
+
+        #[test]
+        fn skip_incomplete_features() {
+            assert_eq!(
+                parse_observations(&fixture("environment_canada/swob_realtime.json"))
+                    .expect("Fixture is valid"),
+                [
+                    SwobObservation {
+                        air_temperature: Some(18.2_f64),
+                        relative_humidity: Some(70_f64),
+                        dew_point: Some(12.6_f64),
+                        station_pressure: Some(1005.3_f64.into()),
+                        sea_level_pressure: Some(1013.4_f64.into()),
+                        wind_speed: Some(18_f64.into()),
+                        wind_gust_speed: Some(27_f64.into()),
+                        wind_direction: Some(250_f64.into()),
+                        precipitation_last_hour: Some(0.4_f64.into()),
+                        visibility: Some(24.1_f64.into()),
+                        ..observation(
+                            "6158359",
+                            "TORONTO CITY CENTRE",
+                            (43.6275_f64, -79.3962_f64),
+                            time("2024-06-01T13:00:00Z"),
+                        )
+                    },
+                    SwobObservation {
+                        air_temperature: Some(19.4_f64),
+                        relative_humidity: Some(64_f64),
+                        ..observation(
+                            "6158731",
+                            "TORONTO INTL A",
+                            (43.6772_f64, -79.6306_f64),
+                            time("2024-06-01T13:00:00Z"),
+                        )
+                    },
+                    SwobObservation {
+                        air_temperature: Some(17.1_f64),
+                        relative_humidity: Some(75_f64),
+                        ..observation(
+                            "6158359",
+                            "TORONTO CITY CENTRE",
+                            (43.6275_f64, -79.3962_f64),
+                            time("2024-06-01T12:00:00Z"),
+                        )
+                    },
+                ]
+            );
+        }
+
+        #[test]
+        fn fail_without_features() {
+            assert_eq!(
+                parse_observations(b"{}")
+                    .expect_err("Not a feature collection")
+                    .to_string(),
+                "Response is not a feature collection"
+            );
+        }
+    }
+
+    mod to_weather {
+        use crate::providers::environment_canada::{parse_observations, to_weather};
+        use crate::providers::testing::{fixture, request};
+        use crate::providers::units::{Coordinates, Ratio};
+        use crate::providers::{Precipitation, Weather};
+        use chrono::{DateTime, Utc};
+        use pretty_assertions::assert_eq;
+        use std::time::Duration;
+
+        #[test]
+        fn map_latest_observation_of_closest_station() {
+            assert_eq!(
+                to_weather(
+                    &request("Toronto", 43.6532_f64, -79.3832_f64),
+                    &parse_observations(&fixture("environment_canada/swob_realtime.json"))
+                        .expect("Fixture is valid"),
+                )
+                .expect("Mapping works"),
+                Weather {
+                    city: Some("TORONTO CITY CENTRE".into()),
+                    distance: Some(3_043.169_682_614_874_f64.into()),
+                    relative_humidity: Some(Ratio::Percentage(70_f64)),
+                    dew_point: Some(12.6_f32.into()),
+                    station_pressure: Some(1005.3_f64.into()),
+                    sea_level_pressure: Some(1013.4_f64.into()),
+                    wind_speed: Some(5_f64.into()),
+                    wind_gust_speed: Some(7.5_f64.into()),
+                    wind_direction: Some(250_f64.into()),
+                    precipitation: Some(Precipitation {
+                        amount: 0.4_f64.into(),
+                        period: Duration::from_secs(3600),
+                    }),
+                    visibility: Some(24_100_f64.into()),
+                    observation_time: Some(
+                        DateTime::parse_from_rfc3339("2024-06-01T13:00:00Z")
+                            .expect("Static value")
+                            .with_timezone(&Utc)
+                    ),
+                    ..Weather::new(
+                        "ca.gc.weather".into(),
+                        "Toronto".into(),
+                        Coordinates {
+                            latitude: 43.6275_f64.into(),
+                            longitude: (-79.3962_f64).into(),
+                        },
+                        18.2_f32.into(),
+                    )
+                }
+            );
+        }
+    }
+}
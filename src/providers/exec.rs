@@ -0,0 +1,429 @@
+use crate::providers::http_request::Configuration;
+use crate::providers::units::{
+    Celsius, Coordinates, Decibels, Degrees, Hectopascal, Meters, MetersPerSecond, Millimeters,
+    MillimetersPerHour, PartsPerMillion, Ratio, WattsPerSquareMeter,
+};
+use crate::providers::{HttpRequestCache, Weather, WeatherProvider, WeatherRequest};
+use anyhow::{anyhow, Context};
+use chrono::{DateTime, Utc};
+use log::debug;
+use reqwest::blocking::Client;
+use reqwest::{Method, Url};
+use serde::{Deserialize, Serialize};
+use std::io::{self, Read};
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+use wait_timeout::ChildExt;
+
+const LOCATION_VARIABLE: &str = "WEATHERMEN_LOCATION";
+const LATITUDE_VARIABLE: &str = "WEATHERMEN_LATITUDE";
+const LONGITUDE_VARIABLE: &str = "WEATHERMEN_LONGITUDE";
+
+// More output is discarded, a weather report is a few hundred bytes
+const MAX_OUTPUT_BYTES: u64 = 1024 * 1024;
+
+// Configured multiple times, so every instance names its own source
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Exec {
+    source: String,
+    command: String,
+    // {location}, {latitude} and {longitude} are replaced with the values of the location
+    #[serde(default)]
+    args: Vec<String>,
+    // Commands running longer are killed
+    #[serde(default = "default_timeout")]
+    #[serde(with = "humantime_serde")]
+    timeout: Duration,
+    #[serde(flatten)]
+    cache: Configuration,
+}
+
+const fn default_timeout() -> Duration {
+    Duration::from_secs(10)
+}
+
+// Values are expected in the units of the corresponding metric
+#[derive(Deserialize, Debug)]
+struct Output {
+    temperature: Celsius,
+    relative_humidity: Option<f64>,
+    dew_point: Option<Celsius>,
+    apparent_temperature: Option<Celsius>,
+    station_pressure: Option<Hectopascal>,
+    sea_level_pressure: Option<Hectopascal>,
+    wind_speed: Option<MetersPerSecond>,
+    wind_gust_speed: Option<MetersPerSecond>,
+    wind_direction: Option<Degrees>,
+    precipitation_intensity: Option<MillimetersPerHour>,
+    daily_precipitation: Option<Millimeters>,
+    snow_depth: Option<Meters>,
+    cloud_cover: Option<f64>,
+    visibility: Option<Meters>,
+    uv_index: Option<f64>,
+    global_radiation: Option<WattsPerSquareMeter>,
+    co2: Option<PartsPerMillion>,
+    noise: Option<Decibels>,
+    observation_time: Option<DateTime<Utc>>,
+}
+
+fn to_weather(
+    source: &str,
+    request: &WeatherRequest<Coordinates>,
+    stdout: &[u8],
+) -> anyhow::Result<Weather> {
+    let output: Output =
+        serde_json::from_slice(stdout).with_context(|| "Command did not print valid JSON")?;
+
+    Ok(Weather {
+        relative_humidity: output.relative_humidity.map(Ratio::Percentage),
+        dew_point: output.dew_point,
+        apparent_temperature: output.apparent_temperature,
+        station_pressure: output.station_pressure,
+        sea_level_pressure: output.sea_level_pressure,
+        wind_speed: output.wind_speed,
+        wind_gust_speed: output.wind_gust_speed,
+        wind_direction: output.wind_direction,
+        precipitation_intensity: output.precipitation_intensity,
+        daily_precipitation: output.daily_precipitation,
+        snow_depth: output.snow_depth,
+        cloud_cover: output.cloud_cover.map(Ratio::Percentage),
+        visibility: output.visibility,
+        uv_index: output.uv_index,
+        global_radiation: output.global_radiation,
+        observation_time: output.observation_time,
+        co2: output.co2,
+        noise: output.noise,
+        ..Weather::new(
+            source.into(),
+            request.name.clone(),
+            request.query.clone(),
+            output.temperature,
+        )
+    })
+}
+
+fn read(pipe: Option<impl Read>) -> io::Result<Vec<u8>> {
+    let mut buffer = vec![];
+    if let Some(mut readable) = pipe {
+        readable
+            .by_ref()
+            .take(MAX_OUTPUT_BYTES)
+            .read_to_end(&mut buffer)?;
+        io::copy(&mut readable, &mut io::sink())?;
+    }
+    Ok(buffer)
+}
+
+// Pipes are drained while waiting, so commands printing a lot do not block. Processes started by
+// the command inherit the pipes, so the output is received with a deadline instead of joining
+fn read_in_background<R: Read + Send + 'static>(pipe: Option<R>) -> Receiver<io::Result<Vec<u8>>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || sender.send(read(pipe)));
+    receiver
+}
+
+// The command leads its own process group, so processes it started are killed along with it
+fn kill_process_group(child: &Child) -> io::Result<()> {
+    let group = libc::pid_t::try_from(child.id()).map_err(io::Error::other)?;
+
+    // SAFETY: killpg only sends a signal. The group cannot be reused while the child is not reaped
+    // or other processes of the group keep its pipes open
+    if unsafe { libc::killpg(group, libc::SIGKILL) } != 0_i32 {
+        let error = io::Error::last_os_error();
+        // All processes of the group exited in the meantime
+        if error.raw_os_error() != Some(libc::ESRCH) {
+            return Err(error);
+        }
+    }
+
+    Ok(())
+}
+
+impl Exec {
+    fn args(&self, request: &WeatherRequest<Coordinates>) -> Vec<String> {
+        self.args
+            .iter()
+            .map(|arg| {
+                arg.replace("{location}", &request.name)
+                    .replace("{latitude}", &request.query.latitude.to_string())
+                    .replace("{longitude}", &request.query.longitude.to_string())
+            })
+            .collect()
+    }
+
+    fn timed_out(&self) -> anyhow::Error {
+        anyhow!("{} did not finish within {:?}", self.command, self.timeout)
+    }
+
+    fn collect(
+        &self,
+        child: &Child,
+        reader: &Receiver<io::Result<Vec<u8>>>,
+        started: Instant,
+    ) -> anyhow::Result<Vec<u8>> {
+        match reader.recv_timeout(self.timeout.saturating_sub(started.elapsed())) {
+            Ok(output) => output.with_context(|| "Could not read command output"),
+            Err(RecvTimeoutError::Timeout) => {
+                kill_process_group(child)?;
+                Err(self.timed_out())
+            }
+            Err(RecvTimeoutError::Disconnected) => Err(anyhow!("Reading command output panicked")),
+        }
+    }
+
+    fn run(&self, request: &WeatherRequest<Coordinates>) -> anyhow::Result<Vec<u8>> {
+        debug!("Running {} for {}", self.command, request.name);
+        let started = Instant::now();
+
+        let mut child = Command::new(&self.command)
+            .args(self.args(request))
+            .env(LOCATION_VARIABLE, &request.name)
+            .env(LATITUDE_VARIABLE, request.query.latitude.to_string())
+            .env(LONGITUDE_VARIABLE, request.query.longitude.to_string())
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .process_group(0)
+            .spawn()
+            .with_context(|| format!("Could not run {}", self.command))?;
+
+        let stdout = read_in_background(child.stdout.take());
+        let stderr = read_in_background(child.stderr.take());
+
+        let Some(status) = child.wait_timeout(self.timeout)? else {
+            kill_process_group(&child)?;
+            child.wait()?;
+            return Err(self.timed_out());
+        };
+
+        let output = self.collect(&child, &stdout, started)?;
+        if !status.success() {
+            return Err(anyhow!(
+                "{} failed with {status}: {}",
+                self.command,
+                String::from_utf8_lossy(&self.collect(&child, &stderr, started)?).trim()
+            ));
+        }
+
+        Ok(output)
+    }
+}
+
+impl WeatherProvider for Exec {
+    fn id(&self) -> &str {
+        &self.source
+    }
+
+    // The output is cached like a response, keyed by the location the command ran for
+    fn for_coordinates(
+        &self,
+        _client: &Client,
+        cache: &HttpRequestCache,
+        request: &WeatherRequest<Coordinates>,
+    ) -> anyhow::Result<Weather> {
+        let key = (
+            Method::GET,
+            Url::parse_with_params(
+                "exec:run",
+                &[
+                    ("location", request.name.clone()),
+                    ("latitude", request.query.latitude.to_string()),
+                    ("longitude", request.query.longitude.to_string()),
+                ],
+            )?,
+        );
+
+        let stdout = cache
+            .try_get_with_by_ref(&key, || self.run(request))
+            .map_err(|e| anyhow!(e))?;
+
+        to_weather(&self.source, request, &stdout)
+    }
+
+    fn refresh_interval(&self) -> Duration {
+        self.cache.refresh_interval
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod to_weather {
+        use crate::providers::exec::to_weather;
+        use crate::providers::testing::{fixture, request};
+        use crate::providers::units::Ratio;
+        use crate::providers::Weather;
+        use chrono::{DateTime, Utc};
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn map_output() {
+            let request = request("Berlin", 52.520_008_f64, 13.404_954_f64);
+
+            assert_eq!(
+                to_weather("local.rooftop", &request, &fixture("exec/output.json"))
+                    .expect("Mapping works"),
+                Weather {
+                    relative_humidity: Some(Ratio::Percentage(61_f64)),
+                    station_pressure: Some(1003.2_f64.into()),
+                    wind_speed: Some(3.4_f64.into()),
+                    wind_direction: Some(225_f64.into()),
+                    daily_precipitation: Some(1.2_f64.into()),
+                    co2: Some(415_f64.into()),
+                    observation_time: Some(
+                        DateTime::parse_from_rfc3339("2024-06-01T12:55:00Z")
+                            .expect("Static value")
+                            .with_timezone(&Utc)
+                    ),
+                    ..Weather::new(
+                        "local.rooftop".into(),
+                        "Berlin".into(),
+                        request.query.clone(),
+                        19.8_f32.into(),
+                    )
+                }
+            );
+        }
+
+        #[test]
+        fn reject_other_output() {
+            assert_eq!(
+                to_weather(
+                    "local.rooftop",
+                    &request("Berlin", 52.520_008_f64, 13.404_954_f64),
+                    b"21.5"
+                )
+                .expect_err("No JSON document")
+                .to_string(),
+                "Command did not print valid JSON"
+            );
+        }
+    }
+
+    mod run {
+        use crate::providers::exec::{Exec, MAX_OUTPUT_BYTES};
+        use crate::providers::testing::request;
+        use crate::providers::units::Coordinates;
+        use crate::providers::WeatherRequest;
+        use pretty_assertions::assert_eq;
+        use std::env;
+        use std::fs;
+        use std::io;
+        use std::process;
+        use std::thread;
+        use std::time::{Duration, Instant};
+
+        fn provider(script: &str, timeout: &str) -> Exec {
+            toml::from_str(&format!(
+                r#"
+                    source = "local.rooftop"
+                    command = "sh"
+                    args = ["-c", '{script}', "sh", "{{location}}", "{{latitude}}"]
+                    timeout = "{timeout}"
+                "#
+            ))
+            .expect("Config is valid")
+        }
+
+        fn berlin() -> WeatherRequest<Coordinates> {
+            request("Berlin", 52.520_008_f64, 13.404_954_f64)
+        }
+
+        // Runs a script writing the PID of `sleep` to PID_FILE, returning the error and whether it was killed
+        fn run_and_kill(script: &str, timeout: &str) -> (String, bool) {
+            let pid_file = env::temp_dir().join(format!("exec-pid-{}-{timeout}", process::id()));
+            let command = script.replace("PID_FILE", &pid_file.display().to_string());
+
+            let error = provider(&command, timeout)
+                .run(&berlin())
+                .expect_err("Command is killed")
+                .to_string();
+
+            let pid = fs::read_to_string(&pid_file)
+                .expect("Script wrote the PID")
+                .trim()
+                .parse::<libc::pid_t>()
+                .expect("PID is a number");
+            fs::remove_file(&pid_file).expect("PID file is removed");
+
+            // Killed processes disappear once init reaped them, shortly after the signal. Without an init
+            // reaping orphans, as in some containers, they remain as zombies
+            let stat = format!("/proc/{pid}/stat");
+            let killed = (0_u8..20_u8).any(|_| {
+                thread::sleep(Duration::from_millis(50));
+                // SAFETY: Signal 0 only checks whether the process exists
+                let failed = unsafe { libc::kill(pid, 0_i32) } != 0_i32;
+                (failed && io::Error::last_os_error().raw_os_error() == Some(libc::ESRCH))
+                    || fs::read_to_string(&stat).is_ok_and(|status| status.contains(") Z "))
+            });
+
+            (error, killed)
+        }
+
+        #[test]
+        fn pass_location_to_command() {
+            let stdout = provider(
+                r#"echo "{\"location\": \"$1 $WEATHERMEN_LOCATION\", \"latitude\": \"$2 $WEATHERMEN_LONGITUDE\"}""#,
+                "10s",
+            )
+            .run(&berlin())
+            .expect("Command succeeds");
+
+            assert_eq!(
+                String::from_utf8_lossy(&stdout).trim(),
+                r#"{"location": "Berlin Berlin", "latitude": "52.5200080 13.4049540"}"#
+            );
+        }
+
+        #[test]
+        fn fail_on_exit_code_and_timeout() {
+            assert_eq!(
+                [
+                    provider("echo broken >&2; exit 3", "10s"),
+                    provider("sleep 5", "100ms"),
+                ]
+                .map(|exec| exec.run(&berlin()).expect_err("Command fails").to_string()),
+                [
+                    "sh failed with exit status: 3: broken",
+                    "sh did not finish within 100ms",
+                ]
+            );
+        }
+
+        #[test]
+        fn cap_output() {
+            assert_eq!(
+                provider("head -c 2000000 /dev/zero", "10s")
+                    .run(&berlin())
+                    .map(|stdout| u64::try_from(stdout.len()).ok())
+                    .expect("Command succeeds"),
+                Some(MAX_OUTPUT_BYTES)
+            );
+        }
+
+        #[test]
+        fn kill_started_processes_on_timeout() {
+            assert_eq!(
+                run_and_kill("sleep 5 & echo $! > PID_FILE; wait", "500ms"),
+                ("sh did not finish within 500ms".to_owned(), true)
+            );
+        }
+
+        #[test]
+        fn kill_started_processes_keeping_output_open() {
+            let started = Instant::now();
+            let (error, killed) = run_and_kill("sleep 600 & echo $! > PID_FILE", "400ms");
+
+            assert_eq!(
+                (
+                    error.as_str(),
+                    killed,
+                    started.elapsed() < Duration::from_secs(5)
+                ),
+                ("sh did not finish within 400ms", true, true)
+            );
+        }
+    }
+}
@@ -0,0 +1,560 @@
+use crate::providers::http_request::{request_cached, Configuration, HttpCacheRequest};
+use crate::providers::units::{
+    Celsius, Centimeters, Coordinates, Decibels, Degrees, Fahrenheit, Hectopascal, Inches,
+    InchesOfMercury, InchesPerHour, Kelvin, Kilometers, KilometersPerHour, Knots, Meters,
+    MetersPerSecond, Miles, MilesPerHour, Millimeters, MillimetersPerHour, PartsPerMillion,
+    Pascals, Ratio, ToCelsius, ToMetersPerSecond, WattsPerSquareMeter,
+};
+use crate::providers::{HttpRequestCache, Weather, WeatherProvider, WeatherRequest};
+use anyhow::{anyhow, Context};
+use reqwest::blocking::Client;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Method, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serde_json_path::JsonPath;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+// Configured multiple times, so every instance names its own source
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GenericJson {
+    source: String,
+    // {latitude} and {longitude} are replaced with the coordinates of the location
+    url: String,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    #[serde(deserialize_with = "deserialize_fields")]
+    fields: Fields,
+    #[serde(flatten)]
+    cache: Configuration,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Fields {
+    temperature: Selector,
+    relative_humidity: Option<Selector>,
+    dew_point: Option<Selector>,
+    apparent_temperature: Option<Selector>,
+    station_pressure: Option<Selector>,
+    sea_level_pressure: Option<Selector>,
+    wind_speed: Option<Selector>,
+    wind_gust_speed: Option<Selector>,
+    wind_direction: Option<Selector>,
+    precipitation_intensity: Option<Selector>,
+    daily_precipitation: Option<Selector>,
+    snow_depth: Option<Selector>,
+    cloud_cover: Option<Selector>,
+    visibility: Option<Selector>,
+    uv_index: Option<Selector>,
+    global_radiation: Option<Selector>,
+    direct_radiation: Option<Selector>,
+    diffuse_radiation: Option<Selector>,
+    co2: Option<Selector>,
+    noise: Option<Selector>,
+}
+
+// Values without a unit are expected in the unit of the corresponding metric
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Selector {
+    path: JsonPath,
+    unit: Option<Unit>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum Unit {
+    Celsius,
+    Kelvin,
+    Fahrenheit,
+    Percentage,
+    Fraction,
+    Hectopascal,
+    Pascals,
+    InchesOfMercury,
+    MetersPerSecond,
+    KilometersPerHour,
+    MilesPerHour,
+    Knots,
+    Degrees,
+    Meters,
+    Kilometers,
+    Miles,
+    Millimeters,
+    Centimeters,
+    Inches,
+    MillimetersPerHour,
+    InchesPerHour,
+    WattsPerSquareMeter,
+    PartsPerMillion,
+    Decibels,
+}
+
+fn unsupported(unit: Unit, quantity: &str) -> anyhow::Error {
+    anyhow!("Unit {unit:?} is not supported for {quantity}")
+}
+
+fn temperature(value: f64, unit: Option<Unit>) -> anyhow::Result<Celsius> {
+    match unit {
+        None | Some(Unit::Celsius) => Ok(Celsius::from_f64(value)),
+        Some(Unit::Kelvin) => Ok(Kelvin::from_f64(value).to_celsius()),
+        Some(Unit::Fahrenheit) => Ok(Fahrenheit::from_f64(value).to_celsius()),
+        Some(other) => Err(unsupported(other, "temperatures")),
+    }
+}
+
+fn ratio(value: f64, unit: Option<Unit>) -> anyhow::Result<Ratio> {
+    match unit {
+        None | Some(Unit::Percentage) => Ok(Ratio::Percentage(value)),
+        Some(Unit::Fraction) => Ok(Ratio::Fraction(value)),
+        Some(other) => Err(unsupported(other, "ratios")),
+    }
+}
+
+fn pressure(value: f64, unit: Option<Unit>) -> anyhow::Result<Hectopascal> {
+    match unit {
+        None | Some(Unit::Hectopascal) => Ok(value.into()),
+        Some(Unit::Pascals) => Ok(Pascals::from(value).to_hectopascal()),
+        Some(Unit::InchesOfMercury) => Ok(InchesOfMercury::from(value).to_hectopascal()),
+        Some(other) => Err(unsupported(other, "pressures")),
+    }
+}
+
+fn speed(value: f64, unit: Option<Unit>) -> anyhow::Result<MetersPerSecond> {
+    match unit {
+        None | Some(Unit::MetersPerSecond) => Ok(value.into()),
+        Some(Unit::KilometersPerHour) => Ok(KilometersPerHour::from(value).to_meters_per_second()),
+        Some(Unit::MilesPerHour) => Ok(MilesPerHour::from(value).to_meters_per_second()),
+        Some(Unit::Knots) => Ok(Knots::from(value).to_meters_per_second()),
+        Some(other) => Err(unsupported(other, "speeds")),
+    }
+}
+
+fn distance(value: f64, unit: Option<Unit>) -> anyhow::Result<Meters> {
+    match unit {
+        None | Some(Unit::Meters) => Ok(value.into()),
+        Some(Unit::Kilometers) => Ok(Kilometers::from(value).to_meters()),
+        Some(Unit::Miles) => Ok(Miles::from(value).to_meters()),
+        Some(Unit::Centimeters) => Ok(Centimeters::from(value).to_meters()),
+        Some(Unit::Millimeters) => Ok(Millimeters::from(value).to_meters()),
+        Some(other) => Err(unsupported(other, "distances")),
+    }
+}
+
+fn amount(value: f64, unit: Option<Unit>) -> anyhow::Result<Millimeters> {
+    match unit {
+        None | Some(Unit::Millimeters) => Ok(value.into()),
+        Some(Unit::Centimeters) => Ok(Centimeters::from(value).to_millimeters()),
+        Some(Unit::Inches) => Ok(Inches::from(value).to_millimeters()),
+        Some(other) => Err(unsupported(other, "precipitation")),
+    }
+}
+
+fn intensity(value: f64, unit: Option<Unit>) -> anyhow::Result<MillimetersPerHour> {
+    match unit {
+        None | Some(Unit::MillimetersPerHour) => Ok(value.into()),
+        Some(Unit::InchesPerHour) => Ok(InchesPerHour::from(value).to_millimeters_per_hour()),
+        Some(other) => Err(unsupported(other, "precipitation intensity")),
+    }
+}
+
+// Quantities with only one unit, which may be stated explicitly
+fn exact<T: From<f64>>(expected: Unit) -> impl Fn(f64, Option<Unit>) -> anyhow::Result<T> {
+    move |value, unit| match unit {
+        Some(other) if other != expected => Err(unsupported(other, &format!("{expected:?}"))),
+        _ => Ok(value.into()),
+    }
+}
+
+fn unitless(value: f64, unit: Option<Unit>) -> anyhow::Result<f64> {
+    unit.map_or(Ok(value), |other| Err(unsupported(other, "UV index")))
+}
+
+fn check_unit<T>(
+    name: &str,
+    configured: Option<&Selector>,
+    convert: impl Fn(f64, Option<Unit>) -> anyhow::Result<T>,
+) -> anyhow::Result<()> {
+    configured.map_or(Ok(()), |selector| {
+        convert(0_f64, selector.unit)
+            .map(|_| ())
+            .with_context(|| format!("Invalid unit for {name}"))
+    })
+}
+
+impl Fields {
+    fn check_units(&self) -> anyhow::Result<()> {
+        check_unit("temperature", Some(&self.temperature), temperature)?;
+        check_unit("relative_humidity", self.relative_humidity.as_ref(), ratio)?;
+        check_unit("dew_point", self.dew_point.as_ref(), temperature)?;
+        check_unit(
+            "apparent_temperature",
+            self.apparent_temperature.as_ref(),
+            temperature,
+        )?;
+        check_unit("station_pressure", self.station_pressure.as_ref(), pressure)?;
+        check_unit(
+            "sea_level_pressure",
+            self.sea_level_pressure.as_ref(),
+            pressure,
+        )?;
+        check_unit("wind_speed", self.wind_speed.as_ref(), speed)?;
+        check_unit("wind_gust_speed", self.wind_gust_speed.as_ref(), speed)?;
+        check_unit(
+            "wind_direction",
+            self.wind_direction.as_ref(),
+            exact::<Degrees>(Unit::Degrees),
+        )?;
+        check_unit(
+            "precipitation_intensity",
+            self.precipitation_intensity.as_ref(),
+            intensity,
+        )?;
+        check_unit(
+            "daily_precipitation",
+            self.daily_precipitation.as_ref(),
+            amount,
+        )?;
+        check_unit("snow_depth", self.snow_depth.as_ref(), distance)?;
+        check_unit("cloud_cover", self.cloud_cover.as_ref(), ratio)?;
+        check_unit("visibility", self.visibility.as_ref(), distance)?;
+        check_unit("uv_index", self.uv_index.as_ref(), unitless)?;
+        check_unit(
+            "global_radiation",
+            self.global_radiation.as_ref(),
+            exact::<WattsPerSquareMeter>(Unit::WattsPerSquareMeter),
+        )?;
+        check_unit(
+            "direct_radiation",
+            self.direct_radiation.as_ref(),
+            exact::<WattsPerSquareMeter>(Unit::WattsPerSquareMeter),
+        )?;
+        check_unit(
+            "diffuse_radiation",
+            self.diffuse_radiation.as_ref(),
+            exact::<WattsPerSquareMeter>(Unit::WattsPerSquareMeter),
+        )?;
+        check_unit(
+            "co2",
+            self.co2.as_ref(),
+            exact::<PartsPerMillion>(Unit::PartsPerMillion),
+        )?;
+        check_unit(
+            "noise",
+            self.noise.as_ref(),
+            exact::<Decibels>(Unit::Decibels),
+        )
+    }
+}
+
+// Units not matching the quantity of their field are rejected while loading the configuration
+fn deserialize_fields<'de, D>(deserializer: D) -> Result<Fields, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let fields = Fields::deserialize(deserializer)?;
+    fields
+        .check_units()
+        .map_err(|error| serde::de::Error::custom(format!("{error:#}")))?;
+
+    Ok(fields)
+}
+
+// Paths not matching anything and null values are treated as missing
+fn select(response: &Value, selector: &Selector) -> anyhow::Result<Option<f64>> {
+    let value = selector
+        .path
+        .query(response)
+        .at_most_one()
+        .map_err(|e| anyhow!("Path {} is ambiguous: {e}", selector.path))?;
+
+    match value {
+        None | Some(Value::Null) => Ok(None),
+        Some(number) => number
+            .as_f64()
+            .map(Some)
+            .ok_or_else(|| anyhow!("Value {number} at {} is no number", selector.path)),
+    }
+}
+
+fn field<T>(
+    response: &Value,
+    configured: Option<&Selector>,
+    convert: impl Fn(f64, Option<Unit>) -> anyhow::Result<T>,
+) -> anyhow::Result<Option<T>> {
+    let Some(selector) = configured else {
+        return Ok(None);
+    };
+
+    select(response, selector)?
+        .map(|value| convert(value, selector.unit))
+        .transpose()
+}
+
+impl GenericJson {
+    fn url(&self, request: &WeatherRequest<Coordinates>) -> anyhow::Result<Url> {
+        Ok(Url::parse(
+            &self
+                .url
+                .replace("{latitude}", &request.query.latitude.to_string())
+                .replace("{longitude}", &request.query.longitude.to_string()),
+        )?)
+    }
+
+    fn headers(&self) -> anyhow::Result<HeaderMap> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            headers.insert(
+                HeaderName::from_bytes(name.as_bytes())?,
+                HeaderValue::from_str(value)?,
+            );
+        }
+
+        Ok(headers)
+    }
+
+    fn to_weather(
+        &self,
+        request: &WeatherRequest<Coordinates>,
+        response: &Value,
+    ) -> anyhow::Result<Weather> {
+        let fields = &self.fields;
+
+        Ok(Weather {
+            relative_humidity: field(response, fields.relative_humidity.as_ref(), ratio)?,
+            dew_point: field(response, fields.dew_point.as_ref(), temperature)?,
+            apparent_temperature: field(
+                response,
+                fields.apparent_temperature.as_ref(),
+                temperature,
+            )?,
+            station_pressure: field(response, fields.station_pressure.as_ref(), pressure)?,
+            sea_level_pressure: field(response, fields.sea_level_pressure.as_ref(), pressure)?,
+            wind_speed: field(response, fields.wind_speed.as_ref(), speed)?,
+            wind_gust_speed: field(response, fields.wind_gust_speed.as_ref(), speed)?,
+            wind_direction: field(
+                response,
+                fields.wind_direction.as_ref(),
+                exact::<Degrees>(Unit::Degrees),
+            )?,
+            precipitation_intensity: field(
+                response,
+                fields.precipitation_intensity.as_ref(),
+                intensity,
+            )?,
+            daily_precipitation: field(response, fields.daily_precipitation.as_ref(), amount)?,
+            snow_depth: field(response, fields.snow_depth.as_ref(), distance)?,
+            cloud_cover: field(response, fields.cloud_cover.as_ref(), ratio)?,
+            visibility: field(response, fields.visibility.as_ref(), distance)?,
+            uv_index: field(response, fields.uv_index.as_ref(), unitless)?,
+            global_radiation: field(
+                response,
+                fields.global_radiation.as_ref(),
+                exact(Unit::WattsPerSquareMeter),
+            )?,
+            direct_radiation: field(
+                response,
+                fields.direct_radiation.as_ref(),
+                exact(Unit::WattsPerSquareMeter),
+            )?,
+            diffuse_radiation: field(
+                response,
+                fields.diffuse_radiation.as_ref(),
+                exact(Unit::WattsPerSquareMeter),
+            )?,
+            co2: field(response, fields.co2.as_ref(), exact(Unit::PartsPerMillion))?,
+            noise: field(response, fields.noise.as_ref(), exact(Unit::Decibels))?,
+            ..Weather::new(
+                self.source.clone(),
+                request.name.clone(),
+                request.query.clone(),
+                field(response, Some(&fields.temperature), temperature)?
+                    .ok_or_else(|| anyhow!("No temperature at {}", fields.temperature.path))?,
+            )
+        })
+    }
+}
+
+impl WeatherProvider for GenericJson {
+    fn id(&self) -> &str {
+        &self.source
+    }
+
+    fn for_coordinates(
+        &self,
+        client: &Client,
+        cache: &HttpRequestCache,
+        request: &WeatherRequest<Coordinates>,
+    ) -> anyhow::Result<Weather> {
+        let url = self.url(request)?;
+
+        let response: Value = request_cached(
+            &HttpCacheRequest::new_json_request(&self.source, client, cache, &Method::GET, &url)
+                .with_headers(self.headers()?),
+        )?;
+
+        self.to_weather(request, &response)
+    }
+
+    fn refresh_interval(&self) -> Duration {
+        self.cache.refresh_interval
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::providers::generic_json::GenericJson;
+
+    const CONFIG: &str = r#"
+        source = "com.example.rooftop"
+        url = "https://weather.example.com/v1/current?lat={latitude}&lon={longitude}"
+        headers = { "X-Api-Key" = "secret" }
+
+        [fields]
+        temperature = { path = "$.readings[?@.sensor == 'air'].temperature_k", unit = "kelvin" }
+        relative_humidity = { path = "$.readings[?@.sensor == 'air'].humidity", unit = "fraction" }
+        station_pressure = { path = "$.readings[?@.sensor == 'air'].pressure_pa", unit = "pascals" }
+        wind_speed = { path = "$.readings[?@.sensor == 'wind'].speed_kmh", unit = "kilometers_per_hour" }
+        wind_direction = { path = "$.readings[?@.sensor == 'wind'].direction" }
+        precipitation_intensity = { path = "$.readings[?@.sensor == 'rain'].rate_in_h", unit = "inches_per_hour" }
+        daily_precipitation = { path = "$.readings[?@.sensor == 'rain'].today_in", unit = "inches" }
+        visibility = { path = "$.visibility_km", unit = "kilometers" }
+        uv_index = { path = "$.uv" }
+    "#;
+
+    fn provider(config: &str) -> GenericJson {
+        toml::from_str(config).expect("Config is valid")
+    }
+
+    mod deserialize {
+        use crate::providers::generic_json::tests::CONFIG;
+        use crate::providers::generic_json::GenericJson;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn reject_unit_of_other_quantity() {
+            assert_eq!(
+                toml::from_str::<GenericJson>(
+                    &CONFIG.replace(r#"unit = "kilometers""#, r#"unit = "fahrenheit""#)
+                )
+                .expect_err("Temperature unit for a distance")
+                .message(),
+                "Invalid unit for visibility: Unit Fahrenheit is not supported for distances"
+            );
+        }
+    }
+
+    mod url {
+        use crate::providers::generic_json::tests::{provider, CONFIG};
+        use crate::providers::testing::request;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn fill_coordinates() {
+            assert_eq!(
+                provider(CONFIG)
+                    .url(&request("Berlin", 52.520_008_f64, 13.404_954_f64))
+                    .expect("URL is valid")
+                    .as_str(),
+                "https://weather.example.com/v1/current?lat=52.5200080&lon=13.4049540"
+            );
+        }
+    }
+
+    mod headers {
+        use crate::providers::generic_json::tests::{provider, CONFIG};
+        use pretty_assertions::assert_eq;
+        use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+
+        #[test]
+        fn send_configured_headers() {
+            assert_eq!(
+                provider(CONFIG).headers().expect("Headers are valid"),
+                HeaderMap::from_iter([(
+                    HeaderName::from_static("x-api-key"),
+                    HeaderValue::from_static("secret"),
+                )])
+            );
+        }
+    }
+
+    mod to_weather {
+        use crate::providers::generic_json::tests::{provider, CONFIG};
+        use crate::providers::testing::{json_fixture, request};
+        use crate::providers::units::{
+            Fahrenheit, Kelvin, Kilometers, KilometersPerHour, Ratio, ToCelsius, ToMetersPerSecond,
+        };
+        use crate::providers::Weather;
+        use pretty_assertions::assert_eq;
+        use serde_json::Value;
+
+        fn response() -> Value {
+            json_fixture("generic_json/station.json")
+        }
+
+        fn weather() -> Weather {
+            let request = request("Berlin", 52.520_008_f64, 13.404_954_f64);
+
+            Weather {
+                relative_humidity: Some(Ratio::Fraction(0.66_f64)),
+                station_pressure: Some(1008.9_f64.into()),
+                wind_speed: Some(KilometersPerHour::from(18_f64).to_meters_per_second()),
+                wind_direction: Some(250_f64.into()),
+                precipitation_intensity: Some(2.54_f64.into()),
+                visibility: Some(Kilometers::from(12.5_f64).to_meters()),
+                ..Weather::new(
+                    "com.example.rooftop".into(),
+                    request.name,
+                    request.query,
+                    Kelvin::from(291.4_f32).to_celsius(),
+                )
+            }
+        }
+
+        #[test]
+        fn map_selected_values() {
+            assert_eq!(
+                provider(CONFIG)
+                    .to_weather(
+                        &request("Berlin", 52.520_008_f64, 13.404_954_f64),
+                        &response()
+                    )
+                    .expect("Mapping works"),
+                weather()
+            );
+        }
+
+        #[test]
+        fn convert_configured_unit() {
+            assert_eq!(
+                provider(&CONFIG.replace(r#"unit = "kelvin""#, r#"unit = "fahrenheit""#))
+                    .to_weather(
+                        &request("Berlin", 52.520_008_f64, 13.404_954_f64),
+                        &response()
+                    )
+                    .expect("Mapping works"),
+                Weather {
+                    temperature: Fahrenheit::from(291.4_f32).to_celsius(),
+                    ..weather()
+                }
+            );
+        }
+
+        #[test]
+        fn reject_several_values() {
+            assert_eq!(
+                provider(&CONFIG.replace(
+                    r#"path = "$.readings[?@.sensor == 'wind'].direction""#,
+                    r#"path = "$.readings[*].sensor""#,
+                ))
+                .to_weather(
+                    &request("Berlin", 52.520_008_f64, 13.404_954_f64),
+                    &response()
+                )
+                .expect_err("Several values")
+                .to_string(),
+                "Path $.readings[*].sensor is ambiguous: nodelist expected to contain at most one entry, but instead contains 3 entries"
+            );
+        }
+    }
+}
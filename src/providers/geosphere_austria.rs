@@ -0,0 +1,311 @@
+use crate::providers::http_request::{request_cached, Configuration, HttpCacheRequest};
+use crate::providers::units::{
+    Celsius, Coordinates, Degrees, Hectopascal, MetersPerSecond, Millimeters, Ratio,
+};
+use crate::providers::{
+    calculate_distance, find_closest, HttpRequestCache, Precipitation, Sunshine, Weather,
+    WeatherProvider, WeatherRequest,
+};
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use log::trace;
+use reqwest::blocking::Client;
+use reqwest::{Method, Url};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const SOURCE_URI: &str = "at.geosphere";
+const ENDPOINT_URL: &str = "https://dataset.api.hub.geosphere.at/v1/station/current/tawes-v1-10min";
+const PARAMETERS: [&str; 7] = ["TL", "RF", "P", "FF", "DD", "RR", "SO"];
+
+const MEASUREMENT_INTERVAL: Duration = Duration::from_secs(60 * 10);
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GeosphereAustria {
+    #[serde(flatten)]
+    cache: Configuration,
+}
+
+#[derive(Deserialize, Debug)]
+struct GeosphereMetadata {
+    stations: Vec<WeatherStation>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+struct WeatherStation {
+    id: String,
+    name: String,
+    lat: f64,
+    lon: f64,
+    is_active: bool,
+}
+
+impl WeatherStation {
+    fn coordinates(&self) -> Coordinates {
+        Coordinates {
+            latitude: self.lat.into(),
+            longitude: self.lon.into(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct GeosphereResponse {
+    #[serde(deserialize_with = "deserialize_timestamps")]
+    timestamps: Vec<DateTime<Utc>>,
+    features: Vec<GeosphereFeature>,
+}
+
+// Timestamps are RFC 3339 without seconds, e.g. 2024-06-01T12:50+00:00
+fn deserialize_timestamps<'de, D>(deserializer: D) -> Result<Vec<DateTime<Utc>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|timestamp| {
+            DateTime::parse_from_str(timestamp, "%Y-%m-%dT%H:%M%:z")
+                .map(|time| time.with_timezone(&Utc))
+                .map_err(serde::de::Error::custom)
+        })
+        .collect()
+}
+
+#[derive(Deserialize, Debug)]
+struct GeosphereFeature {
+    properties: GeosphereProperties,
+}
+
+#[derive(Deserialize, Debug)]
+struct GeosphereProperties {
+    parameters: GeosphereParameters,
+}
+
+// See https://dataset.api.hub.geosphere.at/v1/station/current/tawes-v1-10min/metadata
+#[derive(Deserialize, Debug)]
+struct GeosphereParameters {
+    #[serde(rename = "TL")]
+    temperature: Option<GeosphereParameter<Celsius>>,
+    #[serde(rename = "RF")]
+    relative_humidity: Option<GeosphereParameter<Ratio>>,
+    #[serde(rename = "P")]
+    station_pressure: Option<GeosphereParameter<Hectopascal>>,
+    #[serde(rename = "FF")]
+    wind_speed: Option<GeosphereParameter<MetersPerSecond>>,
+    #[serde(rename = "DD")]
+    wind_direction: Option<GeosphereParameter<Degrees>>,
+    #[serde(rename = "RR")]
+    precipitation: Option<GeosphereParameter<Millimeters>>,
+    // Seconds of sunshine within the measurement interval
+    #[serde(rename = "SO")]
+    sunshine_duration: Option<GeosphereParameter<f64>>,
+}
+
+// One value per timestamp, null if the station did not report it
+#[derive(Deserialize, Debug)]
+struct GeosphereParameter<T> {
+    data: Vec<Option<T>>,
+}
+
+impl<T: Copy> GeosphereParameter<T> {
+    fn latest(&self) -> Option<T> {
+        self.data.last().copied().flatten()
+    }
+}
+
+fn latest<T: Copy>(parameter: Option<&GeosphereParameter<T>>) -> Option<T> {
+    parameter.and_then(GeosphereParameter::latest)
+}
+
+fn find_closest_active_station<'stations>(
+    coords: &Coordinates,
+    stations: &'stations [WeatherStation],
+) -> anyhow::Result<&'stations WeatherStation> {
+    let active_stations: Vec<&WeatherStation> = stations
+        .iter()
+        .filter(|station| station.is_active)
+        .collect();
+
+    find_closest(coords, &active_stations, |station| station.coordinates()).copied()
+}
+
+fn to_weather(
+    request: &WeatherRequest<Coordinates>,
+    station: &WeatherStation,
+    response: &GeosphereResponse,
+) -> anyhow::Result<Weather> {
+    let parameters = &response
+        .features
+        .first()
+        .ok_or_else(|| anyhow!("No measurements for station {}", station.id))?
+        .properties
+        .parameters;
+    let coordinates = station.coordinates();
+
+    Ok(Weather {
+        city: Some(station.name.clone()),
+        distance: Some(calculate_distance(&request.query, &coordinates)),
+        relative_humidity: latest(parameters.relative_humidity.as_ref()),
+        station_pressure: latest(parameters.station_pressure.as_ref()),
+        wind_speed: latest(parameters.wind_speed.as_ref()),
+        wind_direction: latest(parameters.wind_direction.as_ref()),
+        precipitation: latest(parameters.precipitation.as_ref()).map(|amount| Precipitation {
+            amount,
+            period: MEASUREMENT_INTERVAL,
+        }),
+        sunshine: latest(parameters.sunshine_duration.as_ref())
+            .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+            .map(|duration| Sunshine {
+                duration,
+                period: MEASUREMENT_INTERVAL,
+            }),
+        observation_time: response.timestamps.last().copied(),
+        ..Weather::new(
+            SOURCE_URI.into(),
+            request.name.clone(),
+            coordinates,
+            latest(parameters.temperature.as_ref())
+                .ok_or_else(|| anyhow!("No temperature reported by station {}", station.name))?,
+        )
+    })
+}
+
+impl WeatherProvider for GeosphereAustria {
+    fn id(&self) -> &str {
+        SOURCE_URI
+    }
+
+    fn for_coordinates(
+        &self,
+        client: &Client,
+        cache: &HttpRequestCache,
+        request: &WeatherRequest<Coordinates>,
+    ) -> anyhow::Result<Weather> {
+        let metadata: GeosphereMetadata = request_cached(&HttpCacheRequest::new_json_request(
+            SOURCE_URI,
+            client,
+            cache,
+            &Method::GET,
+            &Url::parse(&format!("{ENDPOINT_URL}/metadata"))?,
+        ))?;
+
+        let station = find_closest_active_station(&request.query, &metadata.stations)?;
+        trace!("Found closest weather station {:?}", station);
+
+        let url = Url::parse_with_params(
+            ENDPOINT_URL,
+            &[
+                ("parameters", PARAMETERS.join(",")),
+                ("station_ids", station.id.clone()),
+            ],
+        )?;
+
+        let response: GeosphereResponse = request_cached(&HttpCacheRequest::new_json_request(
+            SOURCE_URI,
+            client,
+            cache,
+            &Method::GET,
+            &url,
+        ))?;
+
+        to_weather(request, station, &response)
+    }
+
+    fn refresh_interval(&self) -> Duration {
+        self.cache.refresh_interval
+    }
+
+    // Station metadata and measurements
+    fn cache_cardinality(&self) -> usize {
+        2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod find_closest_active_station {
+        use crate::providers::geosphere_austria::{
+            find_closest_active_station, GeosphereMetadata, WeatherStation,
+        };
+        use crate::providers::testing::{json_fixture, request};
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn skip_inactive_stations() {
+            let metadata: GeosphereMetadata = json_fixture("geosphere_austria/metadata.json");
+
+            assert_eq!(
+                find_closest_active_station(
+                    &request("Vienna", 48.2082_f64, 16.3738_f64).query,
+                    &metadata.stations,
+                )
+                .expect("Station found"),
+                &WeatherStation {
+                    id: "11035".into(),
+                    name: "WIEN/HOHE WARTE".into(),
+                    lat: 48.248_611_f64,
+                    lon: 16.356_389_f64,
+                    is_active: true,
+                }
+            );
+        }
+    }
+
+    mod to_weather {
+        use crate::providers::geosphere_austria::{to_weather, WeatherStation};
+        use crate::providers::testing::{json_fixture, request};
+        use crate::providers::units::{Coordinates, Ratio};
+        use crate::providers::{Sunshine, Weather};
+        use chrono::{DateTime, Utc};
+        use pretty_assertions::assert_eq;
+        use std::time::Duration;
+
+        fn station() -> WeatherStation {
+            WeatherStation {
+                id: "11035".into(),
+                name: "WIEN/HOHE WARTE".into(),
+                lat: 48.248_611_f64,
+                lon: 16.356_389_f64,
+                is_active: true,
+            }
+        }
+
+        #[test]
+        fn map_latest_measurements() {
+            assert_eq!(
+                to_weather(
+                    &request("Vienna", 48.2082_f64, 16.3738_f64),
+                    &station(),
+                    &json_fixture("geosphere_austria/current.json"),
+                )
+                .expect("Mapping works"),
+                Weather {
+                    city: Some("WIEN/HOHE WARTE".into()),
+                    distance: Some(4_674.924_047_297_213_f64.into()),
+                    relative_humidity: Some(Ratio::Percentage(54_f64)),
+                    station_pressure: Some(993.1_f64.into()),
+                    wind_speed: Some(3.2_f64.into()),
+                    wind_direction: Some(290_f64.into()),
+                    sunshine: Some(Sunshine {
+                        duration: Duration::from_secs(480),
+                        period: Duration::from_secs(600),
+                    }),
+                    observation_time: Some(
+                        DateTime::parse_from_rfc3339("2024-06-01T12:50:00Z")
+                            .expect("Static value")
+                            .with_timezone(&Utc)
+                    ),
+                    ..Weather::new(
+                        "at.geosphere".into(),
+                        "Vienna".into(),
+                        Coordinates {
+                            latitude: 48.248_611_f64.into(),
+                            longitude: 16.356_389_f64.into(),
+                        },
+                        21.4_f32.into(),
+                    )
+                }
+            );
+        }
+    }
+}
@@ -1,14 +1,15 @@
 use crate::providers::HttpRequestCache;
 use anyhow::anyhow;
-use derive_more::Constructor;
+use chrono::{DateTime, Utc};
 use failsafe::backoff::{exponential, Exponential};
 use failsafe::failure_policy::{consecutive_failures, ConsecutiveFailures};
 use failsafe::{CircuitBreaker, Config, Error, StateMachine};
 use log::{debug, trace};
 use moka::sync::Cache as MokaCache;
 use once_cell::sync::Lazy;
-use reqwest::blocking::{Client, Response};
-use reqwest::{Method, Url};
+use reqwest::blocking::Client;
+use reqwest::header::{HeaderMap, EXPIRES, IF_MODIFIED_SINCE, LAST_MODIFIED};
+use reqwest::{Method, StatusCode, Url};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -29,7 +30,6 @@ const fn default_refresh_interval() -> Duration {
     Duration::from_secs(60 * 10)
 }
 
-#[derive(Constructor)]
 pub struct HttpCacheRequest<'req, R: Debug = String> {
     source: &'req str,
     client: &'req Client,
@@ -37,6 +37,8 @@ pub struct HttpCacheRequest<'req, R: Debug = String> {
     method: &'req Method,
     url: &'req Url,
     deserialize: fn(body: &Vec<u8>) -> anyhow::Result<R>,
+    conditional: bool,
+    headers: HeaderMap,
 }
 
 const CONSECUTIVE_FAILURE_COUNT: u32 = 3;
@@ -48,6 +50,97 @@ type HttpCircuitBreaker = StateMachine<ConsecutiveFailures<Exponential>, ()>;
 static CIRCUIT_BREAKER_REGISTRY: Lazy<RwLock<HashMap<String, HttpCircuitBreaker>>> =
     Lazy::new(|| RwLock::new(HashMap::new()));
 
+// Responses are kept beyond the lifetime of the cache to revalidate them with conditional requests
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct ConditionalResponse {
+    expires: Option<DateTime<Utc>>,
+    last_modified: Option<String>,
+    body: Vec<u8>,
+}
+
+impl ConditionalResponse {
+    fn from_headers(headers: &HeaderMap, body: Vec<u8>) -> Self {
+        Self {
+            expires: headers
+                .get(EXPIRES)
+                .and_then(|value| value.to_str().ok())
+                .and_then(parse_http_date),
+            last_modified: headers
+                .get(LAST_MODIFIED)
+                .and_then(|value| value.to_str().ok())
+                .map(ToOwned::to_owned),
+            body,
+        }
+    }
+
+    // A 304 response may omit headers that did not change, those are kept from the previous response
+    fn revalidate(self, headers: &HeaderMap) -> Self {
+        let updated = Self::from_headers(headers, self.body);
+
+        Self {
+            expires: updated.expires.or(self.expires),
+            last_modified: updated.last_modified.or(self.last_modified),
+            body: updated.body,
+        }
+    }
+
+    fn is_fresh(&self, now: DateTime<Utc>) -> bool {
+        self.expires.is_some_and(|expires| expires > now)
+    }
+}
+
+// Bounded like the response caches, responses of URLs no longer requested are dropped eventually
+const MAX_CONDITIONAL_RESPONSES: u64 = 256;
+const CONDITIONAL_RESPONSE_IDLE_SECS: u64 = 60 * 60 * 24;
+
+static CONDITIONAL_RESPONSE_REGISTRY: Lazy<MokaCache<(Method, Url), ConditionalResponse>> =
+    Lazy::new(|| {
+        MokaCache::builder()
+            .max_capacity(MAX_CONDITIONAL_RESPONSES)
+            .time_to_idle(Duration::from_secs(CONDITIONAL_RESPONSE_IDLE_SECS))
+            .build()
+    });
+
+fn parse_http_date(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc2822(value)
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+impl<'req, R: Debug> HttpCacheRequest<'req, R> {
+    pub fn new(
+        source: &'req str,
+        client: &'req Client,
+        cache: &'req HttpRequestCache,
+        method: &'req Method,
+        url: &'req Url,
+        deserialize: fn(body: &Vec<u8>) -> anyhow::Result<R>,
+    ) -> Self {
+        Self {
+            source,
+            client,
+            cache,
+            method,
+            url,
+            deserialize,
+            conditional: false,
+            headers: HeaderMap::new(),
+        }
+    }
+
+    // Headers are not part of the cache key
+    pub fn with_headers(mut self, headers: HeaderMap) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    // Honor Expires and revalidate with If-Modified-Since once the response expired
+    pub const fn with_conditional_requests(mut self) -> Self {
+        self.conditional = true;
+        self
+    }
+}
+
 impl HttpCacheRequest<'_> {
     pub fn new_json_request<'req, T: Debug + DeserializeOwned>(
         source: &'req str,
@@ -187,31 +280,250 @@ fn request_url_with_circuit_breaker<R: Debug>(
             "Circuit breaker {} is open and prevented request",
             circuit_breaker_scope
         )),
-        Ok(response) => {
-            trace!(
-                "Request to {} return with status code {}",
-                request.url.to_string(),
-                response.status()
-            );
+        Ok(body) => Ok(body),
+    }
+}
+
+fn request_url<R: Debug>(request: &HttpCacheRequest<R>) -> anyhow::Result<Vec<u8>> {
+    let key = (request.method.clone(), request.url.clone());
+    let previous_response = if request.conditional {
+        CONDITIONAL_RESPONSE_REGISTRY.get(&key)
+    } else {
+        None
+    };
 
-            Ok(response.bytes().map(|v| v.to_vec())?)
+    if let Some(previous) = &previous_response {
+        if previous.is_fresh(Utc::now()) {
+            debug!(
+                "Response for {} has not expired yet, reusing it",
+                request.url
+            );
+            return Ok(previous.body.clone());
         }
     }
-}
 
-fn request_url<R: Debug>(request: &HttpCacheRequest<R>) -> anyhow::Result<Response> {
-    let response = request
+    let mut request_builder = request
         .client
         .request(request.method.clone(), request.url.clone())
-        .send()?;
+        .headers(request.headers.clone());
 
-    if !response.status().is_success() {
-        return Err(anyhow!(
-            "Request for provider {} return status code {}",
-            request.source,
-            response.status()
-        ));
+    if let Some(last_modified) = previous_response
+        .as_ref()
+        .and_then(|previous| previous.last_modified.as_ref())
+    {
+        request_builder = request_builder.header(IF_MODIFIED_SINCE, last_modified);
+    }
+
+    let response = request_builder.send()?;
+
+    trace!(
+        "Request to {} return with status code {}",
+        request.url.to_string(),
+        response.status()
+    );
+
+    let conditional_response = match previous_response {
+        Some(previous) if response.status() == StatusCode::NOT_MODIFIED => {
+            debug!("Response for {} was not modified", request.url);
+            previous.revalidate(response.headers())
+        }
+        _ if !response.status().is_success() => {
+            return Err(anyhow!(
+                "Request for provider {} return status code {}",
+                request.source,
+                response.status()
+            ));
+        }
+        _ => {
+            let headers = response.headers().clone();
+            ConditionalResponse::from_headers(&headers, response.bytes()?.to_vec())
+        }
+    };
+
+    if request.conditional {
+        CONDITIONAL_RESPONSE_REGISTRY.insert(key, conditional_response.clone());
+    }
+
+    Ok(conditional_response.body)
+}
+
+#[cfg(test)]
+mod tests {
+    mod conditional_response {
+        use crate::providers::http_request::ConditionalResponse;
+        use chrono::{DateTime, Utc};
+        use pretty_assertions::assert_eq;
+        use reqwest::header::{HeaderMap, HeaderValue, EXPIRES, LAST_MODIFIED};
+
+        fn time(rfc3339: &str) -> DateTime<Utc> {
+            DateTime::parse_from_rfc3339(rfc3339)
+                .expect("Static value")
+                .with_timezone(&Utc)
+        }
+
+        #[test]
+        fn parse_headers() {
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                EXPIRES,
+                HeaderValue::from_static("Sat, 01 Jun 2024 12:30:00 GMT"),
+            );
+            headers.insert(
+                LAST_MODIFIED,
+                HeaderValue::from_static("Sat, 01 Jun 2024 12:00:00 GMT"),
+            );
+
+            assert_eq!(
+                ConditionalResponse::from_headers(&headers, vec![]),
+                ConditionalResponse {
+                    expires: Some(time("2024-06-01T12:30:00Z")),
+                    last_modified: Some("Sat, 01 Jun 2024 12:00:00 GMT".into()),
+                    body: vec![],
+                }
+            );
+        }
+
+        #[test]
+        fn fresh_until_expiry() {
+            let response = ConditionalResponse {
+                expires: Some(time("2024-06-01T12:30:00Z")),
+                last_modified: None,
+                body: vec![],
+            };
+
+            assert_eq!(
+                [
+                    response.is_fresh(time("2024-06-01T12:29:59Z")),
+                    response.is_fresh(time("2024-06-01T12:30:00Z")),
+                ],
+                [true, false]
+            );
+        }
+
+        #[test]
+        fn keep_headers_missing_on_revalidation() {
+            let mut expires = HeaderMap::new();
+            expires.insert(
+                EXPIRES,
+                HeaderValue::from_static("Sat, 01 Jun 2024 13:30:00 GMT"),
+            );
+
+            assert_eq!(
+                [HeaderMap::new(), expires].map(|headers| ConditionalResponse {
+                    expires: Some(time("2024-06-01T12:30:00Z")),
+                    last_modified: Some("Sat, 01 Jun 2024 12:00:00 GMT".into()),
+                    body: b"{}".to_vec(),
+                }
+                .revalidate(&headers)),
+                [
+                    ConditionalResponse {
+                        expires: Some(time("2024-06-01T12:30:00Z")),
+                        last_modified: Some("Sat, 01 Jun 2024 12:00:00 GMT".into()),
+                        body: b"{}".to_vec(),
+                    },
+                    ConditionalResponse {
+                        expires: Some(time("2024-06-01T13:30:00Z")),
+                        last_modified: Some("Sat, 01 Jun 2024 12:00:00 GMT".into()),
+                        body: b"{}".to_vec(),
+                    },
+                ]
+            );
+        }
+
+        #[test]
+        fn never_fresh_without_expires() {
+            assert_eq!(
+                ConditionalResponse::from_headers(&HeaderMap::new(), vec![]).is_fresh(Utc::now()),
+                false
+            );
+        }
+    }
+
+    mod request_url {
+        use crate::providers::http_request::{request_url, HttpCacheRequest};
+        use crate::providers::HttpRequestCache;
+        use pretty_assertions::assert_eq;
+        use reqwest::blocking::Client;
+        use reqwest::{Method, Url};
+        use std::io::{BufRead, BufReader, Write};
+        use std::net::TcpListener;
+        use std::thread;
+
+        // Answers each connection with the next response and returns the If-Modified-Since headers
+        fn serve(responses: &'static [&'static str]) -> (Url, thread::JoinHandle<Vec<String>>) {
+            let listener = TcpListener::bind("127.0.0.1:0").expect("Port is free");
+            let url = Url::parse(&format!(
+                "http://{}/current",
+                listener.local_addr().expect("Listener is bound")
+            ))
+            .expect("URL is valid");
+
+            let server = thread::spawn(move || {
+                responses
+                    .iter()
+                    .map(|response| {
+                        let (mut stream, _) = listener.accept().expect("Client connects");
+                        let if_modified_since = BufReader::new(&stream)
+                            .lines()
+                            .map_while(Result::ok)
+                            .take_while(|line| !line.is_empty())
+                            .filter_map(|line| {
+                                line.strip_prefix("if-modified-since: ")
+                                    .map(ToOwned::to_owned)
+                            })
+                            .collect();
+                        stream
+                            .write_all(response.as_bytes())
+                            .expect("Response is sent");
+                        if_modified_since
+                    })
+                    .collect()
+            });
+
+            (url, server)
+        }
+
+        #[test]
+        fn revalidate_after_not_modified() {
+            let (url, server) = serve(&[
+                "HTTP/1.1 200 OK\r\nLast-Modified: Sat, 01 Jun 2024 12:00:00 GMT\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}",
+                "HTTP/1.1 304 Not Modified\r\nConnection: close\r\n\r\n",
+                "HTTP/1.1 304 Not Modified\r\nConnection: close\r\n\r\n",
+            ]);
+            let client = Client::new();
+            let cache = HttpRequestCache::new(1);
+            let request = HttpCacheRequest::new_json_request::<()>(
+                "org.example",
+                &client,
+                &cache,
+                &Method::GET,
+                &url,
+            )
+            .with_conditional_requests();
+
+            let bodies = [(); 3].map(|()| request_url(&request).expect("Request works"));
+
+            assert_eq!(
+                (bodies, server.join().expect("Server does not panic")),
+                (
+                    [b"{}".to_vec(), b"{}".to_vec(), b"{}".to_vec()],
+                    vec![
+                        String::new(),
+                        "Sat, 01 Jun 2024 12:00:00 GMT".into(),
+                        "Sat, 01 Jun 2024 12:00:00 GMT".into(),
+                    ]
+                )
+            );
+        }
     }
 
-    Ok(response)
+    mod parse_http_date {
+        use crate::providers::http_request::parse_http_date;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn invalid_date() {
+            assert_eq!(parse_http_date("invalid"), None);
+        }
+    }
 }
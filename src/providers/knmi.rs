@@ -0,0 +1,348 @@
+use crate::providers::http_request::{request_cached, Configuration, HttpCacheRequest};
+use crate::providers::units::{Celsius, Coordinates, Ratio};
+use crate::providers::{
+    calculate_distance, find_closest, HttpRequestCache, Weather, WeatherProvider, WeatherRequest,
+};
+use anyhow::anyhow;
+use chrono::{DateTime, TimeDelta, Utc};
+use log::trace;
+use reqwest::blocking::Client;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use reqwest::{Method, Url};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+const SOURCE_URI: &str = "nl.knmi";
+const DATASET_URL: &str = "https://api.dataplatform.knmi.nl/open-data/v1/datasets/10-minute-in-situ-meteorological-observations/versions/1.0";
+
+// Times are given as seconds since 1950-01-01T00:00:00Z
+const TIME_EPOCH_UNIX_SECONDS: i64 = -631_152_000;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Knmi {
+    api_key: String,
+    #[serde(flatten)]
+    cache: Configuration,
+}
+
+#[derive(Deserialize, Debug)]
+struct KnmiFileList {
+    files: Vec<KnmiFile>,
+}
+
+#[derive(Deserialize, Debug)]
+struct KnmiFile {
+    filename: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct KnmiFileUrl {
+    temporary_download_url: String,
+}
+
+// Variables read from the observation files, each with one value per station
+type Variables = BTreeMap<&'static str, Vec<Option<f64>>>;
+
+const UNSUPPORTED: &str = "Reading KNMI observations requires the knmi feature";
+
+#[derive(Debug)]
+struct WeatherStation {
+    index: usize,
+    name: String,
+    coordinates: Coordinates,
+}
+
+// The files are NetCDF-4 (HDF5) with the station names stored as strings
+#[cfg(feature = "knmi")]
+fn read_file(body: &[u8]) -> anyhow::Result<(Vec<String>, Variables)> {
+    let file = netcdf::open_mem(None, body)?;
+    let find = |name: &str| {
+        file.variable(name)
+            .ok_or_else(|| anyhow!("Missing variable {name}"))
+    };
+
+    let station_names = find("stationname")?;
+    let names = (0..station_names.len())
+        .map(|index| station_names.get_string(index))
+        .collect::<Result<_, _>>()?;
+
+    let variables = ["time", "lat", "lon", "ta", "rh", "pp", "ff", "gff", "dd"]
+        .into_iter()
+        .map(|name| {
+            let variable = find(name)?;
+            // Missing values are marked with the fill value of the variable
+            let fill_value = variable
+                .attribute_value("_FillValue")
+                .transpose()?
+                .map(f64::try_from)
+                .transpose()?;
+            let values = variable
+                .get_values::<f64, _>(..)?
+                .into_iter()
+                .map(|value| {
+                    (!value.is_nan()
+                        && fill_value.map_or(true, |fill| value.total_cmp(&fill).is_ne()))
+                    .then_some(value)
+                })
+                .collect();
+            Ok((name, values))
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    Ok((names, variables))
+}
+
+#[cfg(not(feature = "knmi"))]
+fn read_file(_body: &[u8]) -> anyhow::Result<(Vec<String>, Variables)> {
+    Err(anyhow!(UNSUPPORTED))
+}
+
+fn value(variables: &Variables, name: &str, index: usize) -> Option<f64> {
+    variables
+        .get(name)
+        .and_then(|values| values.get(index))
+        .copied()
+        .flatten()
+}
+
+// Stations without a temperature only measure e.g. precipitation or wind and are skipped
+fn read_weather_stations(names: &[String], variables: &Variables) -> Vec<WeatherStation> {
+    names
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| value(variables, "ta", *index).is_some())
+        .filter_map(|(index, name)| {
+            Some(WeatherStation {
+                index,
+                name: name.clone(),
+                coordinates: Coordinates {
+                    latitude: value(variables, "lat", index)?.into(),
+                    longitude: value(variables, "lon", index)?.into(),
+                },
+            })
+        })
+        .collect()
+}
+
+fn read_observation_time(variables: &Variables) -> anyhow::Result<DateTime<Utc>> {
+    let seconds = variables
+        .get("time")
+        .and_then(|values| values.last())
+        .copied()
+        .flatten()
+        .ok_or_else(|| anyhow!("Missing observation time"))?;
+
+    DateTime::from_timestamp(TIME_EPOCH_UNIX_SECONDS, 0)
+        .zip(TimeDelta::from_std(Duration::try_from_secs_f64(seconds)?).ok())
+        .and_then(|(epoch, delta)| epoch.checked_add_signed(delta))
+        .ok_or_else(|| anyhow!("Observation time {seconds} out of range"))
+}
+
+fn to_weather(
+    request: &WeatherRequest<Coordinates>,
+    names: &[String],
+    variables: &Variables,
+) -> anyhow::Result<Weather> {
+    let stations = read_weather_stations(names, variables);
+    let station = find_closest(&request.query, &stations, |station| {
+        station.coordinates.clone()
+    })?;
+    trace!("Found closest weather station {:?}", station);
+
+    // Each file holds a single 10-minute interval, so there is one value per station
+    let value = |name: &str| value(variables, name, station.index);
+
+    Ok(Weather {
+        city: Some(station.name.clone()),
+        distance: Some(calculate_distance(&request.query, &station.coordinates)),
+        relative_humidity: value("rh").map(Ratio::Percentage),
+        sea_level_pressure: value("pp").map(Into::into),
+        wind_speed: value("ff").map(Into::into),
+        wind_gust_speed: value("gff").map(Into::into),
+        wind_direction: value("dd").map(Into::into),
+        observation_time: Some(read_observation_time(variables)?),
+        ..Weather::new(
+            SOURCE_URI.into(),
+            request.name.clone(),
+            station.coordinates.clone(),
+            value("ta")
+                .map(Celsius::from_f64)
+                .ok_or_else(|| anyhow!("No temperature reported by station {}", station.name))?,
+        )
+    })
+}
+
+impl WeatherProvider for Knmi {
+    fn id(&self) -> &str {
+        SOURCE_URI
+    }
+
+    fn for_coordinates(
+        &self,
+        client: &Client,
+        cache: &HttpRequestCache,
+        request: &WeatherRequest<Coordinates>,
+    ) -> anyhow::Result<Weather> {
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, HeaderValue::from_str(&self.api_key)?);
+
+        let list_url = Url::parse_with_params(
+            &format!("{DATASET_URL}/files"),
+            &[
+                ("maxKeys", "1"),
+                ("orderBy", "created"),
+                ("sorting", "desc"),
+            ],
+        )?;
+        let files: KnmiFileList = request_cached(
+            &HttpCacheRequest::new_json_request(SOURCE_URI, client, cache, &Method::GET, &list_url)
+                .with_headers(headers.clone()),
+        )?;
+        let latest_file = files
+            .files
+            .first()
+            .ok_or_else(|| anyhow!("No observation files available"))?;
+
+        let file_url = Url::parse(&format!("{DATASET_URL}/files/{}/url", latest_file.filename))?;
+        let download: KnmiFileUrl = request_cached(
+            &HttpCacheRequest::new_json_request(SOURCE_URI, client, cache, &Method::GET, &file_url)
+                .with_headers(headers),
+        )?;
+
+        // The download URL is pre-signed and must not carry the API key
+        let (names, variables) = request_cached(&HttpCacheRequest::new(
+            SOURCE_URI,
+            client,
+            cache,
+            &Method::GET,
+            &Url::parse(&download.temporary_download_url)?,
+            |body| read_file(body),
+        ))?;
+
+        to_weather(request, &names, &variables)
+    }
+
+    // Without the NetCDF library the files cannot be read, so fail on startup instead of on every request
+    fn start(&self) -> anyhow::Result<()> {
+        if cfg!(feature = "knmi") {
+            Ok(())
+        } else {
+            Err(anyhow!(UNSUPPORTED))
+        }
+    }
+
+    fn refresh_interval(&self) -> Duration {
+        self.cache.refresh_interval
+    }
+
+    // File list, download URL and file
+    fn cache_cardinality(&self) -> usize {
+        3
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod to_weather {
+        use crate::providers::knmi::{to_weather, Variables};
+        use crate::providers::testing::request;
+        use crate::providers::units::{Coordinates, Ratio};
+        use crate::providers::Weather;
+        use chrono::{DateTime, Utc};
+        use pretty_assertions::assert_eq;
+
+        fn names() -> Vec<String> {
+            vec![
+                "DE BILT AWS".into(),
+                "CABAUW MAST".into(),
+                "ROTTERDAM THE HAGUE AP".into(),
+            ]
+        }
+
+        // Cabauw is closer to Utrecht than De Bilt in this fixture but only measures wind
+        fn variables() -> Variables {
+            Variables::from([
+                ("time", vec![Some(2_348_398_200_f64)]),
+                (
+                    "lat",
+                    vec![Some(52.1_f64), Some(52.0_f64), Some(51.962_f64)],
+                ),
+                ("lon", vec![Some(5.18_f64), Some(5.1_f64), Some(4.447_f64)]),
+                ("ta", vec![Some(18.3_f64), None, Some(17.9_f64)]),
+                ("rh", vec![Some(71_f64), Some(69_f64), None]),
+                ("pp", vec![Some(1013.2_f64), None, Some(1013.5_f64)]),
+                ("ff", vec![Some(4.1_f64), Some(6.3_f64), Some(5.2_f64)]),
+                ("gff", vec![Some(7.2_f64), Some(9.8_f64), None]),
+                ("dd", vec![Some(230_f64), Some(240_f64), Some(220_f64)]),
+            ])
+        }
+
+        fn observation_time() -> DateTime<Utc> {
+            DateTime::parse_from_rfc3339("2024-06-01T12:50:00Z")
+                .expect("Static value")
+                .with_timezone(&Utc)
+        }
+
+        #[test]
+        fn map_observations_of_closest_station_with_temperature() {
+            assert_eq!(
+                to_weather(
+                    &request("Utrecht", 52.0_f64, 5.11_f64),
+                    &names(),
+                    &variables()
+                )
+                .expect("Mapping works"),
+                Weather {
+                    city: Some("DE BILT AWS".into()),
+                    distance: Some(12_106.045_024_402_836_f64.into()),
+                    relative_humidity: Some(Ratio::Percentage(71_f64)),
+                    sea_level_pressure: Some(1013.2_f64.into()),
+                    wind_speed: Some(4.1_f64.into()),
+                    wind_gust_speed: Some(7.2_f64.into()),
+                    wind_direction: Some(230_f64.into()),
+                    observation_time: Some(observation_time()),
+                    ..Weather::new(
+                        "nl.knmi".into(),
+                        "Utrecht".into(),
+                        Coordinates {
+                            latitude: 52.1_f64.into(),
+                            longitude: 5.18_f64.into(),
+                        },
+                        18.3_f32.into(),
+                    )
+                }
+            );
+        }
+
+        #[test]
+        fn missing_values_are_skipped() {
+            assert_eq!(
+                to_weather(
+                    &request("Rotterdam", 51.9244_f64, 4.4777_f64),
+                    &names(),
+                    &variables()
+                )
+                .expect("Mapping works"),
+                Weather {
+                    city: Some("ROTTERDAM THE HAGUE AP".into()),
+                    distance: Some(4_680.648_882_054_53_f64.into()),
+                    sea_level_pressure: Some(1013.5_f64.into()),
+                    wind_speed: Some(5.2_f64.into()),
+                    wind_direction: Some(220_f64.into()),
+                    observation_time: Some(observation_time()),
+                    ..Weather::new(
+                        "nl.knmi".into(),
+                        "Rotterdam".into(),
+                        Coordinates {
+                            latitude: 51.962_f64.into(),
+                            longitude: 4.447_f64.into(),
+                        },
+                        17.9_f32.into(),
+                    )
+                }
+            );
+        }
+    }
+}
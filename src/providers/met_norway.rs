@@ -0,0 +1,259 @@
+use crate::providers::http_request::{request_cached, Configuration, HttpCacheRequest};
+use crate::providers::units::{
+    Celsius, Condition, Coordinates, Degrees, Hectopascal, MetersPerSecond, Millimeters, Ratio,
+};
+use crate::providers::{
+    HttpRequestCache, Precipitation, Weather, WeatherCondition, WeatherProvider, WeatherRequest,
+};
+use anyhow::anyhow;
+use reqwest::blocking::Client;
+use reqwest::{Method, Url};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const SOURCE_URI: &str = "no.met";
+const ENDPOINT_URL: &str = "https://api.met.no/weatherapi/locationforecast/2.0/compact";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MetNorway {
+    #[serde(flatten)]
+    cache: Configuration,
+}
+
+#[derive(Deserialize, Debug)]
+struct MetNorwayResponse {
+    properties: MetNorwayProperties,
+}
+
+#[derive(Deserialize, Debug)]
+struct MetNorwayProperties {
+    timeseries: Vec<MetNorwayTimeseries>,
+}
+
+#[derive(Deserialize, Debug)]
+struct MetNorwayTimeseries {
+    data: MetNorwayData,
+}
+
+#[derive(Deserialize, Debug)]
+struct MetNorwayData {
+    instant: MetNorwayInstant,
+    next_1_hours: Option<MetNorwayNextHour>,
+}
+
+#[derive(Deserialize, Debug)]
+struct MetNorwayInstant {
+    details: MetNorwayInstantDetails,
+}
+
+#[derive(Deserialize, Debug)]
+struct MetNorwayInstantDetails {
+    air_temperature: Celsius,
+    relative_humidity: Option<Ratio>,
+    air_pressure_at_sea_level: Option<Hectopascal>,
+    wind_speed: Option<MetersPerSecond>,
+    wind_from_direction: Option<Degrees>,
+    cloud_area_fraction: Option<Ratio>,
+}
+
+#[derive(Deserialize, Debug)]
+struct MetNorwayNextHour {
+    summary: MetNorwaySummary,
+    details: Option<MetNorwayNextHourDetails>,
+}
+
+#[derive(Deserialize, Debug)]
+struct MetNorwaySummary {
+    symbol_code: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct MetNorwayNextHourDetails {
+    precipitation_amount: Option<Millimeters>,
+}
+
+// Symbol codes carry a variant suffix like _day or _night, see https://api.met.no/weatherapi/weathericon/2.0/documentation
+fn condition_from_symbol_code(symbol_code: &str) -> Condition {
+    let symbol = symbol_code
+        .split_once('_')
+        .map_or(symbol_code, |(symbol, _)| symbol);
+
+    match symbol {
+        "clearsky" | "fair" => Condition::Clear,
+        "partlycloudy" => Condition::PartlyCloudy,
+        "cloudy" => Condition::Cloudy,
+        "fog" => Condition::Fog,
+        _ if symbol.contains("thunder") => Condition::Thunderstorm,
+        _ if symbol.contains("sleet") => Condition::Sleet,
+        _ if symbol.contains("snow") => Condition::Snow,
+        _ if symbol.contains("rain") => Condition::Rain,
+        _ => Condition::Unknown,
+    }
+}
+
+fn to_weather(
+    request: &WeatherRequest<Coordinates>,
+    response: MetNorwayResponse,
+) -> anyhow::Result<Weather> {
+    // The first entry of the timeseries is the current hour
+    let current = response
+        .properties
+        .timeseries
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("Empty timeseries"))?;
+    let details = current.data.instant.details;
+    let next_hour = current.data.next_1_hours;
+
+    Ok(Weather {
+        relative_humidity: details.relative_humidity,
+        sea_level_pressure: details.air_pressure_at_sea_level,
+        wind_speed: details.wind_speed,
+        wind_direction: details.wind_from_direction,
+        precipitation: next_hour
+            .as_ref()
+            .and_then(|hour| hour.details.as_ref())
+            .and_then(|hour_details| hour_details.precipitation_amount)
+            .map(|amount| Precipitation {
+                amount,
+                period: Duration::from_secs(60 * 60),
+            }),
+        cloud_cover: details.cloud_area_fraction,
+        condition: next_hour.map(|hour| WeatherCondition {
+            condition: condition_from_symbol_code(&hour.summary.symbol_code),
+            code: hour.summary.symbol_code,
+        }),
+        // The timeseries step is the start of a forecast, not the time of a measurement, so no
+        // observation time is reported
+        ..Weather::new(
+            SOURCE_URI.into(),
+            request.name.clone(),
+            request.query.clone(),
+            details.air_temperature,
+        )
+    })
+}
+
+impl WeatherProvider for MetNorway {
+    fn id(&self) -> &str {
+        SOURCE_URI
+    }
+
+    fn for_coordinates(
+        &self,
+        client: &Client,
+        cache: &HttpRequestCache,
+        request: &WeatherRequest<Coordinates>,
+    ) -> anyhow::Result<Weather> {
+        let url = Url::parse_with_params(
+            ENDPOINT_URL,
+            // Only up to four decimals are allowed, more precise coordinates are rejected
+            &[
+                ("lat", request.query.latitude.to_string_with_precision(4)),
+                ("lon", request.query.longitude.to_string_with_precision(4)),
+            ],
+        )?;
+
+        let response: MetNorwayResponse = request_cached(
+            &HttpCacheRequest::new_json_request(SOURCE_URI, client, cache, &Method::GET, &url)
+                .with_conditional_requests(),
+        )?;
+
+        to_weather(request, response)
+    }
+
+    fn refresh_interval(&self) -> Duration {
+        self.cache.refresh_interval
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod to_weather {
+        use crate::providers::met_norway::{to_weather, MetNorwayResponse};
+        use crate::providers::testing::{json_fixture, request};
+        use crate::providers::units::{Condition, Ratio};
+        use crate::providers::{Precipitation, Weather, WeatherCondition};
+        use pretty_assertions::assert_eq;
+        use std::time::Duration;
+
+        #[test]
+        fn map_compact_response() {
+            assert_eq!(
+                to_weather(
+                    &request("Oslo", 59.9139_f64, 10.7522_f64),
+                    json_fixture("met_norway/compact.json"),
+                )
+                .expect("Mapping works"),
+                Weather {
+                    relative_humidity: Some(Ratio::Percentage(62.1_f64)),
+                    sea_level_pressure: Some(1013.4_f64.into()),
+                    wind_speed: Some(3.6_f64.into()),
+                    wind_direction: Some(215.4_f64.into()),
+                    precipitation: Some(Precipitation {
+                        amount: 0.3_f64.into(),
+                        period: Duration::from_secs(3600),
+                    }),
+                    cloud_cover: Some(Ratio::Percentage(68.8_f64)),
+                    condition: Some(WeatherCondition {
+                        condition: Condition::Rain,
+                        code: "lightrainshowers_day".into(),
+                    }),
+                    ..Weather::new(
+                        "no.met".into(),
+                        "Oslo".into(),
+                        request("Oslo", 59.9139_f64, 10.7522_f64).query,
+                        17.3_f32.into(),
+                    )
+                }
+            );
+        }
+
+        #[test]
+        fn empty_timeseries_is_an_error() {
+            assert_eq!(
+                to_weather(
+                    &request("Nowhere", 0_f64, 0_f64),
+                    serde_json::from_str::<MetNorwayResponse>(
+                        r#"{"properties": {"timeseries": []}}"#
+                    )
+                    .expect("Response is valid"),
+                )
+                .expect_err("Nothing to map")
+                .to_string(),
+                "Empty timeseries"
+            );
+        }
+    }
+
+    mod condition_from_symbol_code {
+        use crate::providers::met_norway::condition_from_symbol_code;
+        use crate::providers::units::Condition;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn map_symbol_codes() {
+            assert_eq!(
+                [
+                    "clearsky_night",
+                    "partlycloudy_polartwilight",
+                    "fog",
+                    "heavyrainandthunder",
+                    "lightsleetshowers_day",
+                    "heavysnow",
+                    "unheardof",
+                ]
+                .map(condition_from_symbol_code),
+                [
+                    Condition::Clear,
+                    Condition::PartlyCloudy,
+                    Condition::Fog,
+                    Condition::Thunderstorm,
+                    Condition::Sleet,
+                    Condition::Snow,
+                    Condition::Unknown,
+                ]
+            );
+        }
+    }
+}
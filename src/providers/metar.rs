@@ -0,0 +1,617 @@
+use crate::providers::units::{
+    Celsius, Degrees, Hectopascal, InchesOfMercury, KilometersPerHour, Knots, Meters,
+    MetersPerSecond, Miles, ToMetersPerSecond,
+};
+use anyhow::anyhow;
+use chrono::{DateTime, Datelike, TimeDelta, Utc};
+use std::str;
+
+// Visibility of 9999 or CAVOK means 10 km or more
+const MAXIMUM_VISIBILITY_METERS: f64 = 10_000.0;
+const METERS_PER_HUNDRED_FEET: f64 = 30.48;
+const INCHES_OF_MERCURY_PER_HUNDREDTH: f64 = 100.0;
+const TENTHS: f64 = 10.0;
+
+// Descriptors and phenomena of present weather groups, see WMO Manual on Codes table 4678
+const WEATHER_CODES: [&str; 30] = [
+    "MI", "PR", "BC", "DR", "BL", "SH", "TS", "FZ", "DZ", "RA", "SN", "SG", "IC", "PL", "GR", "GS",
+    "UP", "BR", "FG", "FU", "VA", "DU", "SA", "HZ", "PY", "PO", "SQ", "FC", "SS", "DS",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub(in crate::providers) struct Metar {
+    pub(in crate::providers) station: String,
+    pub(in crate::providers) observation_time: DateTime<Utc>,
+    pub(in crate::providers) wind: Option<Wind>,
+    pub(in crate::providers) visibility: Option<Meters>,
+    // Present weather groups like -RA or +TSRA
+    pub(in crate::providers) weather: Vec<String>,
+    pub(in crate::providers) clouds: Vec<CloudLayer>,
+    // Explicitly reported absence of clouds, e.g. CAVOK or SKC
+    pub(in crate::providers) clear_sky: bool,
+    pub(in crate::providers) temperature: Option<Celsius>,
+    pub(in crate::providers) dew_point: Option<Celsius>,
+    pub(in crate::providers) altimeter: Option<Hectopascal>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(in crate::providers) struct Wind {
+    // Variable wind has no direction
+    pub(in crate::providers) direction: Option<Degrees>,
+    pub(in crate::providers) speed: MetersPerSecond,
+    pub(in crate::providers) gust: Option<MetersPerSecond>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(in crate::providers) struct CloudLayer {
+    pub(in crate::providers) cover: CloudCover,
+    // Height above ground, not reported by all automated stations
+    pub(in crate::providers) height: Option<Meters>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(in crate::providers) enum CloudCover {
+    Few,
+    Scattered,
+    Broken,
+    Overcast,
+    // Sky obscured, only the vertical visibility is reported
+    Obscured,
+}
+
+impl CloudCover {
+    fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "FEW" => Some(Self::Few),
+            "SCT" => Some(Self::Scattered),
+            "BKN" => Some(Self::Broken),
+            "OVC" => Some(Self::Overcast),
+            "VV" => Some(Self::Obscured),
+            _ => None,
+        }
+    }
+
+    pub(in crate::providers) const fn code(self) -> &'static str {
+        match self {
+            Self::Few => "FEW",
+            Self::Scattered => "SCT",
+            Self::Broken => "BKN",
+            Self::Overcast => "OVC",
+            Self::Obscured => "VV",
+        }
+    }
+
+    // Upper bound of the okta range each cover stands for
+    pub(in crate::providers) const fn oktas(self) -> u8 {
+        match self {
+            Self::Few => 2,
+            Self::Scattered => 4,
+            Self::Broken => 7,
+            Self::Overcast | Self::Obscured => 8,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Section {
+    Observation,
+    Trend,
+    Remarks,
+}
+
+impl Metar {
+    // Reports only carry the day of month, which is resolved relative to the given time
+    pub(in crate::providers) fn parse(report: &str, now: DateTime<Utc>) -> anyhow::Result<Self> {
+        let mut groups = report
+            .split_whitespace()
+            .skip_while(|group| matches!(*group, "METAR" | "SPECI"))
+            .peekable();
+
+        let station = groups
+            .next()
+            .filter(|station| {
+                station.len() == 4 && station.chars().all(|c| c.is_ascii_alphanumeric())
+            })
+            .ok_or_else(|| anyhow!("Missing station identifier in METAR {report:?}"))?;
+        let observation_time = groups
+            .next()
+            .and_then(|time| parse_observation_time(time, now))
+            .ok_or_else(|| anyhow!("Missing observation time in METAR {report:?}"))?;
+
+        let mut metar = Self {
+            station: station.to_owned(),
+            observation_time,
+            wind: None,
+            visibility: None,
+            weather: vec![],
+            clouds: vec![],
+            clear_sky: false,
+            temperature: None,
+            dew_point: None,
+            altimeter: None,
+        };
+
+        let mut section = Section::Observation;
+        while let Some(group) = groups.next() {
+            if group == "RMK" {
+                section = Section::Remarks;
+                continue;
+            }
+
+            match section {
+                Section::Observation if matches!(group, "BECMG" | "TEMPO" | "NOSIG") => {
+                    section = Section::Trend;
+                }
+                Section::Observation => {
+                    // Visibility in statute miles may be split into whole miles and a fraction
+                    match groups.next_if(|next| is_whole_number(group) && next.ends_with("SM")) {
+                        Some(fraction) => metar.decode_group(&format!("{group} {fraction}")),
+                        None => metar.decode_group(group),
+                    }
+                }
+                // Forecasts of the expected change are not part of the observation
+                Section::Trend => {}
+                Section::Remarks => metar.decode_remark(group),
+            }
+        }
+
+        Ok(metar)
+    }
+
+    fn decode_group(&mut self, group: &str) {
+        if matches!(group, "SKC" | "CLR" | "NSC" | "NCD") {
+            self.clear_sky = true;
+            return;
+        }
+
+        if group == "CAVOK" {
+            self.visibility = Some(MAXIMUM_VISIBILITY_METERS.into());
+            self.clear_sky = true;
+            return;
+        }
+
+        if let Some(wind) = parse_wind(group) {
+            self.wind = Some(wind);
+            return;
+        }
+
+        if let Some(visibility) = parse_visibility(group) {
+            self.visibility = Some(visibility);
+            return;
+        }
+
+        if let Some(layer) = parse_cloud_layer(group) {
+            self.clouds.push(layer);
+            return;
+        }
+
+        if let Some((temperature, dew_point)) = parse_temperatures(group) {
+            self.temperature = temperature;
+            self.dew_point = dew_point;
+            return;
+        }
+
+        if let Some(altimeter) = parse_altimeter(group) {
+            self.altimeter = Some(altimeter);
+            return;
+        }
+
+        if phenomena(group).is_some() {
+            self.weather.push(group.to_owned());
+        }
+    }
+
+    // North American stations report temperatures with a precision of 0.1 °C in the remarks
+    fn decode_remark(&mut self, remark: &str) {
+        if let Some((temperature, dew_point)) = parse_precise_temperatures(remark) {
+            self.temperature = Some(temperature);
+            self.dew_point = dew_point.or(self.dew_point);
+        }
+    }
+
+    // Densest reported cloud layer, zero if the sky was reported to be clear
+    pub(in crate::providers) fn cloud_oktas(&self) -> Option<u8> {
+        self.clouds
+            .iter()
+            .map(|layer| layer.cover.oktas())
+            .max()
+            .or_else(|| self.clear_sky.then_some(0))
+    }
+}
+
+// Splits present weather groups like +TSRA into their two letter codes
+pub(in crate::providers) fn phenomena(group: &str) -> Option<Vec<&str>> {
+    let without_intensity = group.trim_start_matches(['+', '-']);
+    let codes = without_intensity
+        .strip_prefix("VC")
+        .unwrap_or(without_intensity);
+
+    let chunks = codes.as_bytes().chunks_exact(2);
+    if codes.is_empty() || !chunks.remainder().is_empty() {
+        return None;
+    }
+
+    chunks
+        .map(|chunk| {
+            str::from_utf8(chunk)
+                .ok()
+                .filter(|code| WEATHER_CODES.contains(code))
+        })
+        .collect()
+}
+
+fn parse_number(value: &str) -> Option<f64> {
+    if value.is_empty() || !value.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    value.parse().ok()
+}
+
+fn is_whole_number(value: &str) -> bool {
+    value.len() <= 2 && parse_number(value).is_some()
+}
+
+fn parse_observation_time(group: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let value = group.strip_suffix('Z').filter(|value| value.len() == 6)?;
+    let day: u32 = value.get(..2)?.parse().ok()?;
+    let hour: u32 = value.get(2..4)?.parse().ok()?;
+    let minute: u32 = value.get(4..)?.parse().ok()?;
+
+    // Tolerate clocks running slightly behind the station
+    let latest = now.checked_add_signed(TimeDelta::hours(1))?;
+    let this_month = now.date_naive().with_day(1)?;
+    let previous_month = this_month.pred_opt()?.with_day(1)?;
+
+    [this_month, previous_month]
+        .into_iter()
+        .filter_map(|month| month.with_day(day)?.and_hms_opt(hour, minute, 0))
+        .map(|time| time.and_utc())
+        .find(|time| *time <= latest)
+}
+
+fn parse_wind(group: &str) -> Option<Wind> {
+    let (value, unit) = ["KT", "MPS", "KMH"]
+        .into_iter()
+        .find_map(|unit| group.strip_suffix(unit).map(|value| (value, unit)))?;
+
+    let to_meters_per_second = |reported: &str| -> Option<MetersPerSecond> {
+        // Speeds of 100 units and more may be prefixed with P
+        let speed = parse_number(reported.trim_start_matches('P'))?;
+
+        Some(match unit {
+            "MPS" => speed.into(),
+            "KMH" => KilometersPerHour::from(speed).to_meters_per_second(),
+            _ => Knots::from(speed).to_meters_per_second(),
+        })
+    };
+
+    let direction = match value.get(..3)? {
+        "VRB" => None,
+        direction => Some(parse_number(direction)?.into()),
+    };
+    let (speed, gust) = match value.get(3..)?.split_once('G') {
+        Some((speed, gust)) => (speed, Some(to_meters_per_second(gust)?)),
+        None => (value.get(3..)?, None),
+    };
+
+    Some(Wind {
+        direction,
+        speed: to_meters_per_second(speed)?,
+        gust,
+    })
+}
+
+fn parse_visibility(group: &str) -> Option<Meters> {
+    if let Some(statute_miles) = group.strip_suffix("SM") {
+        return statute_miles
+            .trim_start_matches(['P', 'M'])
+            .split_whitespace()
+            .map(parse_fraction)
+            .sum::<Option<f64>>()
+            .map(|miles| Miles::from(miles).to_meters());
+    }
+
+    // No directional variation is only reported by automated stations
+    let digits = group.strip_suffix("NDV").unwrap_or(group);
+    if digits.len() != 4 {
+        return None;
+    }
+
+    if digits == "9999" {
+        return Some(MAXIMUM_VISIBILITY_METERS.into());
+    }
+
+    parse_number(digits).map(Into::into)
+}
+
+fn parse_fraction(value: &str) -> Option<f64> {
+    match value.split_once('/') {
+        Some((numerator, denominator)) => {
+            Some(parse_number(numerator)? / parse_number(denominator)?)
+        }
+        None => parse_number(value),
+    }
+}
+
+fn parse_cloud_layer(group: &str) -> Option<CloudLayer> {
+    let (cover, layer) = match group.strip_prefix("VV") {
+        Some(height) => (CloudCover::Obscured, height),
+        None => (CloudCover::from_code(group.get(..3)?)?, group.get(3..)?),
+    };
+
+    // Heights are given in hundreds of feet, optionally followed by the cloud type like CB
+    let height = layer.get(..3)?;
+    if height != "///" && parse_number(height).is_none() {
+        return None;
+    }
+
+    Some(CloudLayer {
+        cover,
+        height: parse_number(height).map(|hundreds| (hundreds * METERS_PER_HUNDRED_FEET).into()),
+    })
+}
+
+// Whole degrees with M for negative values, e.g. M05/M07
+fn parse_temperatures(group: &str) -> Option<(Option<Celsius>, Option<Celsius>)> {
+    let (temperature, dew_point) = group.split_once('/')?;
+
+    (is_temperature(temperature) && is_temperature(dew_point))
+        .then(|| (parse_temperature(temperature), parse_temperature(dew_point)))
+}
+
+// Missing values are either omitted or given as //
+fn is_temperature(value: &str) -> bool {
+    let degrees = value.strip_prefix('M').unwrap_or(value);
+
+    value.is_empty() || value == "//" || (degrees.len() == 2 && parse_number(degrees).is_some())
+}
+
+fn parse_temperature(value: &str) -> Option<Celsius> {
+    let (sign, degrees) = value
+        .strip_prefix('M')
+        .map_or((1.0_f64, value), |degrees| (-1.0_f64, degrees));
+
+    parse_number(degrees).map(|number| Celsius::from_f64(sign * number))
+}
+
+fn parse_altimeter(group: &str) -> Option<Hectopascal> {
+    if let Some(hundredths) = group.strip_prefix('A').filter(|value| value.len() == 4) {
+        return parse_number(hundredths).map(|value| {
+            InchesOfMercury::from(value / INCHES_OF_MERCURY_PER_HUNDREDTH).to_hectopascal()
+        });
+    }
+
+    group
+        .strip_prefix('Q')
+        .filter(|value| value.len() == 4)
+        .and_then(parse_number)
+        .map(Into::into)
+}
+
+// Remark like T02390139 with sign (1 for negative) and tenths of degrees
+fn parse_precise_temperatures(remark: &str) -> Option<(Celsius, Option<Celsius>)> {
+    let value = remark
+        .strip_prefix('T')
+        .filter(|value| value.len() == 4 || value.len() == 8)?;
+
+    let temperature = parse_tenths(value.get(..4)?)?;
+    let dew_point = match value.get(4..)? {
+        "" => None,
+        dew_point => Some(parse_tenths(dew_point)?),
+    };
+
+    Some((temperature, dew_point))
+}
+
+fn parse_tenths(value: &str) -> Option<Celsius> {
+    let sign = match value.get(..1)? {
+        "0" => 1.0_f64,
+        "1" => -1.0_f64,
+        _ => return None,
+    };
+
+    Some(Celsius::from_f64(
+        sign * parse_number(value.get(1..)?)? / TENTHS,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Utc};
+
+    fn time(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .expect("Static value")
+            .with_timezone(&Utc)
+    }
+
+    mod parse {
+        use crate::providers::metar::tests::time;
+        use crate::providers::metar::{CloudCover, CloudLayer, Metar, Wind};
+        use crate::providers::units::{
+            Celsius, Hectopascal, InchesOfMercury, Knots, Meters, Miles, ToMetersPerSecond,
+        };
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn decode_us_report_with_remarks() {
+            assert_eq!(
+                Metar::parse(
+                    "KJFK 011251Z 22010G18KT 10SM FEW050 SCT250 24/14 A2992 RMK AO2 SLP132 T02390139",
+                    time("2024-06-01T13:05:00Z"),
+                )
+                .expect("Report is valid"),
+                Metar {
+                    station: "KJFK".into(),
+                    observation_time: time("2024-06-01T12:51:00Z"),
+                    wind: Some(Wind {
+                        direction: Some(220_f64.into()),
+                        speed: Knots::from(10_f64).to_meters_per_second(),
+                        gust: Some(Knots::from(18_f64).to_meters_per_second()),
+                    }),
+                    visibility: Some(Miles::from(10_f64).to_meters()),
+                    weather: vec![],
+                    clouds: vec![
+                        CloudLayer {
+                            cover: CloudCover::Few,
+                            height: Some(Meters::from(1524_f64)),
+                        },
+                        CloudLayer {
+                            cover: CloudCover::Scattered,
+                            height: Some(Meters::from(7620_f64)),
+                        },
+                    ],
+                    clear_sky: false,
+                    temperature: Some(Celsius::from(23.9_f32)),
+                    dew_point: Some(Celsius::from(13.9_f32)),
+                    altimeter: Some(InchesOfMercury::from(29.92_f64).to_hectopascal()),
+                }
+            );
+        }
+
+        #[test]
+        fn ignore_trend() {
+            assert_eq!(
+                Metar::parse(
+                    "METAR EDDF 011220Z AUTO VRB03KT 9999 -RA BKN012 OVC030 M02/M04 Q1013 TEMPO 4000 RA",
+                    time("2024-06-01T13:05:00Z"),
+                )
+                .expect("Report is valid"),
+                Metar {
+                    station: "EDDF".into(),
+                    observation_time: time("2024-06-01T12:20:00Z"),
+                    wind: Some(Wind {
+                        direction: None,
+                        speed: Knots::from(3_f64).to_meters_per_second(),
+                        gust: None,
+                    }),
+                    visibility: Some(Meters::from(10_000_f64)),
+                    weather: vec!["-RA".into()],
+                    clouds: vec![
+                        CloudLayer {
+                            cover: CloudCover::Broken,
+                            height: Some(Meters::from(365.76_f64)),
+                        },
+                        CloudLayer {
+                            cover: CloudCover::Overcast,
+                            height: Some(Meters::from(914.4_f64)),
+                        },
+                    ],
+                    clear_sky: false,
+                    temperature: Some((-2_f32).into()),
+                    dew_point: Some((-4_f32).into()),
+                    altimeter: Some(Hectopascal::from(1013_f64)),
+                }
+            );
+        }
+
+        #[test]
+        fn resolve_day_in_previous_month() {
+            assert_eq!(
+                Metar::parse(
+                    "LSZH 312350Z 05005MPS CAVOK 15/10 Q1020",
+                    time("2024-06-01T00:10:00Z"),
+                )
+                .expect("Report is valid"),
+                Metar {
+                    station: "LSZH".into(),
+                    observation_time: time("2024-05-31T23:50:00Z"),
+                    wind: Some(Wind {
+                        direction: Some(50_f64.into()),
+                        speed: 5_f64.into(),
+                        gust: None,
+                    }),
+                    visibility: Some(Meters::from(10_000_f64)),
+                    weather: vec![],
+                    clouds: vec![],
+                    clear_sky: true,
+                    temperature: Some(15_f32.into()),
+                    dew_point: Some(10_f32.into()),
+                    altimeter: Some(Hectopascal::from(1020_f64)),
+                }
+            );
+        }
+
+        #[test]
+        fn decode_fractional_visibility() {
+            assert_eq!(
+                Metar::parse(
+                    "KSFO 011256Z 28012KT 1 1/2SM BR OVC004 12/11 A2990",
+                    time("2024-06-01T13:05:00Z"),
+                )
+                .expect("Report is valid"),
+                Metar {
+                    station: "KSFO".into(),
+                    observation_time: time("2024-06-01T12:56:00Z"),
+                    wind: Some(Wind {
+                        direction: Some(280_f64.into()),
+                        speed: Knots::from(12_f64).to_meters_per_second(),
+                        gust: None,
+                    }),
+                    visibility: Some(Miles::from(1.5_f64).to_meters()),
+                    weather: vec!["BR".into()],
+                    clouds: vec![CloudLayer {
+                        cover: CloudCover::Overcast,
+                        height: Some(Meters::from(121.92_f64)),
+                    }],
+                    clear_sky: false,
+                    temperature: Some(12_f32.into()),
+                    dew_point: Some(11_f32.into()),
+                    altimeter: Some(InchesOfMercury::from(29.9_f64).to_hectopascal()),
+                }
+            );
+        }
+
+        #[test]
+        fn reject_invalid_reports() {
+            assert_eq!(
+                ["", "KJFK 22010KT 10SM"].map(|report| Metar::parse(
+                    report,
+                    time("2024-06-01T13:05:00Z")
+                )
+                .expect_err("Report is invalid")
+                .to_string()),
+                [
+                    r#"Missing station identifier in METAR """#,
+                    r#"Missing observation time in METAR "KJFK 22010KT 10SM""#,
+                ]
+            );
+        }
+    }
+
+    mod cloud_oktas {
+        use crate::providers::metar::tests::time;
+        use crate::providers::metar::Metar;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn use_densest_layer() {
+            assert_eq!(
+                [
+                    "KJFK 011251Z 22010KT 10SM FEW050 SCT250 24/14 A2992",
+                    "EDDF 011220Z VRB03KT 9999 BKN012 OVC030 M02/M04 Q1013",
+                    "LSZH 011250Z 05005MPS CAVOK 15/10 Q1020",
+                    "KSFO 011256Z 28012KT 10SM 12/11 A2990",
+                ]
+                .map(|report| Metar::parse(report, time("2024-06-01T13:05:00Z"))
+                    .expect("Report is valid")
+                    .cloud_oktas()),
+                [Some(4_u8), Some(8_u8), Some(0_u8), None]
+            );
+        }
+    }
+
+    mod phenomena {
+        use crate::providers::metar::phenomena;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn split_weather_groups() {
+            assert_eq!(
+                ["+TSRA", "VCSH", "FEW050", "AUTO"].map(phenomena),
+                [Some(vec!["TS", "RA"]), Some(vec!["SH"]), None, None]
+            );
+        }
+    }
+}
@@ -76,13 +76,14 @@ impl WeatherProvider for Meteoblue {
         let distance = calculate_distance(&request.query, &response.metadata.coordinates);
 
         Ok(Weather {
-            source: SOURCE_URI.into(),
-            location: request.name.clone(),
             city: Some(response.metadata.name).filter(|v| !v.is_empty()),
-            coordinates: response.metadata.coordinates,
             distance: Some(distance),
-            temperature: response.data_current.temperature,
-            relative_humidity: None,
+            ..Weather::new(
+                SOURCE_URI.into(),
+                request.name.clone(),
+                response.metadata.coordinates,
+                response.data_current.temperature,
+            )
         })
     }
 
@@ -0,0 +1,432 @@
+use crate::providers::http_request::{request_cached, Configuration, HttpCacheRequest};
+use crate::providers::station_network::{
+    decode_latin1, minute_precision_date_format, parse_measurement_data_csv,
+};
+use crate::providers::units::{
+    Celsius, Coordinate, Coordinates, Degrees, Hectopascal, KilometersPerHour, Millimeters, Ratio,
+    ToMetersPerSecond, WattsPerSquareMeter,
+};
+use crate::providers::{
+    calculate_distance, find_closest, HttpRequestCache, Precipitation, Sunshine, Weather,
+    WeatherProvider, WeatherRequest,
+};
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use log::trace;
+use reqwest::blocking::Client;
+use reqwest::{Method, Url};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const SOURCE_URI: &str = "ch.meteoswiss";
+const MEASUREMENTS_URL: &str =
+    "https://data.geo.admin.ch/ch.meteoschweiz.messwerte-aktuell/VQHA80.csv";
+const STATION_LIST_URL: &str = "https://data.geo.admin.ch/ch.meteoschweiz.messnetz-automatisch/ch.meteoschweiz.messnetz-automatisch_en.csv";
+
+const MEASUREMENT_INTERVAL: Duration = Duration::from_secs(60 * 10);
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MeteoSwiss {
+    #[serde(flatten)]
+    cache: Configuration,
+}
+
+#[derive(Deserialize, Debug, PartialEq, Clone)]
+struct WeatherStation {
+    #[serde(rename = "Abbr.")]
+    abbreviation: String,
+    #[serde(rename = "Station")]
+    name: String,
+    #[serde(rename = "Latitude")]
+    latitude: Coordinate,
+    #[serde(rename = "Longitude")]
+    longitude: Coordinate,
+}
+
+// Parameter names are documented in https://data.geo.admin.ch/ch.meteoschweiz.messwerte-aktuell/info/VQHA80_en.txt
+// Parameters dropped or renamed by MeteoSwiss are reported as missing instead of failing the whole file
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+struct Measurement {
+    #[serde(rename = "Station/Location")]
+    station: String,
+    #[serde(rename = "Date", with = "minute_precision_date_format")]
+    time: DateTime<Utc>,
+    #[serde(rename = "tre200s0", with = "optional_measurement", default)]
+    temperature: Option<Celsius>,
+    #[serde(rename = "ure200s0", with = "optional_measurement", default)]
+    relative_humidity: Option<Ratio>,
+    #[serde(rename = "tde200s0", with = "optional_measurement", default)]
+    dew_point: Option<Celsius>,
+    #[serde(rename = "prestas0", with = "optional_measurement", default)]
+    station_pressure: Option<Hectopascal>,
+    #[serde(rename = "pp0qffs0", with = "optional_measurement", default)]
+    sea_level_pressure: Option<Hectopascal>,
+    #[serde(rename = "fu3010z0", with = "optional_measurement", default)]
+    wind_speed: Option<KilometersPerHour>,
+    #[serde(rename = "fu3010z1", with = "optional_measurement", default)]
+    wind_gust_speed: Option<KilometersPerHour>,
+    #[serde(rename = "dkl010z0", with = "optional_measurement", default)]
+    wind_direction: Option<Degrees>,
+    #[serde(rename = "rre150z0", with = "optional_measurement", default)]
+    precipitation: Option<Millimeters>,
+    #[serde(rename = "sre000z0", with = "optional_measurement", default)]
+    sunshine_duration_minutes: Option<f64>,
+    #[serde(rename = "gre000z0", with = "optional_measurement", default)]
+    global_radiation: Option<WattsPerSquareMeter>,
+}
+
+mod optional_measurement {
+    use crate::providers::station_network::deserialize_optional_measurement;
+    use serde::de::DeserializeOwned;
+    use serde::Deserializer;
+
+    // MeteoSwiss marks missing measurements with a dash
+    const MISSING_VALUE: &str = "-";
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: DeserializeOwned,
+    {
+        deserialize_optional_measurement(deserializer, MISSING_VALUE)
+    }
+}
+
+// The station list ends with a blank line followed by source notes
+fn parse_weather_station_list_csv(data: &str) -> anyhow::Result<Vec<WeatherStation>> {
+    let stations = data
+        .lines()
+        .take_while(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    parse_measurement_data_csv(&stations)
+}
+
+fn to_weather(
+    request: &WeatherRequest<Coordinates>,
+    stations: &[WeatherStation],
+    measurements: &[Measurement],
+) -> anyhow::Result<Weather> {
+    // Not every station in the network reports current measurements
+    let measured_stations: Vec<(&WeatherStation, &Measurement)> = stations
+        .iter()
+        .filter_map(|station| {
+            measurements
+                .iter()
+                .find(|measurement| measurement.station == station.abbreviation)
+                .map(|measurement| (station, measurement))
+        })
+        .collect();
+
+    let (station, measurement) =
+        find_closest(&request.query, &measured_stations, |(station, _)| {
+            Coordinates {
+                latitude: station.latitude.clone(),
+                longitude: station.longitude.clone(),
+            }
+        })?;
+    trace!("Found closest weather station {:?}", station);
+
+    let coordinates = Coordinates {
+        latitude: station.latitude.clone(),
+        longitude: station.longitude.clone(),
+    };
+
+    Ok(Weather {
+        city: Some(station.name.clone()),
+        distance: Some(calculate_distance(&request.query, &coordinates)),
+        relative_humidity: measurement.relative_humidity,
+        dew_point: measurement.dew_point,
+        station_pressure: measurement.station_pressure,
+        sea_level_pressure: measurement.sea_level_pressure,
+        wind_speed: measurement
+            .wind_speed
+            .map(|speed| speed.to_meters_per_second()),
+        wind_gust_speed: measurement
+            .wind_gust_speed
+            .map(|speed| speed.to_meters_per_second()),
+        wind_direction: measurement.wind_direction,
+        precipitation: measurement.precipitation.map(|amount| Precipitation {
+            amount,
+            period: MEASUREMENT_INTERVAL,
+        }),
+        global_radiation: measurement.global_radiation,
+        sunshine: measurement
+            .sunshine_duration_minutes
+            .and_then(|minutes| Duration::try_from_secs_f64(minutes * 60.0_f64).ok())
+            .map(|duration| Sunshine {
+                duration,
+                period: MEASUREMENT_INTERVAL,
+            }),
+        observation_time: Some(measurement.time),
+        ..Weather::new(
+            SOURCE_URI.into(),
+            request.name.clone(),
+            coordinates,
+            measurement
+                .temperature
+                .ok_or_else(|| anyhow!("No temperature reported by station {}", station.name))?,
+        )
+    })
+}
+
+impl WeatherProvider for MeteoSwiss {
+    fn id(&self) -> &str {
+        SOURCE_URI
+    }
+
+    fn for_coordinates(
+        &self,
+        client: &Client,
+        cache: &HttpRequestCache,
+        request: &WeatherRequest<Coordinates>,
+    ) -> anyhow::Result<Weather> {
+        let stations = request_cached(&HttpCacheRequest::new(
+            SOURCE_URI,
+            client,
+            cache,
+            &Method::GET,
+            &Url::parse(STATION_LIST_URL)?,
+            |body| parse_weather_station_list_csv(&decode_latin1(body)),
+        ))?;
+
+        let measurements: Vec<Measurement> = request_cached(&HttpCacheRequest::new(
+            SOURCE_URI,
+            client,
+            cache,
+            &Method::GET,
+            &Url::parse(MEASUREMENTS_URL)?,
+            |body| parse_measurement_data_csv(&String::from_utf8_lossy(body)),
+        ))?;
+
+        to_weather(request, &stations, &measurements)
+    }
+
+    fn refresh_interval(&self) -> Duration {
+        self.cache.refresh_interval
+    }
+
+    // Station list and measurements
+    fn cache_cardinality(&self) -> usize {
+        2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod parse_weather_station_list_csv {
+        use crate::providers::meteoswiss::{parse_weather_station_list_csv, WeatherStation};
+        use crate::providers::station_network::decode_latin1;
+        use crate::providers::testing::fixture;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn skip_source_notes() {
+            assert_eq!(
+                parse_weather_station_list_csv(&decode_latin1(&fixture("meteoswiss/stations.csv")))
+                    .expect("Parsing works"),
+                [
+                    WeatherStation {
+                        abbreviation: "BER".into(),
+                        name: "Bern / Zollikofen".into(),
+                        latitude: 46.990_744_f64.into(),
+                        longitude: 7.464_061_f64.into(),
+                    },
+                    WeatherStation {
+                        abbreviation: "SMA".into(),
+                        name: "Z\u{fc}rich / Fluntern".into(),
+                        latitude: 47.377_925_f64.into(),
+                        longitude: 8.565_742_f64.into(),
+                    },
+                    WeatherStation {
+                        abbreviation: "JUN".into(),
+                        name: "Jungfraujoch".into(),
+                        latitude: 46.547_556_f64.into(),
+                        longitude: 7.985_444_f64.into(),
+                    },
+                    WeatherStation {
+                        abbreviation: "GVE".into(),
+                        name: "Gen\u{e8}ve / Cointrin".into(),
+                        latitude: 46.247_519_f64.into(),
+                        longitude: 6.127_742_f64.into(),
+                    },
+                ]
+            );
+        }
+    }
+
+    mod parse_measurement_data_csv {
+        use crate::providers::meteoswiss::Measurement;
+        use crate::providers::station_network::parse_measurement_data_csv;
+        use crate::providers::units::Ratio;
+        use chrono::{DateTime, Utc};
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn missing_parameters_are_empty() {
+            assert_eq!(
+                parse_measurement_data_csv::<Measurement>(
+                    "Station/Location;Date;tre200s0;ure200s0\nSMA;202406011250;20.1;61.0",
+                )
+                .expect("Parameters are optional"),
+                [Measurement {
+                    station: "SMA".into(),
+                    time: DateTime::parse_from_rfc3339("2024-06-01T12:50:00Z")
+                        .expect("Static value")
+                        .with_timezone(&Utc),
+                    temperature: Some(20.1_f32.into()),
+                    relative_humidity: Some(Ratio::Percentage(61_f64)),
+                    dew_point: None,
+                    station_pressure: None,
+                    sea_level_pressure: None,
+                    wind_speed: None,
+                    wind_gust_speed: None,
+                    wind_direction: None,
+                    precipitation: None,
+                    sunshine_duration_minutes: None,
+                    global_radiation: None,
+                }]
+            );
+        }
+    }
+
+    mod to_weather {
+        use crate::providers::meteoswiss::{
+            parse_weather_station_list_csv, to_weather, Measurement, WeatherStation,
+        };
+        use crate::providers::station_network::{decode_latin1, parse_measurement_data_csv};
+        use crate::providers::testing::{fixture, request};
+        use crate::providers::units::{Coordinates, KilometersPerHour, Ratio, ToMetersPerSecond};
+        use crate::providers::{Precipitation, Sunshine, Weather};
+        use chrono::{DateTime, Utc};
+        use pretty_assertions::assert_eq;
+        use std::time::Duration;
+
+        fn stations() -> Vec<WeatherStation> {
+            parse_weather_station_list_csv(&decode_latin1(&fixture("meteoswiss/stations.csv")))
+                .expect("Fixture is valid")
+        }
+
+        fn measurements() -> Vec<Measurement> {
+            parse_measurement_data_csv(&String::from_utf8_lossy(&fixture("meteoswiss/VQHA80.csv")))
+                .expect("Fixture is valid")
+        }
+
+        fn observation_time() -> DateTime<Utc> {
+            DateTime::parse_from_rfc3339("2024-06-01T12:50:00Z")
+                .expect("Static value")
+                .with_timezone(&Utc)
+        }
+
+        #[test]
+        fn map_measurements_of_closest_station() {
+            assert_eq!(
+                to_weather(
+                    &request("Zurich", 47.3769_f64, 8.5417_f64),
+                    &stations(),
+                    &measurements(),
+                )
+                .expect("Mapping works"),
+                Weather {
+                    city: Some("Z\u{fc}rich / Fluntern".into()),
+                    distance: Some(1_813.887_764_959_674_8_f64.into()),
+                    relative_humidity: Some(Ratio::Percentage(61_f64)),
+                    dew_point: Some(12.4_f32.into()),
+                    station_pressure: Some(966.3_f64.into()),
+                    sea_level_pressure: Some(1014.9_f64.into()),
+                    wind_speed: Some(3_f64.into()),
+                    wind_gust_speed: Some(7_f64.into()),
+                    wind_direction: Some(250_f64.into()),
+                    precipitation: Some(Precipitation {
+                        amount: 0.2_f64.into(),
+                        period: Duration::from_secs(600),
+                    }),
+                    global_radiation: Some(480_f64.into()),
+                    sunshine: Some(Sunshine {
+                        duration: Duration::from_secs(300),
+                        period: Duration::from_secs(600),
+                    }),
+                    observation_time: Some(observation_time()),
+                    ..Weather::new(
+                        "ch.meteoswiss".into(),
+                        "Zurich".into(),
+                        Coordinates {
+                            latitude: 47.377_925_f64.into(),
+                            longitude: 8.565_742_f64.into(),
+                        },
+                        20.1_f32.into(),
+                    )
+                }
+            );
+        }
+
+        #[test]
+        fn stations_without_measurements_are_skipped() {
+            assert_eq!(
+                to_weather(
+                    &request("Geneva", 46.2044_f64, 6.1432_f64),
+                    &stations(),
+                    &measurements(),
+                )
+                .expect("Mapping works"),
+                Weather {
+                    city: Some("Bern / Zollikofen".into()),
+                    distance: Some(133_525.656_393_119_98_f64.into()),
+                    relative_humidity: Some(Ratio::Percentage(58.3_f64)),
+                    dew_point: Some(10.9_f32.into()),
+                    station_pressure: Some(952.1_f64.into()),
+                    sea_level_pressure: Some(1015.2_f64.into()),
+                    wind_speed: Some(KilometersPerHour::from(7.2_f64).to_meters_per_second()),
+                    wind_gust_speed: Some(KilometersPerHour::from(18_f64).to_meters_per_second()),
+                    wind_direction: Some(225_f64.into()),
+                    precipitation: Some(Precipitation {
+                        amount: 0_f64.into(),
+                        period: Duration::from_secs(600),
+                    }),
+                    global_radiation: Some(612_f64.into()),
+                    sunshine: Some(Sunshine {
+                        duration: Duration::from_secs(600),
+                        period: Duration::from_secs(600),
+                    }),
+                    observation_time: Some(observation_time()),
+                    ..Weather::new(
+                        "ch.meteoswiss".into(),
+                        "Geneva".into(),
+                        Coordinates {
+                            latitude: 46.990_744_f64.into(),
+                            longitude: 7.464_061_f64.into(),
+                        },
+                        19.4_f32.into(),
+                    )
+                }
+            );
+        }
+
+        #[test]
+        fn missing_measurements_are_empty() {
+            assert_eq!(
+                to_weather(
+                    &request("Jungfraujoch", 46.5475_f64, 7.9854_f64),
+                    &stations(),
+                    &measurements(),
+                )
+                .expect("Mapping works"),
+                Weather {
+                    city: Some("Jungfraujoch".into()),
+                    distance: Some(7.077_927_262_041_24_f64.into()),
+                    observation_time: Some(observation_time()),
+                    ..Weather::new(
+                        "ch.meteoswiss".into(),
+                        "Jungfraujoch".into(),
+                        Coordinates {
+                            latitude: 46.547_556_f64.into(),
+                            longitude: 7.985_444_f64.into(),
+                        },
+                        (-3.2_f32).into(),
+                    )
+                }
+            );
+        }
+    }
+}
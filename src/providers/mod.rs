@@ -1,22 +1,63 @@
+mod aviation_weather;
+mod bright_sky;
 mod deutscher_wetterdienst;
+pub mod ecowitt;
+mod environment_canada;
+mod exec;
+mod generic_json;
+mod geosphere_austria;
 mod http_request;
+mod knmi;
+mod met_norway;
+mod metar;
 mod meteoblue;
+mod meteoswiss;
+mod mqtt;
+mod national_weather_service;
+mod netatmo;
 mod nogoodnik;
 mod open_meteo;
 mod open_weather;
+mod station_network;
+mod station_report;
+#[cfg(test)]
+mod testing;
 mod tomorrow;
 pub mod units;
+mod weather_underground;
+pub mod wunderground_upload;
 
+use crate::providers::aviation_weather::AviationWeather;
+use crate::providers::bright_sky::BrightSky;
 use crate::providers::deutscher_wetterdienst::DeutscherWetterdienst;
+use crate::providers::ecowitt::Ecowitt;
+use crate::providers::environment_canada::EnvironmentCanada;
+use crate::providers::exec::Exec;
+use crate::providers::generic_json::GenericJson;
+use crate::providers::geosphere_austria::GeosphereAustria;
+use crate::providers::knmi::Knmi;
+use crate::providers::met_norway::MetNorway;
 use crate::providers::meteoblue::Meteoblue;
+use crate::providers::meteoswiss::MeteoSwiss;
+use crate::providers::mqtt::Mqtt;
+use crate::providers::national_weather_service::NationalWeatherService;
+use crate::providers::netatmo::Netatmo;
 use crate::providers::nogoodnik::Nogoodnik;
 use crate::providers::open_meteo::OpenMeteo;
 use crate::providers::open_weather::OpenWeather;
 use crate::providers::tomorrow::Tomorrow;
-use crate::providers::units::{Celsius, Meters, Ratio};
-use geo::{Distance, Haversine, Point};
+use crate::providers::units::{
+    Celsius, Condition, Decibels, Degrees, Hectopascal, Meters, MetersPerSecond, Millimeters,
+    MillimetersPerHour, PartsPerMillion, Ratio, WattsPerSquareMeter,
+};
+use crate::providers::weather_underground::WeatherUnderground;
+use crate::providers::wunderground_upload::WundergroundUpload;
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use geo::{Closest, ClosestPoint, Distance, Haversine, MultiPoint, Point};
 use reqwest::blocking::Client;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fmt::Debug;
 use std::sync::Arc;
 use std::time::Duration;
@@ -30,53 +71,246 @@ pub struct Providers {
     tomorrow: Option<Tomorrow>,
     deutscher_wetterdienst: Option<DeutscherWetterdienst>,
     open_meteo: Option<OpenMeteo>,
+    met_norway: Option<MetNorway>,
+    national_weather_service: Option<NationalWeatherService>,
+    bright_sky: Option<BrightSky>,
+    knmi: Option<Knmi>,
+    meteoswiss: Option<MeteoSwiss>,
+    geosphere_austria: Option<GeosphereAustria>,
+    environment_canada: Option<EnvironmentCanada>,
+    aviation_weather: Option<AviationWeather>,
+    weather_underground: Option<WeatherUnderground>,
+    netatmo: Option<Netatmo>,
+    ecowitt: Option<Ecowitt>,
+    wunderground_upload: Option<WundergroundUpload>,
+    mqtt: Option<Mqtt>,
+    // Configured as an array, one entry per API
+    generic_json: Option<Vec<GenericJson>>,
+    // Configured as an array, one entry per command
+    exec: Option<Vec<Exec>>,
     nogoodnik: Option<Nogoodnik>,
 }
 
+impl Providers {
+    // Stations uploading to the exporter are authenticated by the provider configuration
+    pub const fn ecowitt(&self) -> Option<&Ecowitt> {
+        self.ecowitt.as_ref()
+    }
+
+    pub const fn wunderground_upload(&self) -> Option<&WundergroundUpload> {
+        self.wunderground_upload.as_ref()
+    }
+}
+
+// Providers along with the name they are configured with
+pub type ConfiguredProvider = (&'static str, Arc<dyn WeatherProvider + Send + Sync>);
+
 impl IntoIterator for Providers {
-    type Item = Arc<dyn WeatherProvider + Send + Sync>;
-    type IntoIter = IntoIter<Arc<dyn WeatherProvider + Send + Sync>>;
+    type Item = ConfiguredProvider;
+    type IntoIter = IntoIter<ConfiguredProvider>;
 
     fn into_iter(self) -> Self::IntoIter {
-        let mut vec: Vec<Arc<dyn WeatherProvider + Send + Sync>> = vec![];
+        let mut vec: Vec<ConfiguredProvider> = vec![];
 
         if let Some(provider) = self.open_weather {
-            vec.push(Arc::new(provider));
+            vec.push(("open_weather", Arc::new(provider)));
         }
 
         if let Some(provider) = self.meteoblue {
-            vec.push(Arc::new(provider));
+            vec.push(("meteoblue", Arc::new(provider)));
         }
 
         if let Some(provider) = self.tomorrow {
-            vec.push(Arc::new(provider));
+            vec.push(("tomorrow", Arc::new(provider)));
         }
 
         if let Some(provider) = self.deutscher_wetterdienst {
-            vec.push(Arc::new(provider));
+            vec.push(("deutscher_wetterdienst", Arc::new(provider)));
         }
 
         if let Some(provider) = self.open_meteo {
-            vec.push(Arc::new(provider));
+            vec.push(("open_meteo", Arc::new(provider)));
+        }
+
+        if let Some(provider) = self.met_norway {
+            vec.push(("met_norway", Arc::new(provider)));
+        }
+
+        if let Some(provider) = self.national_weather_service {
+            vec.push(("national_weather_service", Arc::new(provider)));
+        }
+
+        if let Some(provider) = self.bright_sky {
+            vec.push(("bright_sky", Arc::new(provider)));
+        }
+
+        if let Some(provider) = self.knmi {
+            vec.push(("knmi", Arc::new(provider)));
+        }
+
+        if let Some(provider) = self.meteoswiss {
+            vec.push(("meteoswiss", Arc::new(provider)));
+        }
+
+        if let Some(provider) = self.geosphere_austria {
+            vec.push(("geosphere_austria", Arc::new(provider)));
+        }
+
+        if let Some(provider) = self.environment_canada {
+            vec.push(("environment_canada", Arc::new(provider)));
+        }
+
+        if let Some(provider) = self.aviation_weather {
+            vec.push(("aviation_weather", Arc::new(provider)));
+        }
+
+        if let Some(provider) = self.weather_underground {
+            vec.push(("weather_underground", Arc::new(provider)));
+        }
+
+        if let Some(provider) = self.netatmo {
+            vec.push(("netatmo", Arc::new(provider)));
+        }
+
+        if let Some(provider) = self.ecowitt {
+            vec.push(("ecowitt", Arc::new(provider)));
+        }
+
+        if let Some(provider) = self.wunderground_upload {
+            vec.push(("wunderground_upload", Arc::new(provider)));
+        }
+
+        if let Some(provider) = self.mqtt {
+            vec.push(("mqtt", Arc::new(provider)));
+        }
+
+        for provider in self.generic_json.into_iter().flatten() {
+            vec.push(("generic_json", Arc::new(provider)));
+        }
+
+        for provider in self.exec.into_iter().flatten() {
+            vec.push(("exec", Arc::new(provider)));
         }
 
         if let Some(provider) = self.nogoodnik {
-            vec.push(Arc::new(provider));
+            vec.push(("nogoodnik", Arc::new(provider)));
         }
 
         IntoIter::into_iter(vec.into_iter())
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct Weather {
     pub location: String,
     pub source: String,
     pub city: Option<String>,
     pub coordinates: Coordinates,
     pub distance: Option<Meters>,
+    // Air temperature at the standard height of 2 meters above ground
     pub temperature: Celsius,
+    // Temperatures measured at other heights, e.g. close to the ground
+    pub temperatures_at_height: Vec<TemperatureAtHeight>,
     pub relative_humidity: Option<Ratio>,
+    pub dew_point: Option<Celsius>,
+    pub apparent_temperature: Option<Celsius>,
+    pub station_pressure: Option<Hectopascal>,
+    pub sea_level_pressure: Option<Hectopascal>,
+    pub wind_speed: Option<MetersPerSecond>,
+    pub wind_gust_speed: Option<MetersPerSecond>,
+    pub wind_direction: Option<Degrees>,
+    pub precipitation: Option<Precipitation>,
+    pub precipitation_intensity: Option<MillimetersPerHour>,
+    // Accumulated since midnight UTC, resets daily
+    pub daily_precipitation: Option<Millimeters>,
+    pub snow_depth: Option<Meters>,
+    // Depth of freshly fallen snow
+    pub snowfall: Option<Precipitation>,
+    pub cloud_cover: Option<Ratio>,
+    pub visibility: Option<Meters>,
+    pub uv_index: Option<f64>,
+    // Global horizontal irradiance
+    pub global_radiation: Option<WattsPerSquareMeter>,
+    pub direct_radiation: Option<WattsPerSquareMeter>,
+    pub diffuse_radiation: Option<WattsPerSquareMeter>,
+    pub sunshine: Option<Sunshine>,
+    pub condition: Option<WeatherCondition>,
+    // Time the weather was observed or measured at
+    pub observation_time: Option<DateTime<Utc>>,
+    // Module of a station with several ones per location, e.g. an indoor module
+    pub module: Option<String>,
+    pub co2: Option<PartsPerMillion>,
+    pub noise: Option<Decibels>,
+}
+
+impl Weather {
+    // Only the required fields, providers fill in what they know with struct update syntax
+    pub const fn new(
+        source: String,
+        location: String,
+        coordinates: Coordinates,
+        temperature: Celsius,
+    ) -> Self {
+        Self {
+            location,
+            source,
+            city: None,
+            coordinates,
+            distance: None,
+            temperature,
+            temperatures_at_height: Vec::new(),
+            relative_humidity: None,
+            dew_point: None,
+            apparent_temperature: None,
+            station_pressure: None,
+            sea_level_pressure: None,
+            wind_speed: None,
+            wind_gust_speed: None,
+            wind_direction: None,
+            precipitation: None,
+            precipitation_intensity: None,
+            daily_precipitation: None,
+            snow_depth: None,
+            snowfall: None,
+            cloud_cover: None,
+            visibility: None,
+            uv_index: None,
+            global_radiation: None,
+            direct_radiation: None,
+            diffuse_radiation: None,
+            sunshine: None,
+            condition: None,
+            observation_time: None,
+            module: None,
+            co2: None,
+            noise: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Precipitation {
+    pub amount: Millimeters,
+    pub period: Duration,
+}
+
+// Normalized condition along with the provider specific code it was derived from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WeatherCondition {
+    pub condition: Condition,
+    pub code: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sunshine {
+    pub duration: Duration,
+    pub period: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemperatureAtHeight {
+    pub height: Meters,
+    pub temperature: Celsius,
 }
 
 pub trait WeatherProvider: Debug {
@@ -89,25 +323,46 @@ pub trait WeatherProvider: Debug {
         request: &WeatherRequest<Coordinates>,
     ) -> anyhow::Result<Weather>;
 
+    // Providers reporting several modules per location return one entry for each of them
+    fn all_for_coordinates(
+        &self,
+        client: &Client,
+        cache: &HttpRequestCache,
+        request: &WeatherRequest<Coordinates>,
+    ) -> anyhow::Result<Vec<Weather>> {
+        self.for_coordinates(client, cache, request)
+            .map(|weather| vec![weather])
+    }
+
+    // Providers receiving data in the background instead of requesting it start doing so here
+    fn start(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
     fn refresh_interval(&self) -> Duration;
     fn cache_cardinality(&self) -> usize {
         1
     }
 }
 
+// Settings of a provider that only apply to a single location, e.g. a station ID
+pub type ProviderParameters = BTreeMap<String, String>;
+
 #[derive(Debug, Clone)]
 pub struct WeatherRequest<T> {
     pub name: String,
     pub query: T,
+    pub parameters: ProviderParameters,
 }
 
 pub type HttpRequestCache = http_request::Cache;
 
+// Points are x/y, so longitude comes first
 fn to_point(coordinates: &Coordinates) -> Point<f64> {
     let owned_coordinates = coordinates.to_owned();
     (
-        owned_coordinates.latitude.into(),
         owned_coordinates.longitude.into(),
+        owned_coordinates.latitude.into(),
     )
         .into()
 }
@@ -115,3 +370,46 @@ fn to_point(coordinates: &Coordinates) -> Point<f64> {
 fn calculate_distance(left: &Coordinates, right: &Coordinates) -> Meters {
     Haversine::distance(to_point(left), to_point(right)).into()
 }
+
+fn find_closest<'items, T, F: Fn(&T) -> Coordinates>(
+    coordinates: &Coordinates,
+    items: &'items [T],
+    position: F,
+) -> anyhow::Result<&'items T> {
+    let points: Vec<Point<f64>> = items.iter().map(|item| to_point(&position(item))).collect();
+
+    match MultiPoint::new(points.clone()).closest_point(&to_point(coordinates)) {
+        Closest::SinglePoint(closest_point) | Closest::Intersection(closest_point) => points
+            .iter()
+            .position(|point| *point == closest_point)
+            .and_then(|index| items.get(index))
+            .ok_or_else(|| anyhow!("Could not find matching station")),
+        Closest::Indeterminate => Err(anyhow!("Could not find closest point")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod calculate_distance {
+        use crate::providers::calculate_distance;
+        use crate::providers::units::Coordinates;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn distance_between_coordinates() {
+            assert_eq!(
+                calculate_distance(
+                    &Coordinates {
+                        latitude: 52.52_f64.into(),
+                        longitude: 13.405_f64.into(),
+                    },
+                    &Coordinates {
+                        latitude: 48.8566_f64.into(),
+                        longitude: 2.3522_f64.into(),
+                    }
+                ),
+                877_464.537_921_509_1_f64.into()
+            );
+        }
+    }
+}
@@ -0,0 +1,533 @@
+use crate::config::NAME;
+use crate::providers::units::{Celsius, Coordinates, Ratio};
+use crate::providers::{HttpRequestCache, Weather, WeatherProvider, WeatherRequest};
+use anyhow::anyhow;
+use chrono::{DateTime, TimeDelta, Utc};
+use log::{debug, info, trace, warn};
+use reqwest::blocking::Client;
+use rumqttc::{Client as MqttClient, Event, MqttOptions, Packet, QoS};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::Duration;
+
+const SOURCE_URI: &str = "local.mqtt";
+
+const DEFAULT_PORT: u16 = 1883;
+const KEEP_ALIVE: Duration = Duration::from_secs(30);
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+const CHANNEL_CAPACITY: usize = 16;
+
+// Per location parameter restricting the location to some of the topics, separated by commas
+const TOPICS_PARAMETER: &str = "topics";
+
+// Latest message per topic
+type Readings = Arc<RwLock<HashMap<String, Reading>>>;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Mqtt {
+    host: String,
+    #[serde(default = "default_port")]
+    port: u16,
+    #[serde(default = "default_client_id")]
+    client_id: String,
+    username: Option<String>,
+    password: Option<String>,
+    // Sensors that did not publish for this long are no longer reported
+    #[serde(default = "default_max_age")]
+    #[serde(with = "humantime_serde")]
+    max_age: Duration,
+    #[serde(rename = "topic", deserialize_with = "deserialize_topics")]
+    topics: Vec<Subscription>,
+    #[serde(skip)]
+    readings: Readings,
+}
+
+const fn default_port() -> u16 {
+    DEFAULT_PORT
+}
+
+fn default_client_id() -> String {
+    NAME.into()
+}
+
+const fn default_max_age() -> Duration {
+    Duration::from_secs(60 * 30)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Subscription {
+    topic: String,
+    module: Option<String>,
+    #[serde(default)]
+    fields: FieldMapping,
+}
+
+impl Subscription {
+    // Every topic is exported as a module of its own, named after the topic unless configured
+    fn module(&self) -> &str {
+        self.module.as_deref().unwrap_or(&self.topic)
+    }
+}
+
+// Topics sharing a module would overwrite each other's series. Messages are kept per topic, so
+// wildcard subscriptions matching several topics are not supported
+fn deserialize_topics<'de, D>(deserializer: D) -> Result<Vec<Subscription>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let topics = Vec::<Subscription>::deserialize(deserializer)?;
+
+    if let Some(wildcard) = topics
+        .iter()
+        .find(|subscription| subscription.topic.contains(['+', '#']))
+    {
+        return Err(serde::de::Error::custom(format!(
+            "Topic {} contains a wildcard, subscribe to each topic instead",
+            wildcard.topic
+        )));
+    }
+
+    let mut modules = HashSet::new();
+    if let Some(duplicate) = topics
+        .iter()
+        .map(Subscription::module)
+        .find(|module| !modules.insert(*module))
+    {
+        return Err(serde::de::Error::custom(format!(
+            "Module {duplicate} is configured for several topics"
+        )));
+    }
+
+    Ok(topics)
+}
+
+// Paths of the payload fields, nested fields are separated by dots. Values are expected in the
+// units of the corresponding metric
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+struct FieldMapping {
+    temperature: String,
+    relative_humidity: Option<String>,
+    dew_point: Option<String>,
+    station_pressure: Option<String>,
+    sea_level_pressure: Option<String>,
+    wind_speed: Option<String>,
+    wind_gust_speed: Option<String>,
+    wind_direction: Option<String>,
+    precipitation_intensity: Option<String>,
+    uv_index: Option<String>,
+    global_radiation: Option<String>,
+    co2: Option<String>,
+    noise: Option<String>,
+}
+
+// Field names as published by Zigbee2MQTT
+impl Default for FieldMapping {
+    fn default() -> Self {
+        Self {
+            temperature: "temperature".into(),
+            relative_humidity: Some("humidity".into()),
+            dew_point: None,
+            station_pressure: Some("pressure".into()),
+            sea_level_pressure: None,
+            wind_speed: None,
+            wind_gust_speed: None,
+            wind_direction: None,
+            precipitation_intensity: None,
+            uv_index: None,
+            global_radiation: None,
+            co2: Some("co2".into()),
+            noise: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Reading {
+    payload: Value,
+    received: DateTime<Utc>,
+}
+
+fn store(readings: &Readings, topic: &str, payload: &[u8], received: DateTime<Utc>) {
+    let Ok(value) = serde_json::from_slice(payload) else {
+        debug!("Ignoring MQTT message on {topic} that is not JSON");
+        return;
+    };
+
+    match readings.write() {
+        Ok(mut latest) => {
+            latest.insert(
+                topic.to_owned(),
+                Reading {
+                    payload: value,
+                    received,
+                },
+            );
+        }
+        Err(e) => warn!("Could not store MQTT message: {e}"),
+    }
+}
+
+fn lookup(payload: &Value, path: &str) -> Option<f64> {
+    path.split('.')
+        .try_fold(payload, |value, key| value.get(key))?
+        .as_f64()
+}
+
+fn to_weather(
+    request: &WeatherRequest<Coordinates>,
+    topic: &Subscription,
+    reading: &Reading,
+) -> Option<Weather> {
+    let fields = &topic.fields;
+    let field = |path: Option<&String>| path.and_then(|key| lookup(&reading.payload, key));
+
+    let Some(temperature) = lookup(&reading.payload, &fields.temperature) else {
+        debug!("No temperature in message on {}", topic.topic);
+        return None;
+    };
+
+    Some(Weather {
+        relative_humidity: field(fields.relative_humidity.as_ref()).map(Ratio::Percentage),
+        dew_point: field(fields.dew_point.as_ref()).map(Celsius::from_f64),
+        station_pressure: field(fields.station_pressure.as_ref()).map(Into::into),
+        sea_level_pressure: field(fields.sea_level_pressure.as_ref()).map(Into::into),
+        wind_speed: field(fields.wind_speed.as_ref()).map(Into::into),
+        wind_gust_speed: field(fields.wind_gust_speed.as_ref()).map(Into::into),
+        wind_direction: field(fields.wind_direction.as_ref()).map(Into::into),
+        precipitation_intensity: field(fields.precipitation_intensity.as_ref()).map(Into::into),
+        uv_index: field(fields.uv_index.as_ref()),
+        global_radiation: field(fields.global_radiation.as_ref()).map(Into::into),
+        observation_time: Some(reading.received),
+        module: Some(topic.module().to_owned()),
+        co2: field(fields.co2.as_ref()).map(Into::into),
+        noise: field(fields.noise.as_ref()).map(Into::into),
+        ..Weather::new(
+            SOURCE_URI.into(),
+            request.name.clone(),
+            request.query.clone(),
+            Celsius::from_f64(temperature),
+        )
+    })
+}
+
+fn is_requested(request: &WeatherRequest<Coordinates>, topic: &Subscription) -> bool {
+    request
+        .parameters
+        .get(TOPICS_PARAMETER)
+        .map_or(true, |topics| {
+            topics.split(',').any(|name| name.trim() == topic.topic)
+        })
+}
+
+impl Mqtt {
+    fn weathers_since(
+        &self,
+        request: &WeatherRequest<Coordinates>,
+        not_before: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<Weather>> {
+        let readings = self
+            .readings
+            .read()
+            .map_err(|e| anyhow!("Could not read MQTT messages: {e}"))?;
+
+        let weathers: Vec<Weather> = self
+            .topics
+            .iter()
+            .filter(|topic| is_requested(request, topic))
+            .filter_map(|topic| {
+                let reading = readings.get(&topic.topic)?;
+                if reading.received < not_before {
+                    trace!("Last message on {} is outdated", topic.topic);
+                    return None;
+                }
+
+                to_weather(request, topic, reading)
+            })
+            .collect();
+
+        if weathers.is_empty() {
+            return Err(anyhow!(
+                "No recent MQTT messages for location {}",
+                request.name
+            ));
+        }
+
+        Ok(weathers)
+    }
+}
+
+impl WeatherProvider for Mqtt {
+    fn id(&self) -> &str {
+        SOURCE_URI
+    }
+
+    fn for_coordinates(
+        &self,
+        client: &Client,
+        cache: &HttpRequestCache,
+        request: &WeatherRequest<Coordinates>,
+    ) -> anyhow::Result<Weather> {
+        self.all_for_coordinates(client, cache, request)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("No MQTT messages"))
+    }
+
+    fn all_for_coordinates(
+        &self,
+        _client: &Client,
+        _cache: &HttpRequestCache,
+        request: &WeatherRequest<Coordinates>,
+    ) -> anyhow::Result<Vec<Weather>> {
+        let not_before = TimeDelta::from_std(self.max_age)
+            .ok()
+            .and_then(|max_age| Utc::now().checked_sub_signed(max_age))
+            .ok_or_else(|| anyhow!("Invalid maximum age {:?}", self.max_age))?;
+
+        self.weathers_since(request, not_before)
+    }
+
+    // Messages are received by a background thread that reconnects on errors
+    fn start(&self) -> anyhow::Result<()> {
+        let mut options = MqttOptions::new(&self.client_id, &self.host, self.port);
+        options.set_keep_alive(KEEP_ALIVE);
+        if let Some(username) = &self.username {
+            options.set_credentials(username, self.password.clone().unwrap_or_default());
+        }
+
+        let (client, mut connection) = MqttClient::new(options, CHANNEL_CAPACITY);
+        let topics: Vec<String> = self
+            .topics
+            .iter()
+            .map(|topic| topic.topic.clone())
+            .collect();
+        let readings = Arc::clone(&self.readings);
+        let broker = format!("{}:{}", self.host, self.port);
+
+        thread::Builder::new().name("mqtt".into()).spawn(move || {
+            for notification in connection.iter() {
+                match notification {
+                    // Subscriptions do not survive reconnects with a clean session
+                    Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                        info!("Connected to MQTT broker {broker}");
+                        for topic in &topics {
+                            if let Err(e) = client.try_subscribe(topic, QoS::AtMostOnce) {
+                                warn!("Could not subscribe to {topic}: {e}");
+                            }
+                        }
+                    }
+                    Ok(Event::Incoming(Packet::Publish(publish))) => {
+                        store(&readings, &publish.topic, &publish.payload, Utc::now());
+                    }
+                    Ok(event) => trace!("MQTT event {event:?}"),
+                    Err(e) => {
+                        warn!("MQTT connection to {broker} failed: {e}");
+                        thread::sleep(RECONNECT_DELAY);
+                    }
+                }
+            }
+        })?;
+
+        Ok(())
+    }
+
+    fn refresh_interval(&self) -> Duration {
+        self.max_age
+    }
+
+    // Messages are pushed by the broker, nothing is requested
+    fn cache_cardinality(&self) -> usize {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod deserialize {
+        use crate::providers::mqtt::Mqtt;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn reject_wildcards() {
+            assert_eq!(
+                ["zigbee2mqtt/+", "tele/#"].map(|topic| toml::from_str::<Mqtt>(&format!(
+                    r#"
+                        host = "localhost"
+
+                        [[topic]]
+                        topic = "{topic}"
+                    "#
+                ))
+                .expect_err("Wildcards are not supported")
+                .message()
+                .to_owned()),
+                [
+                    "Topic zigbee2mqtt/+ contains a wildcard, subscribe to each topic instead",
+                    "Topic tele/# contains a wildcard, subscribe to each topic instead",
+                ]
+            );
+        }
+
+        #[test]
+        fn reject_duplicate_modules() {
+            assert_eq!(
+                toml::from_str::<Mqtt>(
+                    r#"
+                        host = "localhost"
+
+                        [[topic]]
+                        topic = "zigbee2mqtt/garden"
+
+                        [[topic]]
+                        topic = "shellies/shellyht-garden/status"
+                        module = "zigbee2mqtt/garden"
+                    "#
+                )
+                .expect_err("Modules are ambiguous")
+                .message(),
+                "Module zigbee2mqtt/garden is configured for several topics"
+            );
+        }
+    }
+
+    mod weathers_since {
+        use crate::providers::mqtt::{store, Mqtt, Readings};
+        use crate::providers::testing::request;
+        use crate::providers::units::{Coordinates, Ratio};
+        use crate::providers::{ProviderParameters, Weather, WeatherRequest};
+        use chrono::{DateTime, TimeDelta, Utc};
+        use pretty_assertions::assert_eq;
+
+        // A Zigbee2MQTT sensor with the default mapping and a Tasmota device with nested fields
+        const CONFIG: &str = r#"
+            host = "localhost"
+
+            [[topic]]
+            topic = "zigbee2mqtt/garden"
+
+            [[topic]]
+            topic = "tele/living_room/SENSOR"
+            module = "Living room"
+            fields = { temperature = "AM2301.Temperature", relative_humidity = "AM2301.Humidity" }
+        "#;
+
+        fn provider() -> Mqtt {
+            let mqtt: Mqtt = toml::from_str(CONFIG).expect("Config is valid");
+            publish(&mqtt.readings);
+            mqtt
+        }
+
+        fn home() -> WeatherRequest<Coordinates> {
+            request("Home", 52.520_008_f64, 13.404_954_f64)
+        }
+
+        fn received() -> DateTime<Utc> {
+            DateTime::parse_from_rfc3339("2024-06-01T12:55:00Z")
+                .expect("Static value")
+                .with_timezone(&Utc)
+        }
+
+        fn received_after(delta: TimeDelta) -> DateTime<Utc> {
+            received().checked_add_signed(delta).expect("Static value")
+        }
+
+        fn publish(readings: &Readings) {
+            store(
+                readings,
+                "zigbee2mqtt/garden",
+                br#"{"battery":97,"humidity":66.5,"linkquality":134,"pressure":1008.9,"temperature":18.25}"#,
+                received(),
+            );
+            store(
+                readings,
+                "tele/living_room/SENSOR",
+                br#"{"Time":"2024-06-01T14:55:00","AM2301":{"Temperature":22.5,"Humidity":48.0},"TempUnit":"C"}"#,
+                received_after(TimeDelta::seconds(30_i64)),
+            );
+            store(readings, "zigbee2mqtt/bridge/state", b"online", received());
+        }
+
+        fn garden() -> Weather {
+            Weather {
+                relative_humidity: Some(Ratio::Percentage(66.5_f64)),
+                station_pressure: Some(1008.9_f64.into()),
+                observation_time: Some(received()),
+                module: Some("zigbee2mqtt/garden".into()),
+                ..Weather::new(
+                    "local.mqtt".into(),
+                    "Home".into(),
+                    home().query,
+                    18.25_f32.into(),
+                )
+            }
+        }
+
+        fn living_room() -> Weather {
+            Weather {
+                relative_humidity: Some(Ratio::Percentage(48_f64)),
+                observation_time: Some(received_after(TimeDelta::seconds(30_i64))),
+                module: Some("Living room".into()),
+                ..Weather::new(
+                    "local.mqtt".into(),
+                    "Home".into(),
+                    home().query,
+                    22.5_f32.into(),
+                )
+            }
+        }
+
+        #[test]
+        fn map_payloads_of_recent_messages() {
+            assert_eq!(
+                provider()
+                    .weathers_since(&home(), received())
+                    .expect("Messages are recent"),
+                [garden(), living_room()]
+            );
+        }
+
+        #[test]
+        fn skip_outdated_messages() {
+            assert_eq!(
+                provider()
+                    .weathers_since(&home(), received_after(TimeDelta::seconds(10_i64)))
+                    .expect("One message is recent"),
+                [living_room()]
+            );
+        }
+
+        #[test]
+        fn skip_unrequested_topics() {
+            assert_eq!(
+                provider()
+                    .weathers_since(
+                        &WeatherRequest {
+                            parameters: ProviderParameters::from([(
+                                "topics".into(),
+                                "zigbee2mqtt/garden".into(),
+                            )]),
+                            ..home()
+                        },
+                        received(),
+                    )
+                    .expect("Requested topic has a message"),
+                [garden()]
+            );
+        }
+
+        #[test]
+        fn fail_without_recent_messages() {
+            assert_eq!(
+                provider()
+                    .weathers_since(&home(), received_after(TimeDelta::minutes(5_i64)))
+                    .expect_err("All sensors are dead")
+                    .to_string(),
+                "No recent MQTT messages for location Home"
+            );
+        }
+    }
+}
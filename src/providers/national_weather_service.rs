@@ -0,0 +1,404 @@
+use crate::providers::http_request::{request_cached, Configuration, HttpCacheRequest};
+use crate::providers::units::{
+    Celsius, Coordinates, Degrees, KilometersPerHour, Meters, Millimeters, Pascals, Ratio,
+    ToMetersPerSecond,
+};
+use crate::providers::{
+    calculate_distance, HttpRequestCache, Precipitation, Weather, WeatherProvider, WeatherRequest,
+};
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use log::warn;
+use moka::sync::CacheBuilder;
+use once_cell::sync::Lazy;
+use reqwest::blocking::Client;
+use reqwest::{Method, Url};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const SOURCE_URI: &str = "gov.weather";
+const BASE_URL: &str = "https://api.weather.gov";
+
+const UNIT_CELSIUS: &str = "wmoUnit:degC";
+const UNIT_PERCENT: &str = "wmoUnit:percent";
+const UNIT_PASCAL: &str = "wmoUnit:Pa";
+const UNIT_KILOMETERS_PER_HOUR: &str = "wmoUnit:km_h-1";
+const UNIT_DEGREES: &str = "wmoUnit:degree_(angle)";
+const UNIT_METERS: &str = "wmoUnit:m";
+const UNIT_MILLIMETERS: &str = "wmoUnit:mm";
+
+// Grid points and their stations hardly ever change, so they are cached independently of the
+// much shorter lived observations
+const STATION_CACHE_CAPACITY: u64 = 1000;
+const STATION_CACHE_TIME_TO_LIVE: Duration = Duration::from_secs(60 * 60 * 24);
+
+static STATION_CACHE: Lazy<HttpRequestCache> = Lazy::new(|| {
+    CacheBuilder::new(STATION_CACHE_CAPACITY)
+        .time_to_live(STATION_CACHE_TIME_TO_LIVE)
+        .build()
+});
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NationalWeatherService {
+    #[serde(flatten)]
+    cache: Configuration,
+}
+
+#[derive(Deserialize, Debug)]
+struct NationalWeatherServicePoint {
+    properties: NationalWeatherServicePointProperties,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct NationalWeatherServicePointProperties {
+    observation_stations: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct NationalWeatherServiceStations {
+    features: Vec<NationalWeatherServiceStation>,
+}
+
+#[derive(Deserialize, Debug)]
+struct NationalWeatherServiceStation {
+    geometry: NationalWeatherServiceGeometry,
+    properties: NationalWeatherServiceStationProperties,
+}
+
+#[derive(Deserialize, Debug)]
+struct NationalWeatherServiceGeometry {
+    // GeoJSON positions are longitude first
+    coordinates: Vec<f64>,
+}
+
+impl NationalWeatherServiceGeometry {
+    fn to_coordinates(&self) -> anyhow::Result<Coordinates> {
+        match self.coordinates.as_slice() {
+            [longitude, latitude, ..] => Ok(Coordinates {
+                latitude: (*latitude).into(),
+                longitude: (*longitude).into(),
+            }),
+            _ => Err(anyhow!("Invalid station position {:?}", self.coordinates)),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct NationalWeatherServiceStationProperties {
+    station_identifier: String,
+    name: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct NationalWeatherServiceObservation {
+    properties: NationalWeatherServiceObservationProperties,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct NationalWeatherServiceObservationProperties {
+    timestamp: DateTime<Utc>,
+    temperature: Option<NationalWeatherServiceValue<Celsius>>,
+    dewpoint: Option<NationalWeatherServiceValue<Celsius>>,
+    relative_humidity: Option<NationalWeatherServiceValue<Ratio>>,
+    barometric_pressure: Option<NationalWeatherServiceValue<Pascals>>,
+    sea_level_pressure: Option<NationalWeatherServiceValue<Pascals>>,
+    wind_speed: Option<NationalWeatherServiceValue<KilometersPerHour>>,
+    wind_gust: Option<NationalWeatherServiceValue<KilometersPerHour>>,
+    wind_direction: Option<NationalWeatherServiceValue<Degrees>>,
+    visibility: Option<NationalWeatherServiceValue<Meters>>,
+    precipitation_last_hour: Option<NationalWeatherServiceValue<Millimeters>>,
+}
+
+// Values are null if a station does not measure them or they failed quality control
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct NationalWeatherServiceValue<T> {
+    unit_code: String,
+    value: Option<T>,
+}
+
+impl<T> NationalWeatherServiceValue<T> {
+    fn value_in(self, unit_code: &str) -> Option<T> {
+        if self.unit_code == unit_code {
+            self.value
+        } else {
+            warn!(
+                "Ignoring value in unit {}, expected {}",
+                self.unit_code, unit_code
+            );
+            None
+        }
+    }
+}
+
+fn to_weather(
+    request: &WeatherRequest<Coordinates>,
+    station: NationalWeatherServiceStation,
+    observation: NationalWeatherServiceObservation,
+) -> anyhow::Result<Weather> {
+    let coordinates = station.geometry.to_coordinates()?;
+    let properties = observation.properties;
+
+    Ok(Weather {
+        distance: Some(calculate_distance(&request.query, &coordinates)),
+        city: Some(station.properties.name),
+        relative_humidity: properties
+            .relative_humidity
+            .and_then(|value| value.value_in(UNIT_PERCENT)),
+        dew_point: properties
+            .dewpoint
+            .and_then(|value| value.value_in(UNIT_CELSIUS)),
+        station_pressure: properties
+            .barometric_pressure
+            .and_then(|value| value.value_in(UNIT_PASCAL))
+            .map(Pascals::to_hectopascal),
+        sea_level_pressure: properties
+            .sea_level_pressure
+            .and_then(|value| value.value_in(UNIT_PASCAL))
+            .map(Pascals::to_hectopascal),
+        wind_speed: properties
+            .wind_speed
+            .and_then(|value| value.value_in(UNIT_KILOMETERS_PER_HOUR))
+            .map(|speed| speed.to_meters_per_second()),
+        wind_gust_speed: properties
+            .wind_gust
+            .and_then(|value| value.value_in(UNIT_KILOMETERS_PER_HOUR))
+            .map(|speed| speed.to_meters_per_second()),
+        wind_direction: properties
+            .wind_direction
+            .and_then(|value| value.value_in(UNIT_DEGREES)),
+        precipitation: properties
+            .precipitation_last_hour
+            .and_then(|value| value.value_in(UNIT_MILLIMETERS))
+            .map(|amount| Precipitation {
+                amount,
+                period: Duration::from_secs(60 * 60),
+            }),
+        visibility: properties
+            .visibility
+            .and_then(|value| value.value_in(UNIT_METERS)),
+        observation_time: Some(properties.timestamp),
+        ..Weather::new(
+            SOURCE_URI.into(),
+            request.name.clone(),
+            coordinates,
+            properties
+                .temperature
+                .and_then(|value| value.value_in(UNIT_CELSIUS))
+                .ok_or_else(|| {
+                    anyhow!(
+                        "No temperature reported by station {}",
+                        station.properties.station_identifier
+                    )
+                })?,
+        )
+    })
+}
+
+fn request_nearest_station(
+    client: &Client,
+    request: &WeatherRequest<Coordinates>,
+) -> anyhow::Result<NationalWeatherServiceStation> {
+    // Only up to four decimals are allowed, more precise coordinates are redirected
+    let points_url = Url::parse(&format!(
+        "{BASE_URL}/points/{},{}",
+        request.query.latitude.to_string_with_precision(4),
+        request.query.longitude.to_string_with_precision(4)
+    ))?;
+
+    let point: NationalWeatherServicePoint = request_cached(&HttpCacheRequest::new_json_request(
+        SOURCE_URI,
+        client,
+        &STATION_CACHE,
+        &Method::GET,
+        &points_url,
+    ))?;
+
+    let stations: NationalWeatherServiceStations =
+        request_cached(&HttpCacheRequest::new_json_request(
+            SOURCE_URI,
+            client,
+            &STATION_CACHE,
+            &Method::GET,
+            &Url::parse(&point.properties.observation_stations)?,
+        ))?;
+
+    // Stations are ordered by distance to the grid point
+    stations
+        .features
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("No observation stations found for {}", request.name))
+}
+
+impl WeatherProvider for NationalWeatherService {
+    fn id(&self) -> &str {
+        SOURCE_URI
+    }
+
+    fn for_coordinates(
+        &self,
+        client: &Client,
+        cache: &HttpRequestCache,
+        request: &WeatherRequest<Coordinates>,
+    ) -> anyhow::Result<Weather> {
+        let station = request_nearest_station(client, request)?;
+
+        let observation_url = Url::parse(&format!(
+            "{BASE_URL}/stations/{}/observations/latest",
+            station.properties.station_identifier
+        ))?;
+
+        let observation: NationalWeatherServiceObservation =
+            request_cached(&HttpCacheRequest::new_json_request(
+                SOURCE_URI,
+                client,
+                cache,
+                &Method::GET,
+                &observation_url,
+            ))?;
+
+        to_weather(request, station, observation)
+    }
+
+    fn refresh_interval(&self) -> Duration {
+        self.cache.refresh_interval
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod parse_point {
+        use crate::providers::national_weather_service::NationalWeatherServicePoint;
+        use crate::providers::testing::json_fixture;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn parse_observation_stations_url() {
+            assert_eq!(
+                json_fixture::<NationalWeatherServicePoint>("national_weather_service/points.json")
+                    .properties
+                    .observation_stations,
+                "https://api.weather.gov/gridpoints/LWX/97,71/stations"
+            );
+        }
+    }
+
+    mod to_weather {
+        use crate::providers::national_weather_service::{
+            to_weather, NationalWeatherServiceObservation, NationalWeatherServiceStation,
+            NationalWeatherServiceStations,
+        };
+        use crate::providers::testing::{json_fixture, request};
+        use crate::providers::units::{Coordinates, Ratio};
+        use crate::providers::Weather;
+        use chrono::{DateTime, Utc};
+        use pretty_assertions::assert_eq;
+
+        fn nearest_station() -> NationalWeatherServiceStation {
+            json_fixture::<NationalWeatherServiceStations>("national_weather_service/stations.json")
+                .features
+                .into_iter()
+                .next()
+                .expect("Fixture has stations")
+        }
+
+        fn station_coordinates() -> Coordinates {
+            Coordinates {
+                latitude: 38.84833_f64.into(),
+                longitude: (-77.03417_f64).into(),
+            }
+        }
+
+        fn observation_time() -> DateTime<Utc> {
+            DateTime::parse_from_rfc3339("2024-06-01T12:52:00Z")
+                .expect("Static value")
+                .with_timezone(&Utc)
+        }
+
+        fn parse_observation(observation: &str) -> NationalWeatherServiceObservation {
+            serde_json::from_str(observation).expect("Observation is valid")
+        }
+
+        #[test]
+        fn map_latest_observation() {
+            assert_eq!(
+                to_weather(
+                    &request("Washington", 38.8894_f64, -77.0352_f64),
+                    nearest_station(),
+                    json_fixture("national_weather_service/observation.json"),
+                )
+                .expect("Mapping works"),
+                Weather {
+                    city: Some("Washington/Reagan National Airport, DC".into()),
+                    distance: Some(4_567.652_457_935_344_5_f64.into()),
+                    relative_humidity: Some(Ratio::Percentage(66_f64)),
+                    dew_point: Some(15.6_f32.into()),
+                    station_pressure: Some(1015.6_f64.into()),
+                    sea_level_pressure: Some(1015.5_f64.into()),
+                    wind_speed: Some(5_f64.into()),
+                    wind_direction: Some(180_f64.into()),
+                    visibility: Some(16090_f64.into()),
+                    observation_time: Some(observation_time()),
+                    ..Weather::new(
+                        "gov.weather".into(),
+                        "Washington".into(),
+                        station_coordinates(),
+                        22.2_f32.into(),
+                    )
+                }
+            );
+        }
+
+        #[test]
+        fn unexpected_units_are_ignored() {
+            assert_eq!(
+                to_weather(
+                    &request("Washington", 38.8894_f64, -77.0352_f64),
+                    nearest_station(),
+                    parse_observation(
+                        r#"{"properties": {
+                            "timestamp": "2024-06-01T12:52:00+00:00",
+                            "temperature": {"unitCode": "wmoUnit:degC", "value": 22.2},
+                            "windSpeed": {"unitCode": "wmoUnit:m_s-1", "value": 5.1}
+                        }}"#,
+                    ),
+                )
+                .expect("Mapping works"),
+                Weather {
+                    city: Some("Washington/Reagan National Airport, DC".into()),
+                    distance: Some(4_567.652_457_935_344_5_f64.into()),
+                    observation_time: Some(observation_time()),
+                    ..Weather::new(
+                        "gov.weather".into(),
+                        "Washington".into(),
+                        station_coordinates(),
+                        22.2_f32.into(),
+                    )
+                }
+            );
+        }
+
+        #[test]
+        fn missing_temperature_is_an_error() {
+            assert_eq!(
+                to_weather(
+                    &request("Washington", 38.8894_f64, -77.0352_f64),
+                    nearest_station(),
+                    parse_observation(
+                        r#"{"properties": {
+                            "timestamp": "2024-06-01T12:52:00+00:00",
+                            "temperature": {"unitCode": "wmoUnit:degC", "value": null}
+                        }}"#,
+                    ),
+                )
+                .expect_err("Nothing to map")
+                .to_string(),
+                "No temperature reported by station KDCA"
+            );
+        }
+    }
+}
@@ -0,0 +1,663 @@
+use crate::config::NAME;
+use crate::providers::http_request::{request_cached, Configuration, HttpCacheRequest};
+use crate::providers::units::{
+    Celsius, Coordinates, Decibels, Degrees, Hectopascal, KilometersPerHour, Millimeters,
+    PartsPerMillion, Ratio, ToMetersPerSecond,
+};
+use crate::providers::{
+    calculate_distance, find_closest, HttpRequestCache, Precipitation, Weather, WeatherProvider,
+    WeatherRequest,
+};
+use anyhow::{anyhow, Context};
+use chrono::{DateTime, TimeDelta, Utc};
+use const_format::concatcp;
+use log::{debug, trace, warn};
+use once_cell::sync::Lazy;
+use reqwest::blocking::Client;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use reqwest::{Method, Url};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::iter;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+const SOURCE_URI: &str = "com.netatmo";
+const TOKEN_URL: &str = "https://api.netatmo.com/oauth2/token";
+const STATIONS_DATA_URL: &str = "https://api.netatmo.com/api/getstationsdata";
+const DEFAULT_STATE_FILE: &str = concatcp!("/var/lib/", NAME, "/netatmo.json");
+
+// Per location parameter selecting a device instead of the closest one
+const DEVICE_ID_PARAMETER: &str = "device_id";
+
+// Access tokens are refreshed a bit early so they do not expire while a request is in flight
+const TOKEN_EXPIRY_MARGIN: TimeDelta = TimeDelta::minutes(5);
+
+const MAIN_MODULE: &str = "NAMain";
+const OUTDOOR_MODULE: &str = "NAModule1";
+
+// Netatmo rotates the refresh token on every refresh, so refreshes must not run concurrently
+static TOKEN: Lazy<Mutex<Option<Token>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Netatmo {
+    client_id: String,
+    client_secret: String,
+    // Only used until the first refresh, afterwards the rotated token from the state file is used
+    refresh_token: String,
+    #[serde(default = "default_state_file")]
+    state_file: PathBuf,
+    #[serde(flatten)]
+    cache: Configuration,
+}
+
+fn default_state_file() -> PathBuf {
+    DEFAULT_STATE_FILE.into()
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct Token {
+    #[serde(rename = "access_token")]
+    access: String,
+    #[serde(rename = "refresh_token")]
+    refresh: String,
+    expires_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: String,
+    expires_in: i64,
+}
+
+impl Token {
+    fn from_response(response: TokenResponse, now: DateTime<Utc>) -> anyhow::Result<Self> {
+        Ok(Self {
+            expires_at: TimeDelta::try_seconds(response.expires_in)
+                .and_then(|expires_in| now.checked_add_signed(expires_in))
+                .ok_or_else(|| anyhow!("Invalid token lifetime {}", response.expires_in))?,
+            access: response.access_token,
+            refresh: response.refresh_token,
+        })
+    }
+
+    fn is_valid_at(&self, now: DateTime<Utc>) -> bool {
+        now.checked_add_signed(TOKEN_EXPIRY_MARGIN)
+            .is_some_and(|deadline| deadline < self.expires_at)
+    }
+}
+
+fn read_state(path: &Path) -> anyhow::Result<Option<Token>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(serde_json::from_str(&content).with_context(|| {
+            format!("Invalid Netatmo state file {}", path.display())
+        })?)),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(anyhow!(
+            "Could not read Netatmo state file {}: {error}",
+            path.display()
+        )),
+    }
+}
+
+// The state holds credentials, so it is only readable by the owner and replaced atomically
+fn write_state(path: &Path, token: &Token) -> anyhow::Result<()> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&temporary)
+        .and_then(|mut file| file.write_all(serde_json::to_string_pretty(token)?.as_bytes()))
+        .and_then(|()| fs::rename(&temporary, path))
+        .with_context(|| format!("Could not write Netatmo state file {}", path.display()))
+}
+
+#[derive(Deserialize, Debug)]
+struct StationsDataResponse {
+    body: StationsData,
+}
+
+#[derive(Deserialize, Debug)]
+struct StationsData {
+    devices: Vec<Device>,
+}
+
+// The base station is a module itself, carrying the remaining modules
+#[derive(Deserialize, Debug)]
+struct Device {
+    #[serde(flatten)]
+    main: Module,
+    place: Place,
+    #[serde(default)]
+    modules: Vec<Module>,
+}
+
+impl Device {
+    fn modules(&self) -> impl Iterator<Item = &Module> + Clone {
+        iter::once(&self.main).chain(&self.modules)
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct Place {
+    city: Option<String>,
+    // Longitude first
+    location: Vec<f64>,
+}
+
+impl Place {
+    fn coordinates(&self) -> Option<Coordinates> {
+        Some(Coordinates {
+            latitude: (*self.location.get(1)?).into(),
+            longitude: (*self.location.first()?).into(),
+        })
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct Module {
+    #[serde(rename = "_id")]
+    id: String,
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(rename = "module_name")]
+    name: Option<String>,
+    // Missing while the module is unreachable
+    dashboard_data: Option<DashboardData>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct DashboardData {
+    #[serde(rename = "time_utc")]
+    time_utc: i64,
+    temperature: Option<Celsius>,
+    humidity: Option<f64>,
+    #[serde(rename = "CO2")]
+    co2: Option<PartsPerMillion>,
+    noise: Option<Decibels>,
+    // Reduced to sea level
+    pressure: Option<Hectopascal>,
+    absolute_pressure: Option<Hectopascal>,
+    wind_strength: Option<KilometersPerHour>,
+    wind_angle: Option<Degrees>,
+    gust_strength: Option<KilometersPerHour>,
+    #[serde(rename = "sum_rain_1")]
+    rain_last_hour: Option<Millimeters>,
+}
+
+fn select_device<'devices>(
+    request: &WeatherRequest<Coordinates>,
+    devices: &'devices [Device],
+) -> anyhow::Result<&'devices Device> {
+    if let Some(device_id) = request.parameters.get(DEVICE_ID_PARAMETER) {
+        return devices
+            .iter()
+            .find(|device| device.main.id == *device_id)
+            .ok_or_else(|| anyhow!("Unknown Netatmo device {device_id}"));
+    }
+
+    let located: Vec<(&Device, Coordinates)> = devices
+        .iter()
+        .filter_map(|device| Some((device, device.place.coordinates()?)))
+        .collect();
+
+    find_closest(&request.query, &located, |(_, coordinates)| {
+        coordinates.clone()
+    })
+    .map(|(device, _)| *device)
+}
+
+// Wind and rain gauges only complement the outdoor module, every module measuring temperature
+// becomes a weather entry of its own. The outdoor entry comes first.
+fn to_weathers(
+    request: &WeatherRequest<Coordinates>,
+    device: &Device,
+) -> anyhow::Result<Vec<Weather>> {
+    let coordinates = device
+        .place
+        .coordinates()
+        .ok_or_else(|| anyhow!("Netatmo device {} has no location", device.main.id))?;
+    let distance = calculate_distance(&request.query, &coordinates);
+
+    let measurements = |field: fn(&DashboardData) -> bool| {
+        device
+            .modules()
+            .filter_map(|module| module.dashboard_data.as_ref())
+            .find(|data| field(data))
+    };
+    let wind = measurements(|data| data.wind_strength.is_some());
+    let rain = measurements(|data| data.rain_last_hour.is_some());
+    let main = device.main.dashboard_data.as_ref();
+
+    // Without an outdoor module the base station reports the outdoor measurements
+    let outdoor_type = if device.modules().any(|module| module.kind == OUTDOOR_MODULE) {
+        OUTDOOR_MODULE
+    } else {
+        MAIN_MODULE
+    };
+    let (outdoor, indoor): (Vec<&Module>, Vec<&Module>) = device
+        .modules()
+        .partition(|module| module.kind == outdoor_type);
+
+    let weathers: Vec<Weather> = outdoor
+        .iter()
+        .map(|module| (module, true))
+        .chain(indoor.iter().map(|module| (module, false)))
+        .filter_map(|(module, is_outdoor)| {
+            let data = module.dashboard_data.as_ref()?;
+            let Some(temperature) = data.temperature else {
+                debug!("Skipping Netatmo module {} without temperature", module.id);
+                return None;
+            };
+            // Pressure, wind and rain belong to the outdoor entry only
+            let (base_station, wind_gauge, rain_gauge) = if is_outdoor {
+                (main, wind, rain)
+            } else {
+                (None, None, None)
+            };
+
+            Some(Weather {
+                city: device.place.city.clone(),
+                distance: Some(distance.clone()),
+                relative_humidity: data.humidity.map(Ratio::Percentage),
+                station_pressure: base_station.and_then(|measured| measured.absolute_pressure),
+                sea_level_pressure: base_station.and_then(|measured| measured.pressure),
+                wind_speed: wind_gauge
+                    .and_then(|measured| measured.wind_strength)
+                    .map(|speed| speed.to_meters_per_second()),
+                wind_gust_speed: wind_gauge
+                    .and_then(|measured| measured.gust_strength)
+                    .map(|speed| speed.to_meters_per_second()),
+                wind_direction: wind_gauge.and_then(|measured| measured.wind_angle),
+                precipitation: rain_gauge.and_then(|measured| measured.rain_last_hour).map(
+                    |amount| Precipitation {
+                        amount,
+                        period: Duration::from_secs(60 * 60),
+                    },
+                ),
+                observation_time: DateTime::from_timestamp(data.time_utc, 0),
+                module: Some(module.name.clone().unwrap_or_else(|| module.id.clone())),
+                co2: data.co2,
+                noise: data.noise,
+                ..Weather::new(
+                    SOURCE_URI.into(),
+                    request.name.clone(),
+                    coordinates.clone(),
+                    temperature,
+                )
+            })
+        })
+        .collect();
+
+    if weathers.is_empty() {
+        return Err(anyhow!(
+            "No module of Netatmo device {} reported measurements",
+            device.main.id
+        ));
+    }
+
+    Ok(weathers)
+}
+
+impl Netatmo {
+    fn fetch_token(&self, client: &Client, refresh_token: &str) -> anyhow::Result<Token> {
+        debug!("Refreshing Netatmo access token");
+
+        let response: TokenResponse = client
+            .post(TOKEN_URL)
+            .form(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", refresh_token),
+                ("client_id", self.client_id.as_str()),
+                ("client_secret", self.client_secret.as_str()),
+            ])
+            .send()?
+            .error_for_status()?
+            .json()?;
+
+        Token::from_response(response, Utc::now())
+    }
+
+    fn access_token(&self, client: &Client) -> anyhow::Result<String> {
+        let mut current = TOKEN
+            .lock()
+            .map_err(|error| anyhow!("Netatmo token lock is poisoned: {error}"))?;
+
+        if current.is_none() {
+            *current = read_state(&self.state_file)?;
+        }
+
+        if let Some(token) = current
+            .as_ref()
+            .filter(|token| token.is_valid_at(Utc::now()))
+        {
+            return Ok(token.access.clone());
+        }
+
+        let refresh_token = current
+            .as_ref()
+            .map_or(self.refresh_token.as_str(), |token| token.refresh.as_str())
+            .to_owned();
+        // The previous refresh token is invalid now, so the new one is kept even if it cannot be persisted
+        let token = current.insert(self.fetch_token(client, &refresh_token)?);
+        if let Err(error) = write_state(&self.state_file, token) {
+            warn!("{error:#}");
+        }
+        let access_token = token.access.clone();
+        drop(current);
+
+        Ok(access_token)
+    }
+
+    fn fetch_weathers(
+        &self,
+        client: &Client,
+        cache: &HttpRequestCache,
+        request: &WeatherRequest<Coordinates>,
+    ) -> anyhow::Result<Vec<Weather>> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {}", self.access_token(client)?))?,
+        );
+
+        let url = Url::parse(STATIONS_DATA_URL)?;
+        let response: StationsDataResponse = request_cached(
+            &HttpCacheRequest::new_json_request(SOURCE_URI, client, cache, &Method::GET, &url)
+                .with_headers(headers),
+        )?;
+
+        let device = select_device(request, &response.body.devices)?;
+        trace!("Using Netatmo device {:?}", device.main.id);
+
+        to_weathers(request, device)
+    }
+}
+
+impl WeatherProvider for Netatmo {
+    fn id(&self) -> &str {
+        SOURCE_URI
+    }
+
+    fn for_coordinates(
+        &self,
+        client: &Client,
+        cache: &HttpRequestCache,
+        request: &WeatherRequest<Coordinates>,
+    ) -> anyhow::Result<Weather> {
+        self.fetch_weathers(client, cache, request)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("No Netatmo module reported measurements"))
+    }
+
+    fn all_for_coordinates(
+        &self,
+        client: &Client,
+        cache: &HttpRequestCache,
+        request: &WeatherRequest<Coordinates>,
+    ) -> anyhow::Result<Vec<Weather>> {
+        self.fetch_weathers(client, cache, request)
+    }
+
+    fn refresh_interval(&self) -> Duration {
+        self.cache.refresh_interval
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::providers::netatmo::StationsDataResponse;
+    use crate::providers::testing::{json_fixture, request};
+    use crate::providers::units::Coordinates;
+    use crate::providers::{ProviderParameters, WeatherRequest};
+    use chrono::{DateTime, Utc};
+
+    fn response() -> StationsDataResponse {
+        json_fixture("netatmo/getstationsdata.json")
+    }
+
+    fn berlin(parameters: ProviderParameters) -> WeatherRequest<Coordinates> {
+        WeatherRequest {
+            parameters,
+            ..request("Berlin", 52.520_008_f64, 13.404_954_f64)
+        }
+    }
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2024-06-01T13:00:00Z")
+            .expect("Static value")
+            .with_timezone(&Utc)
+    }
+
+    mod select_device {
+        use crate::providers::netatmo::select_device;
+        use crate::providers::netatmo::tests::{berlin, response};
+        use crate::providers::ProviderParameters;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn select_closest_or_configured_device() {
+            let response = response();
+
+            assert_eq!(
+                [None, Some("70:ee:50:00:00:02"), Some("70:ee:50:00:00:03")].map(|device_id| {
+                    select_device(
+                        &berlin(
+                            device_id
+                                .map(|id| ("device_id".into(), id.into()))
+                                .into_iter()
+                                .collect::<ProviderParameters>(),
+                        ),
+                        &response.body.devices,
+                    )
+                    .map(|device| device.main.id.as_str())
+                    .map_err(|error| error.to_string())
+                }),
+                [
+                    Ok("70:ee:50:00:00:01"),
+                    Ok("70:ee:50:00:00:02"),
+                    Err("Unknown Netatmo device 70:ee:50:00:00:03".into()),
+                ]
+            );
+        }
+    }
+
+    mod to_weathers {
+        use crate::providers::netatmo::tests::{berlin, response};
+        use crate::providers::netatmo::{select_device, to_weathers};
+        use crate::providers::units::{
+            Coordinates, KilometersPerHour, Meters, Ratio, ToMetersPerSecond,
+        };
+        use crate::providers::{Precipitation, ProviderParameters, Weather};
+        use chrono::DateTime;
+        use pretty_assertions::assert_eq;
+        use std::time::Duration;
+
+        fn berlin_device() -> Coordinates {
+            Coordinates {
+                latitude: 52.5089_f64.into(),
+                longitude: 13.4359_f64.into(),
+            }
+        }
+
+        fn distance() -> Meters {
+            2_431.214_010_185_837_f64.into()
+        }
+
+        #[test]
+        fn map_outdoor_module_first() {
+            let response = response();
+            let request = berlin(ProviderParameters::new());
+
+            assert_eq!(
+                to_weathers(
+                    &request,
+                    select_device(&request, &response.body.devices).expect("Device found"),
+                )
+                .expect("Mapping works"),
+                [
+                    Weather {
+                        city: Some("Berlin".into()),
+                        distance: Some(distance()),
+                        relative_humidity: Some(Ratio::Percentage(66_f64)),
+                        station_pressure: Some(1008.9_f64.into()),
+                        sea_level_pressure: Some(1013.4_f64.into()),
+                        wind_speed: Some(KilometersPerHour::from(18_f64).to_meters_per_second()),
+                        wind_gust_speed: Some(
+                            KilometersPerHour::from(27_f64).to_meters_per_second()
+                        ),
+                        wind_direction: Some(250_f64.into()),
+                        precipitation: Some(Precipitation {
+                            amount: 0.4_f64.into(),
+                            period: Duration::from_secs(3600),
+                        }),
+                        observation_time: DateTime::from_timestamp(1_717_246_480_i64, 0_u32),
+                        module: Some("Garden".into()),
+                        ..Weather::new(
+                            "com.netatmo".into(),
+                            "Berlin".into(),
+                            berlin_device(),
+                            18.2_f32.into(),
+                        )
+                    },
+                    Weather {
+                        city: Some("Berlin".into()),
+                        distance: Some(distance()),
+                        relative_humidity: Some(Ratio::Percentage(48_f64)),
+                        observation_time: DateTime::from_timestamp(1_717_246_500_i64, 0_u32),
+                        module: Some("Living room".into()),
+                        co2: Some(612_f64.into()),
+                        noise: Some(38_f64.into()),
+                        ..Weather::new(
+                            "com.netatmo".into(),
+                            "Berlin".into(),
+                            berlin_device(),
+                            22.5_f32.into(),
+                        )
+                    },
+                    Weather {
+                        city: Some("Berlin".into()),
+                        distance: Some(distance()),
+                        relative_humidity: Some(Ratio::Percentage(55_f64)),
+                        observation_time: DateTime::from_timestamp(1_717_246_470_i64, 0_u32),
+                        module: Some("Bedroom".into()),
+                        co2: Some(905_f64.into()),
+                        ..Weather::new(
+                            "com.netatmo".into(),
+                            "Berlin".into(),
+                            berlin_device(),
+                            20.1_f32.into(),
+                        )
+                    },
+                ]
+            );
+        }
+
+        #[test]
+        fn base_station_is_outdoor_without_outdoor_module() {
+            let response = response();
+            let request = berlin(ProviderParameters::from([(
+                "device_id".into(),
+                "70:ee:50:00:00:02".into(),
+            )]));
+
+            assert_eq!(
+                to_weathers(
+                    &request,
+                    select_device(&request, &response.body.devices).expect("Device found"),
+                )
+                .expect("Mapping works"),
+                [Weather {
+                    city: Some("Hamburg".into()),
+                    distance: Some(255_247.353_406_157_55_f64.into()),
+                    relative_humidity: Some(Ratio::Percentage(60_f64)),
+                    station_pressure: Some(1011.4_f64.into()),
+                    sea_level_pressure: Some(1012_f64.into()),
+                    observation_time: DateTime::from_timestamp(1_717_246_400_i64, 0_u32),
+                    module: Some("Kitchen".into()),
+                    co2: Some(480_f64.into()),
+                    noise: Some(35_f64.into()),
+                    ..Weather::new(
+                        "com.netatmo".into(),
+                        "Berlin".into(),
+                        Coordinates {
+                            latitude: 53.5511_f64.into(),
+                            longitude: 9.9937_f64.into(),
+                        },
+                        19_f32.into(),
+                    )
+                }]
+            );
+        }
+    }
+
+    mod is_valid_at {
+        use crate::providers::netatmo::tests::now;
+        use crate::providers::netatmo::{Token, TokenResponse};
+        use chrono::TimeDelta;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn expire_with_margin() {
+            let token = Token::from_response(
+                TokenResponse {
+                    access_token: "access".into(),
+                    refresh_token: "refresh".into(),
+                    expires_in: 10_800_i64,
+                },
+                now(),
+            )
+            .expect("Valid lifetime");
+
+            assert_eq!(
+                [0_i64, 10_500_i64, 10_800_i64]
+                    .map(|elapsed| token.is_valid_at(now() + TimeDelta::seconds(elapsed))),
+                [true, false, false]
+            );
+        }
+    }
+
+    mod write_state {
+        use crate::providers::netatmo::tests::now;
+        use crate::providers::netatmo::{read_state, write_state, Token};
+        use pretty_assertions::assert_eq;
+        use std::env;
+        use std::fs;
+        use std::os::unix::fs::PermissionsExt;
+        use std::process;
+
+        #[test]
+        fn restore_written_state() {
+            let path = env::temp_dir().join(format!("netatmo-state-{}.json", process::id()));
+            let missing = read_state(&path).expect("Missing file is fine");
+
+            let token = Token {
+                access: "access".into(),
+                refresh: "rotated".into(),
+                expires_at: now(),
+            };
+            write_state(&path, &token).expect("State is written");
+            let restored = read_state(&path).expect("State is read");
+            let mode = fs::metadata(&path)
+                .expect("State file exists")
+                .permissions()
+                .mode();
+            fs::remove_file(&path).expect("State file is removed");
+
+            assert_eq!(
+                (missing, restored, mode & 0o777),
+                (None, Some(token), 0o600)
+            );
+        }
+    }
+}
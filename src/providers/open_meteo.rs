@@ -1,7 +1,15 @@
 use crate::providers::http_request::{request_cached, Configuration, HttpCacheRequest};
-use crate::providers::units::Coordinates;
 use crate::providers::units::Ratio::Percentage;
-use crate::providers::{HttpRequestCache, Weather, WeatherProvider, WeatherRequest};
+use crate::providers::units::{
+    Celsius, Centimeters, Condition, Coordinates, Degrees, Hectopascal, KilometersPerHour, Meters,
+    Millimeters, Ratio, ToMetersPerSecond, WattsPerSquareMeter,
+};
+use crate::providers::{
+    HttpRequestCache, Precipitation, Sunshine, TemperatureAtHeight, Weather, WeatherCondition,
+    WeatherProvider, WeatherRequest,
+};
+use chrono::serde::ts_seconds;
+use chrono::{DateTime, Utc};
 use reqwest::blocking::Client;
 use reqwest::{Method, Url};
 use rocket::serde::{Deserialize, Serialize};
@@ -11,6 +19,31 @@ const SOURCE_URI: &str = "com.open-meteo";
 
 const ENDPOINT_URL: &str = "https://api.open-meteo.com/v1/forecast";
 
+const CURRENT_VARIABLES: [&str; 22] = [
+    "temperature_2m",
+    "soil_temperature_0cm",
+    "temperature_80m",
+    "relative_humidity_2m",
+    "dew_point_2m",
+    "apparent_temperature",
+    "surface_pressure",
+    "pressure_msl",
+    "wind_speed_10m",
+    "wind_gusts_10m",
+    "wind_direction_10m",
+    "precipitation",
+    "snow_depth",
+    "snowfall",
+    "cloud_cover",
+    "visibility",
+    "uv_index",
+    "shortwave_radiation",
+    "direct_radiation",
+    "diffuse_radiation",
+    "sunshine_duration",
+    "weather_code",
+];
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct OpenMeteo {
     api_key: Option<String>,
@@ -25,8 +58,32 @@ struct OpenMeteoResponse {
 
 #[derive(Deserialize, Debug)]
 struct OpenMeteoResponseCurrent {
+    #[serde(with = "ts_seconds")]
+    time: DateTime<Utc>,
+    interval: u64,
     temperature_2m: f32,
+    soil_temperature_0cm: Option<Celsius>,
+    temperature_80m: Option<Celsius>,
     relative_humidity_2m: f64,
+    dew_point_2m: Option<Celsius>,
+    apparent_temperature: Option<Celsius>,
+    surface_pressure: Option<Hectopascal>,
+    pressure_msl: Option<Hectopascal>,
+    wind_speed_10m: Option<KilometersPerHour>,
+    wind_gusts_10m: Option<KilometersPerHour>,
+    wind_direction_10m: Option<Degrees>,
+    precipitation: Option<Millimeters>,
+    snow_depth: Option<Meters>,
+    snowfall: Option<Centimeters>,
+    cloud_cover: Option<Ratio>,
+    visibility: Option<Meters>,
+    uv_index: Option<f64>,
+    shortwave_radiation: Option<WattsPerSquareMeter>,
+    direct_radiation: Option<WattsPerSquareMeter>,
+    diffuse_radiation: Option<WattsPerSquareMeter>,
+    // Seconds of sunshine within the interval
+    sunshine_duration: Option<f64>,
+    weather_code: Option<u8>,
 }
 
 impl WeatherProvider for OpenMeteo {
@@ -43,7 +100,8 @@ impl WeatherProvider for OpenMeteo {
         let mut url = Url::parse_with_params(
             ENDPOINT_URL,
             &[
-                ("current", "temperature_2m,relative_humidity_2m".to_owned()),
+                ("current", CURRENT_VARIABLES.join(",")),
+                ("timeformat", "unixtime".into()),
                 ("latitude", request.query.latitude.to_string()),
                 ("longitude", request.query.longitude.to_string()),
             ],
@@ -62,13 +120,67 @@ impl WeatherProvider for OpenMeteo {
         ))?;
 
         Ok(Weather {
-            coordinates: request.query.clone(),
-            source: SOURCE_URI.into(),
-            location: request.name.clone(),
-            city: None,
-            distance: None,
-            temperature: response.current.temperature_2m.into(),
+            temperatures_at_height: [
+                (0.0_f64, response.current.soil_temperature_0cm),
+                (80.0_f64, response.current.temperature_80m),
+            ]
+            .into_iter()
+            .filter_map(|(height, value)| {
+                value.map(|temperature| TemperatureAtHeight {
+                    height: height.into(),
+                    temperature,
+                })
+            })
+            .collect(),
             relative_humidity: Some(Percentage(response.current.relative_humidity_2m)),
+            dew_point: response.current.dew_point_2m,
+            apparent_temperature: response.current.apparent_temperature,
+            station_pressure: response.current.surface_pressure,
+            sea_level_pressure: response.current.pressure_msl,
+            wind_speed: response
+                .current
+                .wind_speed_10m
+                .map(|speed| speed.to_meters_per_second()),
+            wind_gust_speed: response
+                .current
+                .wind_gusts_10m
+                .map(|speed| speed.to_meters_per_second()),
+            wind_direction: response.current.wind_direction_10m,
+            // Precipitation is the sum over the preceding interval
+            precipitation: response.current.precipitation.map(|amount| Precipitation {
+                amount,
+                period: Duration::from_secs(response.current.interval),
+            }),
+            snow_depth: response.current.snow_depth,
+            snowfall: response.current.snowfall.map(|amount| Precipitation {
+                amount: amount.to_millimeters(),
+                period: Duration::from_secs(response.current.interval),
+            }),
+            cloud_cover: response.current.cloud_cover,
+            visibility: response.current.visibility,
+            uv_index: response.current.uv_index,
+            global_radiation: response.current.shortwave_radiation,
+            direct_radiation: response.current.direct_radiation,
+            diffuse_radiation: response.current.diffuse_radiation,
+            sunshine: response
+                .current
+                .sunshine_duration
+                .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+                .map(|duration| Sunshine {
+                    duration,
+                    period: Duration::from_secs(response.current.interval),
+                }),
+            condition: response.current.weather_code.map(|code| WeatherCondition {
+                condition: Condition::from_wmo_code(code),
+                code: code.to_string(),
+            }),
+            observation_time: Some(response.current.time),
+            ..Weather::new(
+                SOURCE_URI.into(),
+                request.name.clone(),
+                request.query.clone(),
+                response.current.temperature_2m.into(),
+            )
         })
     }
 
@@ -1,8 +1,14 @@
 use crate::providers::http_request::{request_cached, Configuration, HttpCacheRequest};
-use crate::providers::units::{Coordinates, Kelvin, Ratio, ToCelsius};
+use crate::providers::units::{
+    Condition, Coordinates, Degrees, Hectopascal, Kelvin, Meters, MetersPerSecond, Millimeters,
+    Ratio, ToCelsius,
+};
 use crate::providers::{
-    calculate_distance, HttpRequestCache, Weather, WeatherProvider, WeatherRequest,
+    calculate_distance, HttpRequestCache, Precipitation, Weather, WeatherCondition,
+    WeatherProvider, WeatherRequest,
 };
+use chrono::serde::ts_seconds;
+use chrono::{DateTime, Utc};
 use reqwest::blocking::Client;
 use reqwest::{Method, Url};
 use rocket::serde::Deserialize;
@@ -24,14 +30,67 @@ pub struct OpenWeather {
 #[derive(Deserialize, Debug)]
 struct OpenWeatherResponseMain {
     temp: Kelvin,
+    feels_like: Option<Kelvin>,
     humidity: Ratio,
+    pressure: Hectopascal,
+    grnd_level: Option<Hectopascal>,
+}
+
+#[derive(Deserialize, Debug)]
+struct OpenWeatherResponseWind {
+    speed: MetersPerSecond,
+    deg: Degrees,
+    gust: Option<MetersPerSecond>,
+}
+
+#[derive(Deserialize, Debug)]
+struct OpenWeatherResponsePrecipitation {
+    #[serde(rename = "1h")]
+    one_hour: Option<Millimeters>,
+}
+
+#[derive(Deserialize, Debug)]
+struct OpenWeatherResponseClouds {
+    all: Ratio,
+}
+
+#[derive(Deserialize, Debug)]
+struct OpenWeatherResponseCondition {
+    id: u16,
 }
 
 #[derive(Deserialize, Debug)]
 struct OpenWeatherResponse {
+    #[serde(with = "ts_seconds")]
+    dt: DateTime<Utc>,
     coord: Coordinates,
     name: String,
     main: OpenWeatherResponseMain,
+    wind: Option<OpenWeatherResponseWind>,
+    rain: Option<OpenWeatherResponsePrecipitation>,
+    snow: Option<OpenWeatherResponsePrecipitation>,
+    clouds: Option<OpenWeatherResponseClouds>,
+    visibility: Option<Meters>,
+    #[serde(default)]
+    weather: Vec<OpenWeatherResponseCondition>,
+}
+
+// See https://openweathermap.org/weather-conditions
+const fn condition_from_code(code: u16) -> Condition {
+    match code {
+        200..=299 => Condition::Thunderstorm,
+        300..=399 => Condition::Drizzle,
+        511 => Condition::FreezingRain,
+        500..=599 => Condition::Rain,
+        611..=616 => Condition::Sleet,
+        600..=699 => Condition::Snow,
+        701 | 741 => Condition::Fog,
+        700..=799 => Condition::Haze,
+        800 => Condition::Clear,
+        801 | 802 => Condition::PartlyCloudy,
+        803 | 804 => Condition::Cloudy,
+        _ => Condition::Unknown,
+    }
 }
 
 impl WeatherProvider for OpenWeather {
@@ -65,13 +124,42 @@ impl WeatherProvider for OpenWeather {
         let distance = calculate_distance(&request.query, &response.coord);
 
         Ok(Weather {
-            source: SOURCE_URI.into(),
-            location: request.name.clone(),
             city: Some(response.name),
-            coordinates: response.coord,
             distance: Some(distance),
-            temperature: response.main.temp.to_celsius(),
             relative_humidity: Some(response.main.humidity),
+            apparent_temperature: response.main.feels_like.map(|t| t.to_celsius()),
+            station_pressure: response.main.grnd_level,
+            sea_level_pressure: Some(response.main.pressure),
+            wind_speed: response.wind.as_ref().map(|wind| wind.speed),
+            wind_gust_speed: response.wind.as_ref().and_then(|wind| wind.gust),
+            wind_direction: response.wind.as_ref().map(|wind| wind.deg),
+            // OpenWeather omits rain and snow data altogether if it does not rain or snow. Snow is
+            // reported as liquid water equivalent, so it counts towards precipitation but is not
+            // the depth of fresh snow
+            precipitation: Some(Precipitation {
+                amount: [response.rain, response.snow]
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|precipitation| precipitation.one_hour)
+                    .map(f64::from)
+                    .sum::<f64>()
+                    .into(),
+                period: Duration::from_secs(60 * 60),
+            }),
+            cloud_cover: response.clouds.map(|clouds| clouds.all),
+            visibility: response.visibility,
+            // The first condition is the primary one
+            condition: response.weather.first().map(|weather| WeatherCondition {
+                condition: condition_from_code(weather.id),
+                code: weather.id.to_string(),
+            }),
+            observation_time: Some(response.dt),
+            ..Weather::new(
+                SOURCE_URI.into(),
+                request.name.clone(),
+                response.coord,
+                response.main.temp.to_celsius(),
+            )
         })
     }
 
@@ -0,0 +1,59 @@
+// Helpers for station networks that publish their measurements as semicolon separated CSV files
+use csv::Trim;
+use serde::de::{DeserializeOwned, Error};
+use serde::{Deserialize, Deserializer};
+
+pub(in crate::providers) fn parse_measurement_data_csv<T: DeserializeOwned>(
+    data: &str,
+) -> anyhow::Result<Vec<T>> {
+    let reader = csv::ReaderBuilder::new()
+        .delimiter(b';')
+        .double_quote(false)
+        .trim(Trim::All)
+        .from_reader(data.as_bytes());
+
+    Ok(reader.into_deserialize::<T>().collect::<Result<_, _>>()?)
+}
+
+// Station lists are commonly published in Latin-1
+pub(in crate::providers) fn decode_latin1(body: &[u8]) -> String {
+    body.iter()
+        .filter_map(|&c| char::from_u32(c.into()))
+        .collect()
+}
+
+pub(in crate::providers) fn deserialize_optional_measurement<'de, D, T>(
+    deserializer: D,
+    missing_value: &str,
+) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    let s = String::deserialize(deserializer)?;
+
+    if s == missing_value {
+        return Ok(None);
+    }
+
+    // Measurements are plain decimal numbers, which makes them valid JSON numbers as well
+    serde_json::from_str(&s).map(Some).map_err(Error::custom)
+}
+
+pub(in crate::providers) mod minute_precision_date_format {
+    use chrono::{DateTime, NaiveDateTime, Utc};
+    use serde::de::Error;
+    use serde::{self, Deserialize, Deserializer};
+
+    const FORMAT: &str = "%Y%m%d%H%M";
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        NaiveDateTime::parse_from_str(&s, FORMAT)
+            .map(|v| v.and_utc())
+            .map_err(Error::custom)
+    }
+}
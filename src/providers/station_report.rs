@@ -0,0 +1,345 @@
+use crate::providers::units::{
+    Celsius, Coordinates, Degrees, Fahrenheit, Hectopascal, Inches, InchesOfMercury, InchesPerHour,
+    MetersPerSecond, MilesPerHour, Millimeters, MillimetersPerHour, Ratio, ToCelsius,
+    ToMetersPerSecond, WattsPerSquareMeter,
+};
+use crate::providers::{Precipitation, Weather, WeatherRequest};
+use anyhow::anyhow;
+use chrono::{DateTime, NaiveDateTime, TimeDelta, Utc};
+use log::debug;
+use moka::sync::Cache;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+
+// Shared by the upload protocols weather station consoles use to push their readings in
+// imperial units. The field names that differ between the protocols are configured per protocol.
+
+const DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+const INDOOR_MODULE: &str = "Indoor";
+
+// Reports are pushed from the outside, so the number of stations kept is capped
+const MAX_STATIONS: u64 = 16;
+
+pub(in crate::providers) type ReportStore = Cache<String, Report>;
+
+pub(in crate::providers) fn new_report_store() -> ReportStore {
+    Cache::new(MAX_STATIONS)
+}
+
+pub(in crate::providers) struct FieldNames {
+    // The first of these fields present identifies the station
+    pub(in crate::providers) station: &'static [&'static str],
+    pub(in crate::providers) indoor_temperature: &'static str,
+    pub(in crate::providers) indoor_relative_humidity: &'static str,
+    pub(in crate::providers) station_pressure: &'static str,
+    pub(in crate::providers) sea_level_pressure: &'static str,
+    pub(in crate::providers) precipitation_last_hour: &'static str,
+    pub(in crate::providers) uv_index: &'static str,
+}
+
+// Imperial values of a report converted to metric units
+#[derive(Debug, Clone, PartialEq)]
+pub(in crate::providers) struct Report {
+    pub(in crate::providers) station: String,
+    pub(in crate::providers) received: DateTime<Utc>,
+    pub(in crate::providers) observation_time: DateTime<Utc>,
+    pub(in crate::providers) temperature: Option<Celsius>,
+    pub(in crate::providers) relative_humidity: Option<f64>,
+    pub(in crate::providers) dew_point: Option<Celsius>,
+    pub(in crate::providers) indoor_temperature: Option<Celsius>,
+    pub(in crate::providers) indoor_relative_humidity: Option<f64>,
+    pub(in crate::providers) station_pressure: Option<Hectopascal>,
+    pub(in crate::providers) sea_level_pressure: Option<Hectopascal>,
+    pub(in crate::providers) wind_speed: Option<MetersPerSecond>,
+    pub(in crate::providers) wind_gust_speed: Option<MetersPerSecond>,
+    pub(in crate::providers) wind_direction: Option<Degrees>,
+    pub(in crate::providers) precipitation_last_hour: Option<Millimeters>,
+    pub(in crate::providers) precipitation_intensity: Option<MillimetersPerHour>,
+    pub(in crate::providers) global_radiation: Option<WattsPerSquareMeter>,
+    pub(in crate::providers) uv_index: Option<f64>,
+}
+
+// Sensors that are not connected are left out or reported with values that do not parse
+fn number<T: FromStr>(fields: &HashMap<String, String>, key: &str) -> Option<T> {
+    fields.get(key).and_then(|value| value.trim().parse().ok())
+}
+
+fn temperature(fields: &HashMap<String, String>, key: &str) -> Option<Celsius> {
+    number(fields, key).map(|value: f32| Fahrenheit::from(value).to_celsius())
+}
+
+fn pressure(fields: &HashMap<String, String>, key: &str) -> Option<Hectopascal> {
+    number(fields, key).map(|value: f64| InchesOfMercury::from(value).to_hectopascal())
+}
+
+fn speed(fields: &HashMap<String, String>, key: &str) -> Option<MetersPerSecond> {
+    number(fields, key).map(|value: f64| MilesPerHour::from(value).to_meters_per_second())
+}
+
+// Consoles without a time source send "now" instead of a timestamp
+fn observation_time(fields: &HashMap<String, String>) -> Option<DateTime<Utc>> {
+    let date = fields.get("dateutc")?.replace('+', " ");
+
+    NaiveDateTime::parse_from_str(&date, DATE_FORMAT)
+        .ok()
+        .map(|time| time.and_utc())
+}
+
+impl Report {
+    pub(in crate::providers) fn parse(
+        fields: &HashMap<String, String>,
+        names: &FieldNames,
+        received: DateTime<Utc>,
+    ) -> anyhow::Result<Self> {
+        let station = names
+            .station
+            .iter()
+            .find_map(|key| fields.get(*key))
+            .filter(|station| !station.is_empty())
+            .ok_or_else(|| anyhow!("Report does not identify the station"))?;
+
+        Ok(Self {
+            station: station.clone(),
+            received,
+            observation_time: observation_time(fields).unwrap_or(received),
+            temperature: temperature(fields, "tempf"),
+            relative_humidity: number(fields, "humidity"),
+            dew_point: temperature(fields, "dewptf"),
+            indoor_temperature: temperature(fields, names.indoor_temperature),
+            indoor_relative_humidity: number(fields, names.indoor_relative_humidity),
+            station_pressure: pressure(fields, names.station_pressure),
+            sea_level_pressure: pressure(fields, names.sea_level_pressure),
+            wind_speed: speed(fields, "windspeedmph"),
+            wind_gust_speed: speed(fields, "windgustmph"),
+            wind_direction: number(fields, "winddir").map(|value: f64| Degrees::from(value)),
+            precipitation_last_hour: number(fields, names.precipitation_last_hour)
+                .map(|value: f64| Inches::from(value).to_millimeters()),
+            precipitation_intensity: number(fields, "rainratein")
+                .map(|value: f64| InchesPerHour::from(value).to_millimeters_per_hour()),
+            global_radiation: number(fields, "solarradiation")
+                .map(|value: f64| WattsPerSquareMeter::from(value)),
+            uv_index: number(fields, names.uv_index),
+        })
+    }
+}
+
+// The latest report not older than the given time, optionally restricted to one station
+fn select_report(
+    station: Option<&String>,
+    reports: impl Iterator<Item = Report>,
+    not_before: DateTime<Utc>,
+) -> anyhow::Result<Report> {
+    reports
+        // Stations are accepted regardless of case, e.g. MAC addresses
+        .filter(|report| station.map_or(true, |id| report.station.eq_ignore_ascii_case(id)))
+        .filter(|report| report.received >= not_before)
+        .max_by_key(|report| report.received)
+        .ok_or_else(|| {
+            anyhow!(
+                "No recent report from station {}",
+                station.map_or("", String::as_str)
+            )
+        })
+}
+
+// The outdoor sensors are reported as regular weather, indoor sensors of the console as a module
+pub(in crate::providers) fn to_weathers(
+    source: &str,
+    request: &WeatherRequest<Coordinates>,
+    report: &Report,
+) -> Vec<Weather> {
+    let weather = |module: Option<String>, temperature, relative_humidity: Option<f64>| Weather {
+        relative_humidity: relative_humidity.map(Ratio::Percentage),
+        observation_time: Some(report.observation_time),
+        module,
+        ..Weather::new(
+            source.into(),
+            request.name.clone(),
+            request.query.clone(),
+            temperature,
+        )
+    };
+
+    let outdoor = report.temperature.map(|temperature| Weather {
+        dew_point: report.dew_point,
+        station_pressure: report.station_pressure,
+        sea_level_pressure: report.sea_level_pressure,
+        wind_speed: report.wind_speed,
+        wind_gust_speed: report.wind_gust_speed,
+        wind_direction: report.wind_direction,
+        precipitation: report.precipitation_last_hour.map(|amount| Precipitation {
+            amount,
+            period: Duration::from_secs(60 * 60),
+        }),
+        precipitation_intensity: report.precipitation_intensity,
+        uv_index: report.uv_index,
+        global_radiation: report.global_radiation,
+        ..weather(None, temperature, report.relative_humidity)
+    });
+    let indoor = report.indoor_temperature.map(|temperature| {
+        weather(
+            Some(INDOOR_MODULE.into()),
+            temperature,
+            report.indoor_relative_humidity,
+        )
+    });
+
+    outdoor.into_iter().chain(indoor).collect()
+}
+
+pub(in crate::providers) fn latest_weathers(
+    source: &str,
+    reports: &ReportStore,
+    station: Option<&String>,
+    max_age: Duration,
+    request: &WeatherRequest<Coordinates>,
+) -> anyhow::Result<Vec<Weather>> {
+    let not_before = TimeDelta::from_std(max_age)
+        .ok()
+        .and_then(|age| Utc::now().checked_sub_signed(age))
+        .ok_or_else(|| anyhow!("Invalid maximum age {max_age:?}"))?;
+
+    let report = select_report(
+        station,
+        reports.iter().map(|(_, report)| report),
+        not_before,
+    )?;
+    debug!("Using report of station {} for {source}", report.station);
+
+    let weathers = to_weathers(source, request, &report);
+    if weathers.is_empty() {
+        return Err(anyhow!(
+            "Station {} reported no temperature",
+            report.station
+        ));
+    }
+
+    Ok(weathers)
+}
+
+#[cfg(test)]
+pub(in crate::providers) fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter_map(|field| field.split_once('='))
+        .map(|(key, value)| (key.to_owned(), value.to_owned()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::providers::station_report::FieldNames;
+    use chrono::{DateTime, Utc};
+
+    const FIELD_NAMES: FieldNames = FieldNames {
+        station: &["PASSKEY", "MAC"],
+        indoor_temperature: "tempinf",
+        indoor_relative_humidity: "humidityin",
+        station_pressure: "baromabsin",
+        sea_level_pressure: "baromrelin",
+        precipitation_last_hour: "hourlyrainin",
+        uv_index: "uv",
+    };
+
+    fn received() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2024-06-01T12:55:05Z")
+            .expect("Static value")
+            .with_timezone(&Utc)
+    }
+
+    mod parse {
+        use crate::providers::station_report::tests::{received, FIELD_NAMES};
+        use crate::providers::station_report::{parse_query, Report};
+        use crate::providers::units::{Fahrenheit, ToCelsius};
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn use_any_station_field() {
+            assert_eq!(
+                Report::parse(
+                    &parse_query("MAC=AA:BB&tempf=64.4&dateutc=now"),
+                    &FIELD_NAMES,
+                    received(),
+                )
+                .expect("Report is valid"),
+                Report {
+                    station: "AA:BB".into(),
+                    received: received(),
+                    observation_time: received(),
+                    temperature: Some(Fahrenheit::from(64.4_f32).to_celsius()),
+                    relative_humidity: None,
+                    dew_point: None,
+                    indoor_temperature: None,
+                    indoor_relative_humidity: None,
+                    station_pressure: None,
+                    sea_level_pressure: None,
+                    wind_speed: None,
+                    wind_gust_speed: None,
+                    wind_direction: None,
+                    precipitation_last_hour: None,
+                    precipitation_intensity: None,
+                    global_radiation: None,
+                    uv_index: None,
+                }
+            );
+        }
+
+        #[test]
+        fn reject_reports_without_station() {
+            assert_eq!(
+                Report::parse(&parse_query("tempf=64.4"), &FIELD_NAMES, received())
+                    .expect_err("Station is missing")
+                    .to_string(),
+                "Report does not identify the station"
+            );
+        }
+    }
+
+    mod select_report {
+        use crate::providers::station_report::tests::{received, FIELD_NAMES};
+        use crate::providers::station_report::{parse_query, select_report, Report};
+        use chrono::TimeDelta;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn select_latest_recent_report() {
+            let older = Report::parse(
+                &parse_query("PASSKEY=older&tempf=50"),
+                &FIELD_NAMES,
+                received(),
+            )
+            .expect("Report is valid");
+            let newer = Report::parse(
+                &parse_query("PASSKEY=newer&tempf=60"),
+                &FIELD_NAMES,
+                received() + TimeDelta::seconds(60_i64),
+            )
+            .expect("Report is valid");
+
+            assert_eq!(
+                [
+                    (None, received()),
+                    (Some("older".into()), received()),
+                    (Some("OLDER".into()), received()),
+                    (None, received() + TimeDelta::seconds(120_i64)),
+                ]
+                .map(|(station, not_before)| {
+                    select_report(
+                        station.as_ref(),
+                        vec![older.clone(), newer.clone()].into_iter(),
+                        not_before,
+                    )
+                    .map(|report| report.station)
+                    .map_err(|error| error.to_string())
+                }),
+                [
+                    Ok("newer".into()),
+                    Ok("older".into()),
+                    Ok("older".into()),
+                    Err("No recent report from station ".into()),
+                ]
+            );
+        }
+    }
+}
@@ -0,0 +1,31 @@
+// Helpers shared by the tests of the providers
+use crate::providers::units::Coordinates;
+use crate::providers::{ProviderParameters, WeatherRequest};
+use serde::de::DeserializeOwned;
+use std::fs;
+use std::path::Path;
+
+pub fn request(name: &str, latitude: f64, longitude: f64) -> WeatherRequest<Coordinates> {
+    WeatherRequest {
+        name: name.into(),
+        parameters: ProviderParameters::new(),
+        query: Coordinates {
+            latitude: latitude.into(),
+            longitude: longitude.into(),
+        },
+    }
+}
+
+// Responses as returned by the services, stored below fixtures/
+pub fn fixture(path: &str) -> Vec<u8> {
+    fs::read(
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join(path),
+    )
+    .expect("Fixture exists")
+}
+
+pub fn json_fixture<T: DeserializeOwned>(path: &str) -> T {
+    serde_json::from_slice(&fixture(path)).expect("Fixture is valid")
+}
@@ -1,6 +1,12 @@
 use crate::providers::http_request::{request_cached, Configuration, HttpCacheRequest};
-use crate::providers::units::{Celsius, Coordinates, Ratio};
-use crate::providers::{HttpRequestCache, Weather, WeatherProvider, WeatherRequest};
+use crate::providers::units::{
+    Celsius, Condition, Coordinates, Degrees, Hectopascal, Kilometers, MetersPerSecond,
+    Millimeters, MillimetersPerHour, Ratio, WattsPerSquareMeter,
+};
+use crate::providers::{
+    HttpRequestCache, Precipitation, Weather, WeatherCondition, WeatherProvider, WeatherRequest,
+};
+use chrono::{DateTime, Utc};
 use reqwest::blocking::Client;
 use reqwest::{Method, Url};
 use serde::{Deserialize, Serialize};
@@ -23,13 +29,47 @@ struct TomorrowResponse {
 
 #[derive(Deserialize, Debug)]
 struct TomorrowData {
+    time: DateTime<Utc>,
     values: TomorrowValues,
 }
 
 #[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
 struct TomorrowValues {
     temperature: Celsius,
+    temperature_apparent: Option<Celsius>,
     humidity: Ratio,
+    dew_point: Option<Celsius>,
+    pressure_surface_level: Option<Hectopascal>,
+    pressure_sea_level: Option<Hectopascal>,
+    wind_speed: Option<MetersPerSecond>,
+    wind_gust: Option<MetersPerSecond>,
+    wind_direction: Option<Degrees>,
+    precipitation_intensity: Option<MillimetersPerHour>,
+    snow_accumulation: Option<Millimeters>,
+    cloud_cover: Option<Ratio>,
+    visibility: Option<Kilometers>,
+    uv_index: Option<f64>,
+    #[serde(rename = "solarGHI")]
+    solar_ghi: Option<WattsPerSquareMeter>,
+    weather_code: Option<u16>,
+}
+
+// See https://docs.tomorrow.io/reference/data-layers-weather-codes
+const fn condition_from_code(code: u16) -> Condition {
+    match code {
+        1000 | 1100 => Condition::Clear,
+        1101 => Condition::PartlyCloudy,
+        1001 | 1102 => Condition::Cloudy,
+        2000 | 2100 => Condition::Fog,
+        4000 => Condition::Drizzle,
+        4001 | 4200 | 4201 => Condition::Rain,
+        5000 | 5001 | 5100 | 5101 => Condition::Snow,
+        6000 | 6001 | 6200 | 6201 => Condition::FreezingRain,
+        7000 | 7101 | 7102 => Condition::Sleet,
+        8000 => Condition::Thunderstorm,
+        _ => Condition::Unknown,
+    }
 }
 
 impl WeatherProvider for Tomorrow {
@@ -64,13 +104,42 @@ impl WeatherProvider for Tomorrow {
         ))?;
 
         Ok(Weather {
-            location: request.name.clone(),
-            source: SOURCE_URI.into(),
-            city: None,
-            coordinates: request.query.clone(),
-            distance: None,
-            temperature: response.data.values.temperature,
             relative_humidity: Some(response.data.values.humidity),
+            dew_point: response.data.values.dew_point,
+            apparent_temperature: response.data.values.temperature_apparent,
+            station_pressure: response.data.values.pressure_surface_level,
+            sea_level_pressure: response.data.values.pressure_sea_level,
+            wind_speed: response.data.values.wind_speed,
+            wind_gust_speed: response.data.values.wind_gust,
+            wind_direction: response.data.values.wind_direction,
+            precipitation_intensity: response.data.values.precipitation_intensity,
+            snowfall: response
+                .data
+                .values
+                .snow_accumulation
+                .map(|amount| Precipitation {
+                    amount,
+                    period: Duration::from_secs(60 * 60),
+                }),
+            cloud_cover: response.data.values.cloud_cover,
+            visibility: response.data.values.visibility.map(Kilometers::to_meters),
+            uv_index: response.data.values.uv_index,
+            global_radiation: response.data.values.solar_ghi,
+            condition: response
+                .data
+                .values
+                .weather_code
+                .map(|code| WeatherCondition {
+                    condition: condition_from_code(code),
+                    code: code.to_string(),
+                }),
+            observation_time: Some(response.data.time),
+            ..Weather::new(
+                SOURCE_URI.into(),
+                request.name.clone(),
+                request.query.clone(),
+                response.data.values.temperature,
+            )
         })
     }
 
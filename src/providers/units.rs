@@ -2,10 +2,23 @@ use derive_more::{Display, From, Into};
 use rocket::serde::Serialize;
 use serde::Deserialize;
 use std::fmt::Debug;
+use std::time::Duration;
+
+// Air temperatures are well within f32 range and precision
+#[allow(clippy::as_conversions, clippy::cast_possible_truncation)]
+const fn temperature_from_f64(value: f64) -> f32 {
+    value as f32
+}
 
 #[derive(Deserialize, Debug, Copy, Clone, From, PartialEq)]
 pub struct Kelvin(f32);
 
+impl Kelvin {
+    pub const fn from_f64(value: f64) -> Self {
+        Self(temperature_from_f64(value))
+    }
+}
+
 impl ToCelsius for Kelvin {
     fn to_celsius(&self) -> Celsius {
         Celsius(self.0 + CELSIUS_ABSOLUTE_ZERO)
@@ -18,6 +31,12 @@ pub struct Celsius(f32);
 
 const CELSIUS_ABSOLUTE_ZERO: f32 = -273.15;
 
+impl Celsius {
+    pub const fn from_f64(value: f64) -> Self {
+        Self(temperature_from_f64(value))
+    }
+}
+
 impl ToCelsius for Celsius {
     fn to_celsius(&self) -> Self {
         Self(self.0)
@@ -27,6 +46,12 @@ impl ToCelsius for Celsius {
 #[derive(Deserialize, Debug, Copy, Clone, From, PartialEq)]
 pub struct Fahrenheit(f32);
 
+impl Fahrenheit {
+    pub const fn from_f64(value: f64) -> Self {
+        Self(temperature_from_f64(value))
+    }
+}
+
 pub trait ToCelsius {
     fn to_celsius(&self) -> Celsius;
 }
@@ -60,13 +85,19 @@ impl From<Ratio> for f64 {
 #[display("{_0:.7}")]
 pub struct Coordinate(f64);
 
+impl Coordinate {
+    pub fn to_string_with_precision(&self, decimals: usize) -> String {
+        format!("{:.decimals$}", self.0)
+    }
+}
+
 impl PartialEq for Coordinate {
     fn eq(&self, other: &Self) -> bool {
         (self.0 - other.0).abs() < 0.000_000_1_f64
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Coordinates {
     #[serde(alias = "lat")]
     pub latitude: Coordinate,
@@ -74,12 +105,233 @@ pub struct Coordinates {
     pub longitude: Coordinate,
 }
 
-#[derive(Debug, Clone, From, Into)]
+#[derive(Deserialize, Debug, Clone, From, Into, PartialEq)]
 pub struct Meters(f64);
 
+#[derive(Deserialize, Debug, Copy, Clone, From, PartialEq)]
+pub struct Kilometers(f64);
+
+const METERS_PER_KILOMETER: f64 = 1000.0;
+
+impl Kilometers {
+    pub fn to_meters(self) -> Meters {
+        Meters(self.0 * METERS_PER_KILOMETER)
+    }
+}
+
+#[derive(Deserialize, Debug, Copy, Clone, From, PartialEq)]
+pub struct Miles(f64);
+
+const METERS_PER_MILE: f64 = 1609.344;
+
+impl Miles {
+    pub fn to_meters(self) -> Meters {
+        Meters(self.0 * METERS_PER_MILE)
+    }
+}
+
+#[derive(Deserialize, Debug, Copy, Clone, From, Into, PartialEq)]
+pub struct Hectopascal(f64);
+
+#[derive(Deserialize, Debug, Copy, Clone, From, PartialEq)]
+pub struct Pascals(f64);
+
+impl Pascals {
+    pub fn to_hectopascal(self) -> Hectopascal {
+        Hectopascal(self.0 / PASCALS_PER_HECTOPASCAL)
+    }
+}
+
+const PASCALS_PER_HECTOPASCAL: f64 = 100.0;
+
+#[derive(Deserialize, Debug, Copy, Clone, From, PartialEq)]
+pub struct InchesOfMercury(f64);
+
+const HECTOPASCALS_PER_INCH_OF_MERCURY: f64 = 33.863_886;
+
+impl InchesOfMercury {
+    pub fn to_hectopascal(self) -> Hectopascal {
+        Hectopascal(self.0 * HECTOPASCALS_PER_INCH_OF_MERCURY)
+    }
+}
+
+impl Hectopascal {
+    pub fn to_pascals(self) -> f64 {
+        self.0 * PASCALS_PER_HECTOPASCAL
+    }
+}
+
+#[derive(Deserialize, Debug, Copy, Clone, From, Into, PartialEq)]
+pub struct MetersPerSecond(f64);
+
+impl ToMetersPerSecond for MetersPerSecond {
+    fn to_meters_per_second(&self) -> Self {
+        Self(self.0)
+    }
+}
+
+#[derive(Deserialize, Debug, Copy, Clone, From, PartialEq)]
+pub struct KilometersPerHour(f64);
+
+#[derive(Deserialize, Debug, Copy, Clone, From, PartialEq)]
+pub struct MilesPerHour(f64);
+
+#[derive(Deserialize, Debug, Copy, Clone, From, PartialEq)]
+pub struct Knots(f64);
+
+pub trait ToMetersPerSecond {
+    fn to_meters_per_second(&self) -> MetersPerSecond;
+}
+
+const KILOMETERS_PER_HOUR_PER_METER_PER_SECOND: f64 = 3.6;
+const METERS_PER_SECOND_PER_MILE_PER_HOUR: f64 = 0.447_04;
+const METERS_PER_SECOND_PER_KNOT: f64 = 1852.0 / 3600.0;
+
+impl ToMetersPerSecond for KilometersPerHour {
+    fn to_meters_per_second(&self) -> MetersPerSecond {
+        MetersPerSecond(self.0 / KILOMETERS_PER_HOUR_PER_METER_PER_SECOND)
+    }
+}
+
+impl ToMetersPerSecond for MilesPerHour {
+    fn to_meters_per_second(&self) -> MetersPerSecond {
+        MetersPerSecond(self.0 * METERS_PER_SECOND_PER_MILE_PER_HOUR)
+    }
+}
+
+impl ToMetersPerSecond for Knots {
+    fn to_meters_per_second(&self) -> MetersPerSecond {
+        MetersPerSecond(self.0 * METERS_PER_SECOND_PER_KNOT)
+    }
+}
+
+#[derive(Deserialize, Debug, Copy, Clone, From, Into, PartialEq)]
+pub struct Degrees(f64);
+
+#[derive(Deserialize, Debug, Copy, Clone, From, Into, PartialEq)]
+pub struct Millimeters(f64);
+
+impl Millimeters {
+    pub fn to_meters(self) -> Meters {
+        Meters(self.0 / MILLIMETERS_PER_METER)
+    }
+}
+
+#[derive(Deserialize, Debug, Copy, Clone, From, PartialEq)]
+pub struct Centimeters(f64);
+
+const MILLIMETERS_PER_CENTIMETER: f64 = 10.0;
+const MILLIMETERS_PER_METER: f64 = 1000.0;
+
+impl Centimeters {
+    pub fn to_millimeters(self) -> Millimeters {
+        Millimeters(self.0 * MILLIMETERS_PER_CENTIMETER)
+    }
+
+    pub fn to_meters(self) -> Meters {
+        self.to_millimeters().to_meters()
+    }
+}
+
+#[derive(Deserialize, Debug, Copy, Clone, From, PartialEq)]
+pub struct Inches(f64);
+
+const MILLIMETERS_PER_INCH: f64 = 25.4;
+
+impl Inches {
+    pub fn to_millimeters(self) -> Millimeters {
+        Millimeters(self.0 * MILLIMETERS_PER_INCH)
+    }
+}
+
+#[derive(Deserialize, Debug, Copy, Clone, From, Into, PartialEq)]
+pub struct MillimetersPerHour(f64);
+
+#[derive(Deserialize, Debug, Copy, Clone, From, PartialEq)]
+pub struct InchesPerHour(f64);
+
+impl InchesPerHour {
+    pub fn to_millimeters_per_hour(self) -> MillimetersPerHour {
+        MillimetersPerHour(self.0 * MILLIMETERS_PER_INCH)
+    }
+}
+
+#[derive(Deserialize, Debug, Copy, Clone, From, Into, PartialEq)]
+pub struct WattsPerSquareMeter(f64);
+
+#[derive(Deserialize, Debug, Copy, Clone, From, Into, PartialEq)]
+pub struct PartsPerMillion(f64);
+
+#[derive(Deserialize, Debug, Copy, Clone, From, Into, PartialEq)]
+pub struct Decibels(f64);
+
+#[derive(Deserialize, Debug, Copy, Clone, From, PartialEq)]
+pub struct JoulesPerSquareCentimeter(f64);
+
+const SQUARE_CENTIMETERS_PER_SQUARE_METER: f64 = 10_000.0;
+
+impl JoulesPerSquareCentimeter {
+    // Average power of the energy received over the given period
+    pub fn to_watts_per_square_meter(self, period: Duration) -> WattsPerSquareMeter {
+        WattsPerSquareMeter(self.0 * SQUARE_CENTIMETERS_PER_SQUARE_METER / period.as_secs_f64())
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Display)]
+pub enum Condition {
+    #[display("clear")]
+    Clear,
+    #[display("partly_cloudy")]
+    PartlyCloudy,
+    #[display("cloudy")]
+    Cloudy,
+    #[display("fog")]
+    Fog,
+    #[display("haze")]
+    Haze,
+    #[display("drizzle")]
+    Drizzle,
+    #[display("rain")]
+    Rain,
+    #[display("freezing_rain")]
+    FreezingRain,
+    #[display("sleet")]
+    Sleet,
+    #[display("snow")]
+    Snow,
+    #[display("thunderstorm")]
+    Thunderstorm,
+    #[display("unknown")]
+    Unknown,
+}
+
+impl Condition {
+    // WMO code table 4677 as used by Open-Meteo and others
+    pub const fn from_wmo_code(code: u8) -> Self {
+        match code {
+            0 => Self::Clear,
+            1 | 2 => Self::PartlyCloudy,
+            3 => Self::Cloudy,
+            45 | 48 => Self::Fog,
+            51 | 53 | 55 => Self::Drizzle,
+            56 | 57 | 66 | 67 => Self::FreezingRain,
+            61 | 63 | 65 | 80..=82 => Self::Rain,
+            71 | 73 | 75 | 77 | 85 | 86 => Self::Snow,
+            95 | 96 | 99 => Self::Thunderstorm,
+            _ => Self::Unknown,
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::providers::units::{Celsius, Fahrenheit, Kelvin, ToCelsius};
+    use crate::providers::units::{
+        Celsius, Centimeters, Condition, Coordinate, Fahrenheit, Hectopascal, Inches,
+        InchesOfMercury, InchesPerHour, JoulesPerSquareCentimeter, Kelvin, Kilometers,
+        KilometersPerHour, Knots, Meters, MetersPerSecond, Miles, MilesPerHour, Millimeters,
+        MillimetersPerHour, Pascals, ToCelsius, ToMetersPerSecond, WattsPerSquareMeter,
+    };
+    use std::time::Duration;
 
     #[test]
     fn test_fahrenheit_to_celsius() {
@@ -98,4 +350,119 @@ mod test {
     fn test_celsius_to_celsius() {
         assert_eq!(Celsius(37_f32).to_celsius(), Celsius(37_f32));
     }
+
+    #[test]
+    fn test_pascals_to_hectopascal() {
+        assert_eq!(
+            Pascals(101_325_f64).to_hectopascal(),
+            Hectopascal(1013.25_f64)
+        );
+    }
+
+    #[test]
+    fn test_inches_of_mercury_to_hectopascal() {
+        assert_eq!(
+            InchesOfMercury(1_f64).to_hectopascal(),
+            Hectopascal(33.863_886_f64)
+        );
+    }
+
+    #[test]
+    fn test_inches_to_millimeters() {
+        assert_eq!(
+            Inches(2_f64).to_millimeters(),
+            Millimeters(50.8_f64),
+            "Amount"
+        );
+        assert_eq!(
+            InchesPerHour(0.5_f64).to_millimeters_per_hour(),
+            MillimetersPerHour(12.7_f64),
+            "Intensity"
+        );
+    }
+
+    #[test]
+    fn test_miles_to_meters() {
+        assert_eq!(Miles(1_f64).to_meters(), Meters(1609.344_f64));
+    }
+
+    #[test]
+    fn test_coordinate_with_precision() {
+        assert_eq!(
+            Coordinate(59.913_868_2_f64).to_string_with_precision(4),
+            "59.9139"
+        );
+    }
+
+    #[test]
+    fn test_hectopascal_to_pascals() {
+        assert!((Hectopascal(1013.25_f64).to_pascals() - 101_325_f64).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_condition_from_wmo_code() {
+        assert_eq!(Condition::from_wmo_code(0), Condition::Clear);
+        assert_eq!(Condition::from_wmo_code(48), Condition::Fog);
+        assert_eq!(Condition::from_wmo_code(57), Condition::FreezingRain);
+        assert_eq!(Condition::from_wmo_code(81), Condition::Rain);
+        assert_eq!(Condition::from_wmo_code(86), Condition::Snow);
+        assert_eq!(Condition::from_wmo_code(99), Condition::Thunderstorm);
+        assert_eq!(Condition::from_wmo_code(42), Condition::Unknown);
+    }
+
+    #[test]
+    fn test_condition_display() {
+        assert_eq!(Condition::PartlyCloudy.to_string(), "partly_cloudy");
+    }
+
+    #[test]
+    fn test_centimeters_to_millimeters_and_meters() {
+        assert_eq!(Centimeters(12_f64).to_millimeters(), Millimeters(120_f64));
+        assert_eq!(Centimeters(12_f64).to_meters(), Meters(0.12_f64));
+    }
+
+    #[test]
+    fn test_kilometers_to_meters() {
+        assert_eq!(Kilometers(16.5_f64).to_meters(), Meters(16_500_f64));
+    }
+
+    #[test]
+    fn test_joules_per_square_centimeter_to_watts_per_square_meter() {
+        assert_eq!(
+            JoulesPerSquareCentimeter(3_f64).to_watts_per_square_meter(Duration::from_secs(600)),
+            WattsPerSquareMeter(50_f64)
+        );
+    }
+
+    #[test]
+    fn test_kilometers_per_hour_to_meters_per_second() {
+        assert_eq!(
+            KilometersPerHour(36_f64).to_meters_per_second(),
+            MetersPerSecond(10_f64)
+        );
+    }
+
+    #[test]
+    fn test_miles_per_hour_to_meters_per_second() {
+        assert_eq!(
+            MilesPerHour(10_f64).to_meters_per_second(),
+            MetersPerSecond(4.4704_f64)
+        );
+    }
+
+    #[test]
+    fn test_knots_to_meters_per_second() {
+        assert_eq!(
+            Knots(10_f64).to_meters_per_second(),
+            MetersPerSecond(5.144_444_444_444_445_f64)
+        );
+    }
+
+    #[test]
+    fn test_meters_per_second_to_meters_per_second() {
+        assert_eq!(
+            MetersPerSecond(3.5_f64).to_meters_per_second(),
+            MetersPerSecond(3.5_f64)
+        );
+    }
 }
@@ -0,0 +1,313 @@
+use crate::providers::http_request::{request_cached, Configuration, HttpCacheRequest};
+use crate::providers::units::{
+    Celsius, Coordinates, Degrees, Hectopascal, KilometersPerHour, MillimetersPerHour, Ratio,
+    ToMetersPerSecond, WattsPerSquareMeter,
+};
+use crate::providers::{
+    calculate_distance, HttpRequestCache, Weather, WeatherProvider, WeatherRequest,
+};
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use log::trace;
+use reqwest::blocking::Client;
+use reqwest::{Method, Url};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const SOURCE_URI: &str = "com.wunderground";
+const OBSERVATIONS_URL: &str = "https://api.weather.com/v2/pws/observations/current";
+const NEAR_URL: &str = "https://api.weather.com/v3/location/near";
+
+// Per location parameter selecting a station instead of the closest one
+const STATION_ID_PARAMETER: &str = "station_id";
+const QC_STATUS_FAILED: i8 = -1;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WeatherUnderground {
+    api_key: String,
+    #[serde(flatten)]
+    cache: Configuration,
+}
+
+#[derive(Deserialize, Debug)]
+struct NearbyStationsResponse {
+    location: NearbyStations,
+}
+
+// Stations are ordered by distance, each field holds one value per station
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct NearbyStations {
+    station_id: Vec<String>,
+    qc_status: Vec<i8>,
+}
+
+impl NearbyStations {
+    fn closest_station_id(&self) -> anyhow::Result<&str> {
+        self.station_id
+            .iter()
+            .zip(&self.qc_status)
+            .find(|(_, qc_status)| **qc_status != QC_STATUS_FAILED)
+            .map(|(station_id, _)| station_id.as_str())
+            .ok_or_else(|| anyhow!("No personal weather station nearby"))
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct ObservationsResponse {
+    observations: Vec<Observation>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Observation {
+    #[serde(rename = "stationID")]
+    station_id: String,
+    obs_time_utc: DateTime<Utc>,
+    neighborhood: Option<String>,
+    lat: f64,
+    lon: f64,
+    solar_radiation: Option<WattsPerSquareMeter>,
+    uv: Option<f64>,
+    winddir: Option<Degrees>,
+    humidity: Option<f64>,
+    qc_status: i8,
+    // Present with units=m
+    metric: MetricObservation,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct MetricObservation {
+    temp: Option<Celsius>,
+    dewpt: Option<Celsius>,
+    wind_speed: Option<KilometersPerHour>,
+    wind_gust: Option<KilometersPerHour>,
+    // Reduced to sea level by the station
+    pressure: Option<Hectopascal>,
+    precip_rate: Option<MillimetersPerHour>,
+}
+
+fn to_weather(
+    request: &WeatherRequest<Coordinates>,
+    response: &ObservationsResponse,
+) -> anyhow::Result<Weather> {
+    let observation = response
+        .observations
+        .first()
+        .ok_or_else(|| anyhow!("No current observation"))?;
+
+    if observation.qc_status == QC_STATUS_FAILED {
+        return Err(anyhow!(
+            "Observation of station {} failed quality control",
+            observation.station_id
+        ));
+    }
+
+    let coordinates = Coordinates {
+        latitude: observation.lat.into(),
+        longitude: observation.lon.into(),
+    };
+
+    Ok(Weather {
+        city: observation
+            .neighborhood
+            .clone()
+            .or_else(|| Some(observation.station_id.clone())),
+        distance: Some(calculate_distance(&request.query, &coordinates)),
+        relative_humidity: observation.humidity.map(Ratio::Percentage),
+        dew_point: observation.metric.dewpt,
+        sea_level_pressure: observation.metric.pressure,
+        wind_speed: observation
+            .metric
+            .wind_speed
+            .map(|speed| speed.to_meters_per_second()),
+        wind_gust_speed: observation
+            .metric
+            .wind_gust
+            .map(|speed| speed.to_meters_per_second()),
+        wind_direction: observation.winddir,
+        precipitation_intensity: observation.metric.precip_rate,
+        // The daily total resets at local midnight of the station, not at midnight UTC
+        daily_precipitation: None,
+        uv_index: observation.uv,
+        global_radiation: observation.solar_radiation,
+        observation_time: Some(observation.obs_time_utc),
+        ..Weather::new(
+            SOURCE_URI.into(),
+            request.name.clone(),
+            coordinates,
+            observation.metric.temp.ok_or_else(|| {
+                anyhow!(
+                    "No temperature reported by station {}",
+                    observation.station_id
+                )
+            })?,
+        )
+    })
+}
+
+impl WeatherUnderground {
+    fn find_closest_station_id(
+        &self,
+        client: &Client,
+        cache: &HttpRequestCache,
+        coordinates: &Coordinates,
+    ) -> anyhow::Result<String> {
+        let url = Url::parse_with_params(
+            NEAR_URL,
+            &[
+                (
+                    "geocode",
+                    format!("{},{}", coordinates.latitude, coordinates.longitude),
+                ),
+                ("product", "pws".into()),
+                ("format", "json".into()),
+                ("apiKey", self.api_key.clone()),
+            ],
+        )?;
+
+        let response: NearbyStationsResponse = request_cached(
+            &HttpCacheRequest::new_json_request(SOURCE_URI, client, cache, &Method::GET, &url),
+        )?;
+
+        Ok(response.location.closest_station_id()?.to_owned())
+    }
+}
+
+impl WeatherProvider for WeatherUnderground {
+    fn id(&self) -> &str {
+        SOURCE_URI
+    }
+
+    fn for_coordinates(
+        &self,
+        client: &Client,
+        cache: &HttpRequestCache,
+        request: &WeatherRequest<Coordinates>,
+    ) -> anyhow::Result<Weather> {
+        let station_id = match request.parameters.get(STATION_ID_PARAMETER) {
+            Some(station_id) => station_id.clone(),
+            None => self.find_closest_station_id(client, cache, &request.query)?,
+        };
+        trace!("Using personal weather station {station_id}");
+
+        let url = Url::parse_with_params(
+            OBSERVATIONS_URL,
+            &[
+                ("stationId", station_id.as_str()),
+                ("format", "json"),
+                ("units", "m"),
+                ("numericPrecision", "decimal"),
+                ("apiKey", self.api_key.as_str()),
+            ],
+        )?;
+
+        let response: ObservationsResponse = request_cached(&HttpCacheRequest::new_json_request(
+            SOURCE_URI,
+            client,
+            cache,
+            &Method::GET,
+            &url,
+        ))?;
+
+        to_weather(request, &response)
+    }
+
+    fn refresh_interval(&self) -> Duration {
+        self.cache.refresh_interval
+    }
+
+    // Nearby stations and observation
+    fn cache_cardinality(&self) -> usize {
+        2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod closest_station_id {
+        use crate::providers::testing::json_fixture;
+        use crate::providers::weather_underground::NearbyStationsResponse;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn skip_stations_failing_quality_control() {
+            let response: NearbyStationsResponse = json_fixture("weather_underground/near.json");
+
+            assert_eq!(
+                response
+                    .location
+                    .closest_station_id()
+                    .expect("Station found"),
+                "IBERLIN1234"
+            );
+        }
+    }
+
+    mod to_weather {
+        use crate::providers::testing::{json_fixture, request};
+        use crate::providers::units::{Coordinates, Ratio};
+        use crate::providers::weather_underground::to_weather;
+        use crate::providers::Weather;
+        use chrono::{DateTime, Utc};
+        use pretty_assertions::assert_eq;
+        use serde_json::Value;
+
+        #[test]
+        fn map_current_observation() {
+            assert_eq!(
+                to_weather(
+                    &request("Berlin", 52.508_963_f64, 13.435_965_f64),
+                    &json_fixture("weather_underground/current.json"),
+                )
+                .expect("Mapping works"),
+                Weather {
+                    city: Some("Kreuzberg".into()),
+                    distance: Some(1_210.592_114_823_805_6_f64.into()),
+                    relative_humidity: Some(Ratio::Percentage(66_f64)),
+                    dew_point: Some(14.8_f32.into()),
+                    sea_level_pressure: Some(1013.21_f64.into()),
+                    wind_speed: Some(5_f64.into()),
+                    wind_gust_speed: Some(7.5_f64.into()),
+                    wind_direction: Some(250_f64.into()),
+                    precipitation_intensity: Some(0.6_f64.into()),
+                    uv_index: Some(4_f64),
+                    global_radiation: Some(436.5_f64.into()),
+                    observation_time: Some(
+                        DateTime::parse_from_rfc3339("2024-06-01T12:55:02Z")
+                            .expect("Static value")
+                            .with_timezone(&Utc)
+                    ),
+                    ..Weather::new(
+                        "com.wunderground".into(),
+                        "Berlin".into(),
+                        Coordinates {
+                            latitude: 52.503_f64.into(),
+                            longitude: 13.421_f64.into(),
+                        },
+                        21.4_f32.into(),
+                    )
+                }
+            );
+        }
+
+        #[test]
+        fn reject_observation_failing_quality_control() {
+            let mut response: Value = json_fixture("weather_underground/current.json");
+            *response
+                .pointer_mut("/observations/0/qcStatus")
+                .expect("Fixture has a status") = (-1_i8).into();
+
+            assert_eq!(
+                to_weather(
+                    &request("Berlin", 52.508_963_f64, 13.435_965_f64),
+                    &serde_json::from_value(response).expect("Response is valid"),
+                )
+                .expect_err("Quality control failed")
+                .to_string(),
+                "Observation of station IBERLIN1234 failed quality control"
+            );
+        }
+    }
+}
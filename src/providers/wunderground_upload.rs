@@ -0,0 +1,269 @@
+use crate::authentication::{maybe_authenticate, CredentialsStore, Denied};
+use crate::providers::station_report::{
+    latest_weathers, new_report_store, FieldNames, Report, ReportStore,
+};
+use crate::providers::units::Coordinates;
+use crate::providers::{HttpRequestCache, Weather, WeatherProvider, WeatherRequest};
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use log::trace;
+use once_cell::sync::Lazy;
+use reqwest::blocking::Client;
+use rocket_basicauth::BasicAuth;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+const SOURCE_URI: &str = "local.wunderground";
+
+const STATION_FIELD: &str = "ID";
+const PASSWORD_FIELD: &str = "PASSWORD";
+const FIELD_NAMES: FieldNames = FieldNames {
+    station: &[STATION_FIELD],
+    indoor_temperature: "indoortempf",
+    indoor_relative_humidity: "indoorhumidity",
+    station_pressure: "absbaromin",
+    sea_level_pressure: "baromin",
+    precipitation_last_hour: "rainin",
+    uv_index: "UV",
+};
+
+// Per location parameter mapping an uploading station to the location
+const STATION_ID_PARAMETER: &str = "station_id";
+
+static REPORTS: Lazy<ReportStore> = Lazy::new(new_report_store);
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WundergroundUpload {
+    // Station IDs with the bcrypt hash of their upload password
+    stations: CredentialsStore,
+    // Reports older than this are discarded, e.g. after the station went offline
+    #[serde(default = "default_max_age")]
+    #[serde(with = "humantime_serde")]
+    max_age: Duration,
+}
+
+const fn default_max_age() -> Duration {
+    Duration::from_secs(60 * 10)
+}
+
+impl WundergroundUpload {
+    pub fn authenticate(&self, fields: &HashMap<String, String>) -> Result<(), Denied> {
+        let credentials = BasicAuth {
+            username: fields.get(STATION_FIELD).cloned().unwrap_or_default(),
+            password: fields.get(PASSWORD_FIELD).cloned().unwrap_or_default(),
+        };
+
+        maybe_authenticate(Some(&self.stations), Some(&credentials)).map(|_| ())
+    }
+}
+
+// Stores the latest report of an authenticated station
+pub fn receive(fields: &HashMap<String, String>, received: DateTime<Utc>) -> anyhow::Result<()> {
+    let report = Report::parse(fields, &FIELD_NAMES, received)?;
+    trace!("Received upload of station {}", report.station);
+
+    REPORTS.insert(report.station.clone(), report);
+
+    Ok(())
+}
+
+impl WeatherProvider for WundergroundUpload {
+    fn id(&self) -> &str {
+        SOURCE_URI
+    }
+
+    fn for_coordinates(
+        &self,
+        client: &Client,
+        cache: &HttpRequestCache,
+        request: &WeatherRequest<Coordinates>,
+    ) -> anyhow::Result<Weather> {
+        self.all_for_coordinates(client, cache, request)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("No station uploads for location {}", request.name))
+    }
+
+    // Locations without a station mapped to them have no weather from this provider
+    fn all_for_coordinates(
+        &self,
+        _client: &Client,
+        _cache: &HttpRequestCache,
+        request: &WeatherRequest<Coordinates>,
+    ) -> anyhow::Result<Vec<Weather>> {
+        let Some(station_id) = request.parameters.get(STATION_ID_PARAMETER) else {
+            trace!("No uploading station mapped to location {}", request.name);
+            return Ok(vec![]);
+        };
+
+        latest_weathers(
+            SOURCE_URI,
+            &REPORTS,
+            Some(station_id),
+            self.max_age,
+            request,
+        )
+    }
+
+    fn refresh_interval(&self) -> Duration {
+        self.max_age
+    }
+
+    // Reports are pushed by the station, nothing is requested
+    fn cache_cardinality(&self) -> usize {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Utc};
+
+    // As sent by weewx, the password is "secret"
+    const UPLOAD: &str = "ID=IBERLIN1234&PASSWORD=secret&action=updateraw&dateutc=2024-06-01+12:55:02&tempf=64.4&humidity=66&dewptf=53.0&baromin=29.921&windspeedmph=10.0&windgustmph=15.0&winddir=250&rainin=0.05&dailyrainin=0.12&solarradiation=436.5&UV=4&indoortempf=71.6&indoorhumidity=48&softwaretype=weewx-5.0.2";
+
+    fn received() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2024-06-01T12:55:05Z")
+            .expect("Static value")
+            .with_timezone(&Utc)
+    }
+
+    fn observation_time() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2024-06-01T12:55:02Z")
+            .expect("Static value")
+            .with_timezone(&Utc)
+    }
+
+    mod authenticate {
+        use crate::authentication::{CredentialsStore, Denied, Hash};
+        use crate::providers::station_report::parse_query;
+        use crate::providers::wunderground_upload::tests::UPLOAD;
+        use crate::providers::wunderground_upload::WundergroundUpload;
+        use pretty_assertions::assert_eq;
+        use std::time::Duration;
+
+        #[test]
+        fn check_station_password() {
+            let provider = WundergroundUpload {
+                stations: CredentialsStore::from([(
+                    "IBERLIN1234".into(),
+                    Hash::from(
+                        "$2b$04$saLjU4JU8zXK2wW19caZnOAur1bS2N65gOmqlO0cpgkerKLahKdha".to_owned(),
+                    ),
+                )]),
+                max_age: Duration::from_secs(600),
+            };
+
+            assert_eq!(
+                [
+                    UPLOAD,
+                    "ID=IBERLIN1234&PASSWORD=wrong",
+                    "ID=IHAMBURG1&PASSWORD=secret",
+                ]
+                .map(|upload| provider.authenticate(&parse_query(upload))),
+                [Ok(()), Err(Denied::Forbidden), Err(Denied::Forbidden)]
+            );
+        }
+    }
+
+    mod parse {
+        use crate::providers::station_report::{parse_query, Report};
+        use crate::providers::units::{
+            Fahrenheit, Inches, InchesOfMercury, MilesPerHour, ToCelsius, ToMetersPerSecond,
+        };
+        use crate::providers::wunderground_upload::tests::{observation_time, received, UPLOAD};
+        use crate::providers::wunderground_upload::FIELD_NAMES;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn convert_upload() {
+            assert_eq!(
+                Report::parse(&parse_query(UPLOAD), &FIELD_NAMES, received())
+                    .expect("Upload is valid"),
+                Report {
+                    station: "IBERLIN1234".into(),
+                    received: received(),
+                    observation_time: observation_time(),
+                    temperature: Some(Fahrenheit::from(64.4_f32).to_celsius()),
+                    relative_humidity: Some(66_f64),
+                    dew_point: Some(Fahrenheit::from(53_f32).to_celsius()),
+                    indoor_temperature: Some(Fahrenheit::from(71.6_f32).to_celsius()),
+                    indoor_relative_humidity: Some(48_f64),
+                    station_pressure: None,
+                    sea_level_pressure: Some(InchesOfMercury::from(29.921_f64).to_hectopascal()),
+                    wind_speed: Some(MilesPerHour::from(10_f64).to_meters_per_second()),
+                    wind_gust_speed: Some(MilesPerHour::from(15_f64).to_meters_per_second()),
+                    wind_direction: Some(250_f64.into()),
+                    precipitation_last_hour: Some(Inches::from(0.05_f64).to_millimeters()),
+                    precipitation_intensity: None,
+                    global_radiation: Some(436.5_f64.into()),
+                    uv_index: Some(4_f64),
+                }
+            );
+        }
+    }
+
+    mod to_weathers {
+        use crate::providers::station_report::{parse_query, to_weathers, Report};
+        use crate::providers::testing::request;
+        use crate::providers::units::{
+            Fahrenheit, InchesOfMercury, MilesPerHour, Ratio, ToCelsius, ToMetersPerSecond,
+        };
+        use crate::providers::wunderground_upload::tests::{observation_time, received, UPLOAD};
+        use crate::providers::wunderground_upload::{FIELD_NAMES, SOURCE_URI};
+        use crate::providers::{Precipitation, Weather};
+        use pretty_assertions::assert_eq;
+        use std::time::Duration;
+
+        #[test]
+        fn report_indoor_sensors_as_module() {
+            let request = request("Berlin", 52.520_008_f64, 13.404_954_f64);
+
+            assert_eq!(
+                to_weathers(
+                    SOURCE_URI,
+                    &request,
+                    &Report::parse(&parse_query(UPLOAD), &FIELD_NAMES, received())
+                        .expect("Upload is valid"),
+                ),
+                [
+                    Weather {
+                        relative_humidity: Some(Ratio::Percentage(66_f64)),
+                        dew_point: Some(Fahrenheit::from(53_f32).to_celsius()),
+                        sea_level_pressure: Some(
+                            InchesOfMercury::from(29.921_f64).to_hectopascal()
+                        ),
+                        wind_speed: Some(MilesPerHour::from(10_f64).to_meters_per_second()),
+                        wind_gust_speed: Some(MilesPerHour::from(15_f64).to_meters_per_second()),
+                        wind_direction: Some(250_f64.into()),
+                        precipitation: Some(Precipitation {
+                            amount: 1.27_f64.into(),
+                            period: Duration::from_secs(3600),
+                        }),
+                        uv_index: Some(4_f64),
+                        global_radiation: Some(436.5_f64.into()),
+                        observation_time: Some(observation_time()),
+                        ..Weather::new(
+                            "local.wunderground".into(),
+                            "Berlin".into(),
+                            request.query.clone(),
+                            Fahrenheit::from(64.4_f32).to_celsius(),
+                        )
+                    },
+                    Weather {
+                        relative_humidity: Some(Ratio::Percentage(48_f64)),
+                        observation_time: Some(observation_time()),
+                        module: Some("Indoor".into()),
+                        ..Weather::new(
+                            "local.wunderground".into(),
+                            "Berlin".into(),
+                            request.query.clone(),
+                            Fahrenheit::from(71.6_f32).to_celsius(),
+                        )
+                    },
+                ]
+            );
+        }
+    }
+}